@@ -0,0 +1,67 @@
+//! Compares the owned `Ini` parser against the borrowed `IniRef` view on a large generated file.
+//! Run with `cargo bench --bench parse`.
+use configparser::ini::{Ini, IniRef};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 10;
+
+fn generate(sections: usize, keys: usize) -> String {
+    let mut out = String::new();
+    for section in 0..sections {
+        out.push_str(&format!("[Section{}]\n", section));
+        for key in 0..keys {
+            out.push_str(&format!(
+                "key_{} = some moderately long value number {} ; with a comment\n",
+                key, key
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn bench(name: &str, input_len: Option<usize>, mut run: impl FnMut()) {
+    let mut best = Duration::MAX;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed());
+    }
+    print!("{:<24} best of {}: {:>10.2?}", name, ITERATIONS, best);
+    if let Some(input_len) = input_len {
+        let throughput = input_len as f64 / best.as_secs_f64() / (1024.0 * 1024.0);
+        print!(" ({:.1} MiB/s)", throughput);
+    }
+    println!();
+}
+
+fn main() {
+    let input = generate(1_000, 100);
+    println!(
+        "input size: {:.1} MiB",
+        input.len() as f64 / (1024.0 * 1024.0)
+    );
+
+    bench("Ini::read", Some(input.len()), || {
+        let mut config = Ini::new();
+        black_box(config.read(black_box(input.clone())).unwrap());
+    });
+    bench("IniRef::parse", Some(input.len()), || {
+        black_box(IniRef::parse(black_box(&input)).unwrap());
+    });
+
+    let mut config = Ini::new();
+    config.read(input.clone()).unwrap();
+    let view = IniRef::parse(&input).unwrap();
+    bench("Ini::get x100k", None, || {
+        for i in 0..100_000 {
+            black_box(config.get("Section500", &format!("key_{}", i % 100)));
+        }
+    });
+    bench("IniRef::get x100k", None, || {
+        for i in 0..100_000 {
+            black_box(view.get("Section500", &format!("key_{}", i % 100)));
+        }
+    });
+}
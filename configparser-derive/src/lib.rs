@@ -0,0 +1,282 @@
+/*!
+This crate provides `#[derive(FromIni)]` for the [`configparser`](https://docs.rs/configparser) crate. Enable the
+`derive` feature of `configparser` and use it as `configparser::typed::FromIni` instead of depending on this crate
+directly; the attributes it accepts are documented on the `FromIni` trait.
+*/
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    Data, DeriveInput, Fields, GenericArgument, LitStr, Path, PathArguments, Type,
+    parse_macro_input,
+};
+
+///Derives `configparser::typed::FromIni` for a struct with named fields.
+#[proc_macro_derive(FromIni, attributes(ini))]
+pub fn derive_from_ini(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+///Where a missing key falls back to.
+enum Cascade {
+    None,
+    DefaultSection,
+    Section(LitStr),
+}
+
+impl Cascade {
+    fn parse(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Cascade> {
+        if meta.input.peek(syn::Token![=]) {
+            Ok(Cascade::Section(meta.value()?.parse()?))
+        } else {
+            Ok(Cascade::DefaultSection)
+        }
+    }
+}
+
+///The options of the `#[ini(...)]` attribute on the struct.
+struct StructOptions {
+    section: Option<LitStr>,
+    cascade: Cascade,
+}
+
+///The options of the `#[ini(...)]` attribute on a field.
+struct FieldOptions {
+    rename: Option<LitStr>,
+    section: Option<LitStr>,
+    default: Option<Option<Path>>,
+    cascade: Cascade,
+    with: Option<Path>,
+    flatten: bool,
+    skip: bool,
+}
+
+fn struct_options(input: &DeriveInput) -> syn::Result<StructOptions> {
+    let mut options = StructOptions {
+        section: None,
+        cascade: Cascade::None,
+    };
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ini"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("section") {
+                options.section = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("cascade") {
+                options.cascade = Cascade::parse(&meta)?;
+            } else {
+                return Err(
+                    meta.error("unsupported ini attribute, expected `section` or `cascade`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions {
+        rename: None,
+        section: None,
+        default: None,
+        cascade: Cascade::None,
+        with: None,
+        flatten: false,
+        skip: false,
+    };
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ini"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                options.rename = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("section") {
+                options.section = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("default") {
+                options.default = Some(if meta.input.peek(syn::Token![=]) {
+                    Some(meta.value()?.parse::<LitStr>()?.parse()?)
+                } else {
+                    None
+                });
+            } else if meta.path.is_ident("cascade") {
+                options.cascade = Cascade::parse(&meta)?;
+            } else if meta.path.is_ident("with") {
+                options.with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("flatten") {
+                options.flatten = true;
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else {
+                return Err(meta.error(
+                    "unsupported ini attribute, expected `rename`, `section`, `default`, `cascade`, `with`, `flatten` or `skip`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+///Returns the `T` of an `Option<T>` type.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "FromIni can only be derived for structs with named fields",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(syn::Error::new(
+            data.fields.span(),
+            "FromIni can only be derived for structs with named fields",
+        ));
+    };
+    let options = struct_options(&input)?;
+
+    let mut reads = Vec::new();
+    let mut idents = Vec::new();
+    let mut vars = Vec::new();
+    let mut uses_default_section = false;
+    for field in named.named.iter() {
+        let field_options = field_options(field)?;
+        let ident = field.ident.as_ref().expect("named fields have identifiers");
+        let var = format_ident!("__field_{}", ident);
+        let ty = &field.ty;
+        let key = match &field_options.rename {
+            Some(rename) => rename.value(),
+            None => ident.to_string().trim_start_matches("r#").to_owned(),
+        };
+        let section = match &field_options.section {
+            Some(section) => quote!(#section),
+            None => quote!(__section),
+        };
+        let cascade = match &field_options.cascade {
+            Cascade::None => &options.cascade,
+            cascade => cascade,
+        };
+        let fallback = match cascade {
+            Cascade::None => quote!(::core::option::Option::None),
+            Cascade::DefaultSection => {
+                uses_default_section = true;
+                quote!(::core::option::Option::Some(__default_section.as_str()))
+            }
+            Cascade::Section(section) => quote!(::core::option::Option::Some(#section)),
+        };
+
+        let read = if field_options.skip {
+            quote!(::core::option::Option::Some(
+                ::core::default::Default::default()
+            ))
+        } else if field_options.flatten {
+            quote!(__fields.flatten::<#ty>(#section))
+        } else {
+            let inner = option_inner(ty);
+            let get = match &field_options.with {
+                Some(with) => quote!(__fields.get_with(#section, #key, #fallback, #with)),
+                None => {
+                    let value_ty = inner.unwrap_or(ty);
+                    quote!(__fields.get::<#value_ty>(#section, #key, #fallback))
+                }
+            };
+            match (inner, &field_options.default) {
+                (Some(_), Some(_)) => {
+                    return Err(syn::Error::new(
+                        ty.span(),
+                        "`default` has no effect on optional fields",
+                    ));
+                }
+                (Some(_), None) => get,
+                (None, default) => {
+                    let missing = match default {
+                        Some(Some(path)) => quote!(::core::option::Option::Some(#path())),
+                        Some(None) => {
+                            quote!(::core::option::Option::Some(
+                                ::core::default::Default::default()
+                            ))
+                        }
+                        None => quote!(__fields.missing(#section, #key)),
+                    };
+                    quote! {
+                        match #get {
+                            ::core::option::Option::Some(::core::option::Option::Some(value)) => {
+                                ::core::option::Option::Some(value)
+                            }
+                            ::core::option::Option::Some(::core::option::Option::None) => #missing,
+                            ::core::option::Option::None => ::core::option::Option::None,
+                        }
+                    }
+                }
+            }
+        };
+        reads.push(quote!(let #var: ::core::option::Option<#ty> = #read;));
+        idents.push(ident);
+        vars.push(var);
+    }
+
+    let default_section = if uses_default_section {
+        quote!(let __default_section = __config.defaults().default_section;)
+    } else {
+        quote!()
+    };
+    let from_ini = options.section.map(|section| {
+        quote! {
+            fn from_ini(
+                __config: &::configparser::ini::Ini,
+            ) -> ::core::result::Result<Self, ::std::vec::Vec<::configparser::schema::Violation>> {
+                Self::from_ini_section(__config, #section)
+            }
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::configparser::typed::FromIni for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn from_ini_section(
+                __config: &::configparser::ini::Ini,
+                __section: &str,
+            ) -> ::core::result::Result<Self, ::std::vec::Vec<::configparser::schema::Violation>> {
+                #[allow(unused_mut)]
+                let mut __fields = ::configparser::typed::Fields::new(__config);
+                #default_section
+                #(#reads)*
+                match (#(#vars,)*) {
+                    (#(::core::option::Option::Some(#vars),)*) => {
+                        ::core::result::Result::Ok(#name { #(#idents: #vars,)* })
+                    }
+                    #[allow(unreachable_patterns)]
+                    _ => ::core::result::Result::Err(__fields.finish()),
+                }
+            }
+
+            #from_ini
+        }
+    })
+}
@@ -0,0 +1,123 @@
+//!Command-line plumbing shared by the binaries: parsing options, exit codes and file access.
+//!Each binary uses a subset of it.
+#![allow(dead_code)]
+
+use configparser::dialect::Dialect;
+use configparser::ini::{CasePolicy, IniDefault};
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::ExitCode;
+
+///The help text of the options read by `parse_option()`.
+pub const PARSE_OPTIONS: &str = "  --dialect <NAME>                  Start from the options of python, php, windows, git, systemd,
+                                    mysql, samba or desktop files (later options override them)
+  --comment-symbols <CHARS>         Characters starting a comment (default: ;#)
+  --inline-comment-symbols <CHARS>  Characters starting an inline comment (default: the comment symbols)
+  --no-inline-comments              Don't strip inline comments from values
+  --delimiters <CHARS>              Characters separating keys from values (default: =:)
+  --default-section <NAME>          Name of the default section (default: default)
+  --case-sensitive                  Match sections and keys case-sensitively
+  --case-preserving                 Match case-insensitively but keep the spelling of names
+  --multiline                       Allow values to continue on indented lines
+";
+
+///The ways a binary can fail, each with its own exit code.
+pub enum Failure {
+    ///Exit code 1: the command worked, but its check failed (a missing key, an unformatted file, ...).
+    Check(String),
+    ///Exit code 2: invalid usage.
+    Usage(String),
+    ///Exit code 3: a file couldn't be parsed or the requested change couldn't be made.
+    Parse(String),
+    ///Exit code 4: a file couldn't be read or written.
+    Io(String),
+}
+
+///Reports the result of a binary on standard error and turns it into its exit code.
+pub fn exit(name: &str, usage: &str, result: Result<(), Failure>) -> ExitCode {
+    let (code, why) = match result {
+        Ok(()) => return ExitCode::SUCCESS,
+        Err(Failure::Check(why)) => (1, why),
+        Err(Failure::Usage(why)) => (2, format!("{}\n\n{}", why, usage)),
+        Err(Failure::Parse(why)) => (3, why),
+        Err(Failure::Io(why)) => (4, why),
+    };
+    if !why.is_empty() {
+        eprintln!("{}: {}", name, why);
+    }
+    ExitCode::from(code)
+}
+
+///Returns the value of an option from the remaining arguments.
+pub fn value(name: &str, args: &mut impl Iterator<Item = String>) -> Result<String, Failure> {
+    args.next()
+        .ok_or_else(|| Failure::Usage(format!("{} needs a value", name)))
+}
+
+///Applies one of the options in `PARSE_OPTIONS` to the defaults.
+///Returns `Ok(false)` if `arg` isn't one of them.
+pub fn parse_option(
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+    defaults: &mut IniDefault,
+) -> Result<bool, Failure> {
+    match arg {
+        "--dialect" => {
+            *defaults = value(arg, args)?
+                .parse::<Dialect>()
+                .map_err(Failure::Usage)?
+                .defaults()
+        }
+        "--comment-symbols" => defaults.comment_symbols = value(arg, args)?.chars().collect(),
+        "--inline-comment-symbols" => {
+            defaults.inline_comment_symbols = Some(value(arg, args)?.chars().collect())
+        }
+        "--no-inline-comments" => defaults.enable_inline_comments = false,
+        "--delimiters" => {
+            defaults.delimiters = value(arg, args)?.chars().collect();
+            if defaults.delimiters.is_empty() {
+                return Err(Failure::Usage(String::from("--delimiters can't be empty")));
+            }
+        }
+        "--default-section" => defaults.default_section = value(arg, args)?,
+        "--case-sensitive" => defaults.case_sensitive = true,
+        "--case-preserving" => {
+            defaults.section_case = CasePolicy::Preserve;
+            defaults.key_case = CasePolicy::Preserve;
+        }
+        "--multiline" => defaults.multiline = true,
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+///Reads a file, or standard input for `-`.
+pub fn read(file: &str) -> Result<String, Failure> {
+    let mut text = String::new();
+    let result = if file == "-" {
+        io::stdin().read_to_string(&mut text).map(|_| ())
+    } else {
+        fs::read_to_string(file).map(|read| text = read)
+    };
+    result.map_err(|why| Failure::Io(format!("couldn't read {}: {}", file, why)))?;
+    Ok(text)
+}
+
+///Replaces a file atomically, so readers never see a partial file.
+pub fn write(file: &str, text: &str) -> Result<(), Failure> {
+    let path = Path::new(file);
+    let temporary = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        path.file_name()
+            .map_or_else(Default::default, |name| name.to_string_lossy()),
+        std::process::id()
+    ));
+    fs::write(&temporary, text)
+        .and_then(|()| fs::rename(&temporary, path))
+        .map_err(|why| {
+            let _ = fs::remove_file(&temporary);
+            Failure::Io(format!("couldn't write {}: {}", file, why))
+        })
+}
@@ -0,0 +1,175 @@
+//!A formatter for ini-syntax files, built on `configparser`. It rewrites files with the layout of `WriteOptions`
+//!while keeping their comments, see `configparser::edit::Document::format()`.
+mod common;
+
+use common::{Failure, parse_option, read, value};
+use configparser::edit::Document;
+use configparser::ini::{CasePolicy, IniDefault, WriteOptions};
+
+use std::env;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: configparser-fmt [OPTIONS] [FILES]...
+
+Reformats ini-syntax files in place, keeping their comments. Without files, standard input is formatted to standard
+output. Names keep their spelling unless --case-sensitive is given or the case policy is changed.
+
+Options:
+  --check                           Don't write anything, exit with 1 if any file isn't formatted
+  --space-around-delimiters         Write `key = value` instead of `key=value`
+  --delimiter <CHAR>                Delimiter written between keys and values (default: =)
+  --align-delimiters                Align the delimiters of all keys in a section
+  --indent <N>                      Indentation of multiline continuation lines (default: 4)
+  --blank-lines <N>                 Blank lines between sections (default: 0)
+  --wrap-width <N>                  Wrap multiline values at this width
+  --default-section-header          Write the default section with a header
+  --sort                            Sort sections and keys by name instead of keeping their order
+  -h, --help                        Print this help
+{PARSE_OPTIONS}
+Exit codes:
+  0  success
+  1  --check found files that aren't formatted
+  2  invalid usage
+  3  a file couldn't be parsed
+  4  a file couldn't be read or written
+";
+
+fn main() -> ExitCode {
+    let usage = USAGE.replace("{PARSE_OPTIONS}", common::PARSE_OPTIONS);
+    let result = run(env::args().skip(1).collect(), &usage);
+    common::exit("configparser-fmt", &usage, result)
+}
+
+///Private function that parses a numeric option.
+fn number(name: &str, args: &mut impl Iterator<Item = String>) -> Result<usize, Failure> {
+    let number = value(name, args)?;
+    number
+        .parse()
+        .map_err(|_| Failure::Usage(format!("{} needs a number, found {}", name, number)))
+}
+
+fn run(args: Vec<String>, usage: &str) -> Result<(), Failure> {
+    let mut defaults = IniDefault::default();
+    defaults.section_case = CasePolicy::Preserve;
+    defaults.key_case = CasePolicy::Preserve;
+    let mut write_options = WriteOptions::new();
+    let mut check = false;
+    let mut sort = false;
+    let mut files = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", usage);
+                return Ok(());
+            }
+            "--check" => check = true,
+            "--space-around-delimiters" => write_options.space_around_delimiters = true,
+            "--delimiter" => {
+                let delimiter = value(&arg, &mut args)?;
+                let mut chars = delimiter.chars();
+                write_options.delimiter = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => {
+                        return Err(Failure::Usage(String::from(
+                            "--delimiter needs a single character",
+                        )));
+                    }
+                };
+            }
+            "--align-delimiters" => write_options.align_delimiters = true,
+            "--indent" => write_options.multiline_line_indentation = number(&arg, &mut args)?,
+            "--blank-lines" => {
+                write_options.blank_lines_between_sections = number(&arg, &mut args)?
+            }
+            "--wrap-width" => write_options.wrap_width = Some(number(&arg, &mut args)?),
+            "--default-section-header" => write_options.write_default_section_header = true,
+            "--sort" => sort = true,
+            "--" => files.extend(args.by_ref()),
+            _ if parse_option(&arg, &mut args, &mut defaults)? => {}
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {}", arg)));
+            }
+            _ => files.push(arg),
+        }
+    }
+    if !defaults.delimiters.contains(&write_options.delimiter) {
+        return Err(Failure::Usage(format!(
+            "the delimiter {} must be one of the parsed delimiters",
+            write_options.delimiter
+        )));
+    }
+
+    if files.is_empty() {
+        let formatted = format(&defaults, &write_options, sort, "-")?;
+        return match formatted {
+            (text, _) if !check => io::stdout()
+                .write_all(text.as_bytes())
+                .map_err(|why| Failure::Io(why.to_string())),
+            (_, true) => Ok(()),
+            (_, false) => Err(Failure::Check(String::from(
+                "standard input isn't formatted",
+            ))),
+        };
+    }
+
+    // Every file is processed, and the most severe failure decides the exit code.
+    let mut worst: Option<Failure> = None;
+    for file in files.iter() {
+        let result = format(&defaults, &write_options, sort, file).and_then(|(text, unchanged)| {
+            match (unchanged, check) {
+                (true, _) => Ok(()),
+                (false, true) => Err(Failure::Check(format!("{} isn't formatted", file))),
+                (false, false) => common::write(file, &text),
+            }
+        });
+        if let Err(failure) = result {
+            let (Failure::Check(why)
+            | Failure::Usage(why)
+            | Failure::Parse(why)
+            | Failure::Io(why)) = &failure;
+            eprintln!("configparser-fmt: {}", why);
+            if worst
+                .as_ref()
+                .is_none_or(|worst| severity(worst) < severity(&failure))
+            {
+                worst = Some(failure);
+            }
+        }
+    }
+    match worst {
+        // The failures were already reported.
+        Some(Failure::Check(_)) => Err(Failure::Check(String::new())),
+        Some(Failure::Parse(_)) => Err(Failure::Parse(String::new())),
+        Some(_) => Err(Failure::Io(String::new())),
+        None => Ok(()),
+    }
+}
+
+///Private function that ranks failures by their exit code.
+fn severity(failure: &Failure) -> u8 {
+    match failure {
+        Failure::Check(_) => 1,
+        Failure::Usage(_) => 2,
+        Failure::Parse(_) => 3,
+        Failure::Io(_) => 4,
+    }
+}
+
+///Private function that formats a file, or standard input for `-`.
+///Returns the formatted text and whether it is the same as before.
+fn format(
+    defaults: &IniDefault,
+    write_options: &WriteOptions,
+    sort: bool,
+    file: &str,
+) -> Result<(String, bool), Failure> {
+    let text = read(file)?;
+    let parse_error = |why| Failure::Parse(format!("couldn't parse {}: {}", file, why));
+    let mut document = Document::new(text.clone(), defaults.clone()).map_err(parse_error)?;
+    document.format(write_options, sort).map_err(parse_error)?;
+    let unchanged = document.text() == text;
+    Ok((document.text().to_owned(), unchanged))
+}
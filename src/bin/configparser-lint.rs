@@ -0,0 +1,142 @@
+//!A linter for ini-syntax files, built on `configparser`. It prints the findings of `configparser::lint::Linter`
+//!in the `file:line:column` format understood by editors and CI systems.
+mod common;
+
+use common::{Failure, parse_option, read, value};
+use configparser::ini::IniDefault;
+use configparser::lint::{Linter, Rule, Severity};
+
+use std::env;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: configparser-lint [OPTIONS] [FILES]...
+
+Checks ini-syntax files for likely mistakes. Without files, standard input is checked. Rules can be suppressed in
+a file with comments like `; configparser-lint: allow(empty-value)` (for the next line, or the same line if inline)
+and `; configparser-lint: allow-file(key-case)`.
+
+Options:
+  --allow <RULES>                   Turn off the comma-separated rules
+  --warn <RULES>                    Report the rules as warnings
+  --deny <RULES>                    Report the rules as errors
+  --deny-warnings                   Exit with 1 on warnings too
+  --list-rules                      Print every rule with its default severity
+  -h, --help                        Print this help
+{PARSE_OPTIONS}
+Exit codes:
+  0  success, possibly with warnings
+  1  errors were found
+  2  invalid usage
+  4  a file couldn't be read
+";
+
+fn main() -> ExitCode {
+    let usage = USAGE.replace("{PARSE_OPTIONS}", common::PARSE_OPTIONS);
+    let result = run(env::args().skip(1).collect(), &usage);
+    common::exit("configparser-lint", &usage, result)
+}
+
+///Private function that parses a comma-separated list of rule IDs.
+fn rules(name: &str, args: &mut impl Iterator<Item = String>) -> Result<Vec<Rule>, Failure> {
+    value(name, args)?
+        .split(',')
+        .map(|id| id.trim().parse().map_err(Failure::Usage))
+        .collect()
+}
+
+fn run(args: Vec<String>, usage: &str) -> Result<(), Failure> {
+    let mut defaults = IniDefault::default();
+    let mut severities: Vec<(Rule, Option<Severity>)> = Vec::new();
+    let mut deny_warnings = false;
+    let mut files = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", usage);
+                return Ok(());
+            }
+            "--list-rules" => {
+                let mut stdout = io::stdout().lock();
+                for rule in Rule::ALL {
+                    writeln!(stdout, "{:<20} {}", rule.id(), rule.default_severity())
+                        .map_err(|why| Failure::Io(why.to_string()))?;
+                }
+                return Ok(());
+            }
+            "--allow" => {
+                severities.extend(rules(&arg, &mut args)?.into_iter().map(|rule| (rule, None)))
+            }
+            "--warn" => severities.extend(
+                rules(&arg, &mut args)?
+                    .into_iter()
+                    .map(|rule| (rule, Some(Severity::Warning))),
+            ),
+            "--deny" => severities.extend(
+                rules(&arg, &mut args)?
+                    .into_iter()
+                    .map(|rule| (rule, Some(Severity::Error))),
+            ),
+            "--deny-warnings" => deny_warnings = true,
+            "--" => files.extend(args.by_ref()),
+            _ if parse_option(&arg, &mut args, &mut defaults)? => {}
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {}", arg)));
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    let mut linter = Linter::new_from_defaults(defaults);
+    for (rule, severity) in severities {
+        linter = match severity {
+            Some(severity) => linter.severity(rule, severity),
+            None => linter.allow(rule),
+        };
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    // Every file is linted even if some can't be read, and an unreadable file decides the exit code.
+    let mut failed = false;
+    let mut unreadable = false;
+    let mut stdout = io::stdout().lock();
+    for file in files.iter() {
+        let text = match read(file) {
+            Ok(text) => text,
+            Err(Failure::Io(why)) => {
+                eprintln!("configparser-lint: {}", why);
+                unreadable = true;
+                continue;
+            }
+            Err(failure) => return Err(failure),
+        };
+        let name = if file == "-" { "<stdin>" } else { file };
+        for diagnostic in linter.lint(&text) {
+            failed |= diagnostic.severity == Severity::Error || deny_warnings;
+            writeln!(
+                stdout,
+                "{}:{}:{}: {}[{}]: {}",
+                name,
+                diagnostic.line,
+                diagnostic.column,
+                diagnostic.severity,
+                diagnostic.rule,
+                diagnostic.message
+            )
+            .map_err(|why| Failure::Io(why.to_string()))?;
+        }
+    }
+    if unreadable {
+        // The failures were already reported.
+        return Err(Failure::Io(String::new()));
+    }
+    if failed {
+        // The diagnostics were already printed.
+        return Err(Failure::Check(String::new()));
+    }
+    Ok(())
+}
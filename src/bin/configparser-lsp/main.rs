@@ -0,0 +1,768 @@
+//!A language server for ini-syntax files over standard input and output, built on `configparser`. It reports parse
+//!errors and lint findings, lists sections and keys as document symbols, formats documents, finds the keys that
+//!`%(key)s` and `${section:key}` references point to and, given a schema, validates documents and offers hover and
+//!completion.
+#[path = "../common/mod.rs"]
+mod common;
+mod json;
+mod sample;
+
+use common::{Failure, parse_option, value};
+use configparser::edit::Document;
+use configparser::ini::{CasePolicy, Ini, IniDefault, WriteOptions};
+use configparser::lint::{Linter, Severity};
+use configparser::parser::{EventKind, Events};
+use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+use json::{Json, object};
+use sample::read_schema;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: configparser-lsp [OPTIONS]
+
+Runs a language server for ini-syntax files, speaking the language server protocol over standard input and output.
+
+Options:
+  --schema <FILE>                   Validate documents against a schema, read from a sample configuration in the
+                                    format of `Schema::write_sample()`, and use it for hover and completion
+  --space-around-delimiters         Format documents with `key = value` instead of `key=value`
+  -h, --help                        Print this help
+{PARSE_OPTIONS}";
+
+///Error codes of JSON-RPC and the language server protocol.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+///Symbol, completion item and diagnostic kinds of the language server protocol.
+const SYMBOL_NAMESPACE: usize = 3;
+const SYMBOL_PROPERTY: usize = 7;
+const COMPLETION_MODULE: usize = 9;
+const COMPLETION_PROPERTY: usize = 10;
+const COMPLETION_VALUE: usize = 12;
+const DIAGNOSTIC_ERROR: usize = 1;
+const DIAGNOSTIC_WARNING: usize = 2;
+
+fn main() -> ExitCode {
+    let usage = USAGE.replace("{PARSE_OPTIONS}", common::PARSE_OPTIONS);
+    let result = match options(env::args().skip(1).collect(), &usage) {
+        Ok(Some(server)) => server.run(&mut io::stdin().lock(), &mut io::stdout().lock()),
+        Ok(None) => Ok(()),
+        Err(failure) => Err(failure),
+    };
+    common::exit("configparser-lsp", &usage, result)
+}
+
+///Private function that reads the command-line options.
+///Returns the server to run, or `None` if only the help was requested.
+fn options(args: Vec<String>, usage: &str) -> Result<Option<Server>, Failure> {
+    let mut defaults = IniDefault::default();
+    defaults.section_case = CasePolicy::Preserve;
+    defaults.key_case = CasePolicy::Preserve;
+    let mut schema = None;
+    let mut write_options = WriteOptions::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", usage);
+                return Ok(None);
+            }
+            "--schema" => {
+                let file = value(&arg, &mut args)?;
+                let sample = fs::read_to_string(&file)
+                    .map_err(|why| Failure::Io(format!("couldn't read {}: {}", file, why)))?;
+                schema =
+                    Some(read_schema(&sample).map_err(|why| {
+                        Failure::Parse(format!("couldn't parse {}: {}", file, why))
+                    })?);
+            }
+            "--space-around-delimiters" => write_options.space_around_delimiters = true,
+            _ if parse_option(&arg, &mut args, &mut defaults)? => {}
+            _ => return Err(Failure::Usage(format!("unknown option {}", arg))),
+        }
+    }
+    Ok(Some(Server {
+        defaults,
+        schema,
+        write_options,
+        documents: HashMap::new(),
+        shutdown: false,
+    }))
+}
+
+///Private function that reads a message framed by a `Content-Length` header.
+///Returns `None` at the end of the input, or the message (which may not be valid JSON).
+fn read_message(input: &mut impl BufRead) -> Result<Option<Result<Json, String>>, Failure> {
+    let io_error = |why: io::Error| Failure::Io(why.to_string());
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(io_error)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length
+        .ok_or_else(|| Failure::Parse(String::from("message without a Content-Length header")))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).map_err(io_error)?;
+    Ok(Some(match String::from_utf8(body) {
+        Ok(body) => Json::parse(&body),
+        Err(_) => Err(String::from("the message isn't valid UTF-8")),
+    }))
+}
+
+///Private function that writes a message framed by a `Content-Length` header.
+fn send(output: &mut impl Write, message: Json) -> Result<(), Failure> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+        .and_then(|_| output.flush())
+        .map_err(|why| Failure::Io(why.to_string()))
+}
+
+fn error_response(id: Json, code: i64, message: &str) -> Json {
+    object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            object([
+                ("code", Json::Number(code as f64)),
+                ("message", message.into()),
+            ]),
+        ),
+    ])
+}
+
+///Private function that converts a column counted in characters to UTF-16 code units, the default position
+///encoding of the protocol.
+fn utf16(line: &str, column: usize) -> usize {
+    line.chars().take(column).map(char::len_utf16).sum()
+}
+
+///Private function that converts a column in UTF-16 code units to characters.
+fn chars(line: &str, units: usize) -> usize {
+    let mut total = 0;
+    line.chars()
+        .take_while(|c| {
+            total += c.len_utf16();
+            total <= units
+        })
+        .count()
+}
+
+///A section of an open document, with 0-based lines and columns counted in characters.
+struct Section {
+    name: String,
+    ///The line and column of the header, or `None` for the keys before the first header.
+    header: Option<(usize, usize)>,
+    ///The first and last line of the section.
+    lines: (usize, usize),
+    keys: Vec<Key>,
+}
+
+///A key of an open document, with 0-based lines and columns counted in characters.
+struct Key {
+    name: String,
+    line: usize,
+    column: usize,
+    ///The last line of the key, which is later than `line` for multiline values.
+    end: usize,
+}
+
+///A text and its lines.
+struct Text<'a> {
+    text: &'a str,
+    lines: Vec<&'a str>,
+}
+
+impl<'a> Text<'a> {
+    fn new(text: &'a str) -> Text<'a> {
+        Text {
+            text,
+            lines: text.lines().collect(),
+        }
+    }
+
+    fn line(&self, line: usize) -> &'a str {
+        self.lines.get(line).copied().unwrap_or_default()
+    }
+
+    fn position(&self, line: usize, column: usize) -> Json {
+        object([
+            ("line", line.into()),
+            ("character", utf16(self.line(line), column).into()),
+        ])
+    }
+
+    fn range(&self, start: (usize, usize), end: (usize, usize)) -> Json {
+        object([
+            ("start", self.position(start.0, start.1)),
+            ("end", self.position(end.0, end.1)),
+        ])
+    }
+
+    ///Returns the column after the last non-whitespace character of a line.
+    fn line_end(&self, line: usize) -> usize {
+        self.line(line).trim_end().chars().count()
+    }
+}
+
+struct Server {
+    defaults: IniDefault,
+    schema: Option<Schema>,
+    write_options: WriteOptions,
+    ///The text of every open document by URI.
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+impl Server {
+    fn run(mut self, input: &mut impl BufRead, output: &mut impl Write) -> Result<(), Failure> {
+        loop {
+            let message = match read_message(input)? {
+                Some(Ok(message)) => message,
+                Some(Err(why)) => {
+                    send(output, error_response(Json::Null, PARSE_ERROR, &why))?;
+                    continue;
+                }
+                None => {
+                    return Err(Failure::Check(String::from(
+                        "the client closed the connection without an exit notification",
+                    )));
+                }
+            };
+            let id = message.get("id").clone();
+            let params = message.get("params");
+            let Some(method) = message.get("method").as_str() else {
+                // The server sends no requests, so there are no responses to handle.
+                continue;
+            };
+            if method == "exit" {
+                return match self.shutdown {
+                    true => Ok(()),
+                    false => Err(Failure::Check(String::from(
+                        "exit without a shutdown request",
+                    ))),
+                };
+            }
+            if id == Json::Null {
+                for notification in self.notify(method, params) {
+                    send(output, notification)?;
+                }
+                continue;
+            }
+            let response = match self.request(method, params) {
+                Ok(result) => object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)]),
+                Err((code, message)) => error_response(id, code, &message),
+            };
+            send(output, response)?;
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params
+            .get("textDocument")
+            .get("uri")
+            .as_str()
+            .unwrap_or_default();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params
+                    .get("textDocument")
+                    .get("text")
+                    .as_str()
+                    .unwrap_or_default();
+                self.documents.insert(uri.to_owned(), text.to_owned());
+                vec![self.diagnostics(uri)]
+            }
+            "textDocument/didChange" => {
+                // Documents are synchronized in full, so the last change has the whole text.
+                let Some(text) = params.get("contentChanges").as_array().last() else {
+                    return Vec::new();
+                };
+                let text = text.get("text").as_str().unwrap_or_default();
+                self.documents.insert(uri.to_owned(), text.to_owned());
+                vec![self.diagnostics(uri)]
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                vec![publish_diagnostics(uri, Vec::new())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        if self.shutdown {
+            return Err((INVALID_REQUEST, String::from("the server is shutting down")));
+        }
+        if method == "initialize" {
+            return Ok(object([
+                (
+                    "capabilities",
+                    object([
+                        ("textDocumentSync", 1.into()),
+                        ("documentSymbolProvider", true.into()),
+                        ("hoverProvider", true.into()),
+                        (
+                            "completionProvider",
+                            object([(
+                                "triggerCharacters",
+                                vec!["[".into(), "=".into(), ":".into()].into(),
+                            )]),
+                        ),
+                        ("definitionProvider", true.into()),
+                        ("documentFormattingProvider", true.into()),
+                    ]),
+                ),
+                (
+                    "serverInfo",
+                    object([
+                        ("name", "configparser-lsp".into()),
+                        ("version", env!("CARGO_PKG_VERSION").into()),
+                    ]),
+                ),
+            ]));
+        }
+        if method == "shutdown" {
+            self.shutdown = true;
+            return Ok(Json::Null);
+        }
+
+        let uri = params
+            .get("textDocument")
+            .get("uri")
+            .as_str()
+            .unwrap_or_default();
+        let Some(text) = self.documents.get(uri) else {
+            return match method {
+                "textDocument/documentSymbol"
+                | "textDocument/hover"
+                | "textDocument/completion"
+                | "textDocument/definition"
+                | "textDocument/formatting" => Ok(Json::Null),
+                _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+            };
+        };
+        let text = Text::new(text);
+        let position = params.get("position");
+        let line = position.get("line").as_usize().unwrap_or_default();
+        let column = chars(
+            text.line(line),
+            position.get("character").as_usize().unwrap_or_default(),
+        );
+        match method {
+            "textDocument/documentSymbol" => Ok(self.symbols(&text)),
+            "textDocument/hover" => Ok(self.hover(&text, line, column)),
+            "textDocument/completion" => Ok(self.completion(&text, line, column)),
+            "textDocument/definition" => Ok(self.definition(&text, uri, line, column)),
+            "textDocument/formatting" => {
+                let tab_size = params.get("options").get("tabSize").as_usize();
+                Ok(self.formatting(&text, tab_size))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        }
+    }
+
+    ///Returns the sections of a document, starting with the keys before the first header.
+    fn outline(&self, text: &Text) -> Vec<Section> {
+        let mut sections = vec![Section {
+            name: self.defaults.default_section.clone(),
+            header: None,
+            lines: (0, 0),
+            keys: Vec::new(),
+        }];
+        for event in Events::with_defaults(text.text.as_bytes(), &self.defaults).flatten() {
+            let line = event.line - 1;
+            let column = event.column - 1;
+            let section = sections.last_mut().expect("there is always a section");
+            match event.kind {
+                EventKind::SectionStart { name } => sections.push(Section {
+                    name,
+                    header: Some((line, column)),
+                    lines: (line, line),
+                    keys: Vec::new(),
+                }),
+                EventKind::KeyValue { key, .. } => section.keys.push(Key {
+                    name: key,
+                    line,
+                    column,
+                    end: line,
+                }),
+                EventKind::Continuation { .. } => {
+                    if let Some(key) = section.keys.last_mut() {
+                        key.end = line;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let last = text.lines.len().saturating_sub(1);
+        for i in 0..sections.len() {
+            sections[i].lines.1 = match sections.get(i + 1) {
+                Some(next) => next.lines.0.saturating_sub(1),
+                None => last,
+            };
+        }
+        sections
+    }
+
+    ///Returns the section of a document that contains a line.
+    fn section_at<'a>(&self, sections: &'a [Section], line: usize) -> &'a Section {
+        sections
+            .iter()
+            .rev()
+            .find(|section| section.lines.0 <= line)
+            .unwrap_or(&sections[0])
+    }
+
+    fn schema_section(&self, name: &str) -> Option<&SectionSchema> {
+        self.schema
+            .as_ref()?
+            .sections
+            .iter()
+            .find(|section| self.defaults.same_section(&section.name, name))
+    }
+
+    fn schema_key(&self, section: &str, key: &str) -> Option<&KeySchema> {
+        self.schema_section(section)?
+            .keys
+            .iter()
+            .find(|k| self.defaults.same_key(&k.name, key))
+    }
+
+    fn diagnostics(&self, uri: &str) -> Json {
+        let text = Text::new(self.documents.get(uri).map_or("", String::as_str));
+        let diagnostic =
+            |line: usize, column: usize, severity: usize, code: Json, message: String| {
+                let end = text.line_end(line).max(column);
+                object([
+                    ("range", text.range((line, column), (line, end))),
+                    ("severity", severity.into()),
+                    ("code", code),
+                    ("source", "configparser".into()),
+                    ("message", message.into()),
+                ])
+            };
+        let mut diagnostics = Vec::new();
+        for found in Linter::new_from_defaults(self.defaults.clone()).lint(text.text) {
+            let severity = match found.severity {
+                Severity::Error => DIAGNOSTIC_ERROR,
+                _ => DIAGNOSTIC_WARNING,
+            };
+            diagnostics.push(diagnostic(
+                found.line - 1,
+                found.column - 1,
+                severity,
+                found.rule.id().into(),
+                found.message,
+            ));
+        }
+
+        let mut config = Ini::new_from_defaults(self.defaults.clone());
+        if let Some(schema) = &self.schema
+            && config.read(text.text.to_owned()).is_ok()
+            && let Err(violations) = config.validate(schema)
+        {
+            for mut violation in violations {
+                // Missing sections have no position, so they are reported at the start of the document.
+                let line = violation.line.take().map_or(0, |line| line - 1);
+                let column = violation.column.take().map_or(0, |column| column - 1);
+                diagnostics.push(diagnostic(
+                    line,
+                    column,
+                    DIAGNOSTIC_ERROR,
+                    "schema".into(),
+                    violation.to_string(),
+                ));
+            }
+        }
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    fn symbols(&self, text: &Text) -> Json {
+        let key_symbol = |key: &Key| {
+            let name_end = (key.line, key.column + key.name.chars().count());
+            object([
+                ("name", key.name.as_str().into()),
+                ("kind", SYMBOL_PROPERTY.into()),
+                (
+                    "range",
+                    text.range((key.line, key.column), (key.end, text.line_end(key.end))),
+                ),
+                (
+                    "selectionRange",
+                    text.range((key.line, key.column), name_end),
+                ),
+            ])
+        };
+        let mut symbols = Vec::new();
+        for section in self.outline(text) {
+            let keys = section.keys.iter().map(key_symbol);
+            let Some((line, column)) = section.header else {
+                symbols.extend(keys);
+                continue;
+            };
+            let end = section.keys.last().map_or(line, |key| key.end);
+            symbols.push(object([
+                ("name", section.name.as_str().into()),
+                ("kind", SYMBOL_NAMESPACE.into()),
+                (
+                    "range",
+                    text.range((line, column), (end, text.line_end(end))),
+                ),
+                (
+                    "selectionRange",
+                    text.range((line, column), (line, text.line_end(line))),
+                ),
+                ("children", keys.collect::<Vec<_>>().into()),
+            ]));
+        }
+        symbols.into()
+    }
+
+    fn hover(&self, text: &Text, line: usize, column: usize) -> Json {
+        let sections = self.outline(text);
+        let section = self.section_at(&sections, line);
+        let (contents, range) = if section.header.is_some_and(|(header, _)| header == line) {
+            let Some(schema) = self.schema_section(&section.name) else {
+                return Json::Null;
+            };
+            let mut contents = format!("**[{}]**", schema.name);
+            if let Some(description) = &schema.description {
+                contents.push_str(&format!("\n\n{}", description));
+            }
+            (contents, text.range((line, 0), (line, text.line_end(line))))
+        } else {
+            let Some(key) = section.keys.iter().find(|key| {
+                key.line <= line && line <= key.end && (key.line < line || key.column <= column)
+            }) else {
+                return Json::Null;
+            };
+            let Some(schema) = self.schema_key(&section.name, &key.name) else {
+                return Json::Null;
+            };
+            let mut contents = format!("**{}**: {}", schema.name, schema.value_type);
+            if schema.required && schema.default.is_none() {
+                contents.push_str(" (required)");
+            }
+            if let Some(default) = &schema.default {
+                contents.push_str(&format!("\n\nDefault: `{}`", default));
+            }
+            if let Some(description) = &schema.description {
+                contents.push_str(&format!("\n\n{}", description));
+            }
+            let name_end = key.column + key.name.chars().count();
+            (
+                contents,
+                text.range((key.line, key.column), (key.line, name_end)),
+            )
+        };
+        object([
+            (
+                "contents",
+                object([("kind", "markdown".into()), ("value", contents.into())]),
+            ),
+            ("range", range),
+        ])
+    }
+
+    fn completion(&self, text: &Text, line: usize, column: usize) -> Json {
+        let Some(schema) = &self.schema else {
+            return Json::Array(Vec::new());
+        };
+        let sections = self.outline(text);
+        let typed: String = text.line(line).chars().take(column).collect();
+        let typed = typed.trim_start();
+        let mut items = Vec::new();
+
+        if typed.starts_with('[') {
+            let close = if text.line(line).contains(']') {
+                ""
+            } else {
+                "]"
+            };
+            for section in schema.sections.iter() {
+                items.push(object([
+                    ("label", section.name.as_str().into()),
+                    ("kind", COMPLETION_MODULE.into()),
+                    ("insertText", format!("{}{}", section.name, close).into()),
+                    (
+                        "documentation",
+                        section.description.clone().unwrap_or_default().into(),
+                    ),
+                ]));
+            }
+            return items.into();
+        }
+
+        let section = self.section_at(&sections, line);
+        if let Some(delimiter) = typed.find(&self.defaults.delimiters[..]) {
+            // The cursor is on a value: complete the values the type of the key allows.
+            let Some(key) = self.schema_key(&section.name, typed[..delimiter].trim()) else {
+                return items.into();
+            };
+            let values: Vec<String> = match &key.value_type {
+                ValueType::Bool => vec![String::from("true"), String::from("false")],
+                ValueType::Enum(values) => values.clone(),
+                _ => key.default.iter().cloned().collect(),
+            };
+            for value in values {
+                items.push(object([
+                    ("label", value.into()),
+                    ("kind", COMPLETION_VALUE.into()),
+                ]));
+            }
+            return items.into();
+        }
+
+        let Some(section_schema) = self.schema_section(&section.name) else {
+            return items.into();
+        };
+        let delimiter = match self.write_options.space_around_delimiters {
+            true => format!(" {} ", self.write_options.delimiter),
+            false => self.write_options.delimiter.to_string(),
+        };
+        for key in section_schema.keys.iter() {
+            // Keys that are already set in the section (on other lines) aren't offered again.
+            let set = sections.iter().any(|s| {
+                self.defaults.same_section(&s.name, &section.name)
+                    && s.keys
+                        .iter()
+                        .any(|k| k.line != line && self.defaults.same_key(&k.name, &key.name))
+            });
+            if set {
+                continue;
+            }
+            items.push(object([
+                ("label", key.name.as_str().into()),
+                ("kind", COMPLETION_PROPERTY.into()),
+                ("detail", key.value_type.to_string().into()),
+                (
+                    "documentation",
+                    key.description.clone().unwrap_or_default().into(),
+                ),
+                (
+                    "insertText",
+                    format!(
+                        "{}{}{}",
+                        key.name,
+                        delimiter,
+                        key.default.as_deref().unwrap_or_default()
+                    )
+                    .into(),
+                ),
+            ]));
+        }
+        items.into()
+    }
+
+    ///Finds the key that a `%(key)s`, `${key}` or `${section:key}` reference under the cursor points to. A
+    ///reference without a section is looked up in the section of the cursor and then in the default section.
+    fn definition(&self, text: &Text, uri: &str, line: usize, column: usize) -> Json {
+        let Some((section, key)) = reference_at(text.line(line), column) else {
+            return Json::Null;
+        };
+        let sections = self.outline(text);
+        let current = &self.section_at(&sections, line).name;
+        let default_section = &self.defaults.default_section;
+        let candidates: Vec<&str> = match &section {
+            Some(section) => vec![section],
+            None => vec![current, default_section],
+        };
+        for candidate in candidates {
+            // The last definition of a key is the one that is stored.
+            let found = sections
+                .iter()
+                .filter(|s| self.defaults.same_section(&s.name, candidate))
+                .flat_map(|s| s.keys.iter())
+                .rev()
+                .find(|k| self.defaults.same_key(&k.name, &key));
+            if let Some(found) = found {
+                let name_end = found.column + found.name.chars().count();
+                return object([
+                    ("uri", uri.into()),
+                    (
+                        "range",
+                        text.range((found.line, found.column), (found.line, name_end)),
+                    ),
+                ]);
+            }
+        }
+        Json::Null
+    }
+
+    fn formatting(&self, text: &Text, tab_size: Option<usize>) -> Json {
+        let mut write_options = self.write_options.clone();
+        if let Some(tab_size) = tab_size {
+            write_options.multiline_line_indentation = tab_size;
+        }
+        let Ok(mut document) = Document::new(text.text.to_owned(), self.defaults.clone()) else {
+            return Json::Null;
+        };
+        if document.format(&write_options, false).is_err() || document.text() == text.text {
+            return Json::Array(Vec::new());
+        }
+        vec![object([
+            ("range", text.range((0, 0), (text.lines.len(), 0))),
+            ("newText", document.text().into()),
+        ])]
+        .into()
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            object([("uri", uri.into()), ("diagnostics", diagnostics.into())]),
+        ),
+    ])
+}
+
+///Private function that finds the `%(key)s`, `${key}` or `${section:key}` reference at a column of a line.
+///Returns the section (if the reference names one) and the key.
+fn reference_at(line: &str, column: usize) -> Option<(Option<String>, String)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let (open, close) = match (chars[start], chars[start + 1]) {
+            ('%', '(') => (start + 2, ')'),
+            ('$', '{') => (start + 2, '}'),
+            _ => {
+                start += 1;
+                continue;
+            }
+        };
+        let Some(length) = chars[open..].iter().position(|&c| c == close) else {
+            break;
+        };
+        let end = open + length;
+        if (start..=end).contains(&column) {
+            let inside: String = chars[open..end].iter().collect();
+            return match (close, inside.split_once(':')) {
+                ('}', Some((section, key))) => Some((Some(section.to_owned()), key.to_owned())),
+                _ => Some((None, inside)),
+            };
+        }
+        start = end + 1;
+    }
+    None
+}
@@ -0,0 +1,170 @@
+//!Reading schemas back from sample configurations written by `Schema::write_sample()`.
+use configparser::ini::IniDefault;
+use configparser::parser::{EventKind, Events};
+use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+
+use std::str::FromStr;
+
+///Reads a schema back from a sample configuration in the format of `Schema::sample()`, so a sample file can double
+///as the description of a configuration. The comments before a section header become its description, and a key
+///preceded by a `; Type: ...` comment gets that type, the comments before it as description and its value
+///(even if the key is commented out) as default. Keys without a type comment are strings.
+///Sections are optional and unknown sections and keys aren't allowed, like in a new `Schema`.
+///Returns `Ok(schema)` or an `Err(error_string)` with the line of a type that couldn't be read.
+pub fn read_schema(sample: &str) -> Result<Schema, String> {
+    fn section<'a>(schema: &'a mut Schema, name: &str) -> &'a mut SectionSchema {
+        match schema.sections.iter().position(|s| s.name == name) {
+            Some(position) => &mut schema.sections[position],
+            None => {
+                schema.sections.push(SectionSchema::new(name));
+                schema
+                    .sections
+                    .last_mut()
+                    .expect("a section was just added")
+            }
+        }
+    }
+
+    fn description(comments: &mut Vec<String>) -> Option<String> {
+        let lines = std::mem::take(comments);
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    fn add_key(
+        schema: &mut Schema,
+        current: &str,
+        comments: &mut Vec<String>,
+        key_type: Option<(ValueType, bool)>,
+        name: &str,
+        value: Option<&str>,
+    ) {
+        let (value_type, required) = key_type.unwrap_or((ValueType::String, false));
+        let mut key = KeySchema::new(name, value_type);
+        key.required = required;
+        key.default = value.filter(|value| !value.is_empty()).map(str::to_owned);
+        key.description = description(comments);
+        section(schema, current).keys.push(key);
+    }
+
+    let defaults = IniDefault::default();
+    let mut schema = Schema::new();
+    let mut current = defaults.default_section.clone();
+    let mut comments: Vec<String> = Vec::new();
+    // The type of the next key, read from its `Type:` comment.
+    let mut key_type: Option<(ValueType, bool)> = None;
+    for event in Events::with_defaults(sample.as_bytes(), &defaults) {
+        let event = event?;
+        match event.kind {
+            EventKind::SectionStart { name } => {
+                let description = description(&mut comments);
+                let section = section(&mut schema, &name);
+                section.description = section.description.take().or(description);
+                current = name;
+                key_type = None;
+            }
+            EventKind::KeyValue { key, value, .. } => {
+                let key_type = key_type.take();
+                add_key(
+                    &mut schema,
+                    &current,
+                    &mut comments,
+                    key_type,
+                    &key,
+                    value.as_deref(),
+                );
+            }
+            EventKind::Comment { text } => {
+                let line = &text[text.chars().next().map_or(0, char::len_utf8)..];
+                let line = line.strip_prefix(' ').unwrap_or(line);
+                if let Some(key_type) = key_type.take() {
+                    // A commented out key.
+                    let (key, value) = match line.find(&defaults.delimiters[..]) {
+                        Some(delimiter) => (&line[..delimiter], Some(line[delimiter + 1..].trim())),
+                        None => (line, None),
+                    };
+                    add_key(
+                        &mut schema,
+                        &current,
+                        &mut comments,
+                        Some(key_type),
+                        key.trim(),
+                        value,
+                    );
+                } else if let Some(value_type) = line.strip_prefix("Type: ") {
+                    let (value_type, required) = match value_type.strip_suffix(" (required)") {
+                        Some(value_type) => (value_type, true),
+                        None => (value_type, false),
+                    };
+                    let value_type = parse_value_type(value_type)
+                        .map_err(|why| format!("line {}:{}: {}", event.line, event.column, why))?;
+                    key_type = Some((value_type, required));
+                } else {
+                    comments.push(line.to_owned());
+                }
+            }
+            _ => {
+                comments.clear();
+                key_type = None;
+            }
+        }
+    }
+    Ok(schema)
+}
+
+///Private function that parses a value type in the format of its `Display`, such as `integer (1..=65535)` or
+///`one of dev, prod`.
+fn parse_value_type(s: &str) -> Result<ValueType, String> {
+    fn range<T: FromStr>(s: &str, name: &str) -> Option<(Option<T>, Option<T>)> {
+        if s == name {
+            return Some((None, None));
+        }
+        let bounds = s
+            .strip_prefix(name)?
+            .strip_prefix(" (")?
+            .strip_suffix(')')?;
+        if let Some(min) = bounds.strip_prefix(">= ") {
+            Some((Some(min.parse().ok()?), None))
+        } else if let Some(max) = bounds.strip_prefix("<= ") {
+            Some((None, Some(max.parse().ok()?)))
+        } else {
+            let (min, max) = bounds.split_once("..=")?;
+            Some((Some(min.parse().ok()?), Some(max.parse().ok()?)))
+        }
+    }
+
+    let invalid = || format!("unknown value type {:?}", s);
+    match s {
+        "string" => return Ok(ValueType::String),
+        "boolean" => return Ok(ValueType::Bool),
+        "duration" => return Ok(ValueType::Duration),
+        _ => {}
+    }
+    if let Some((min, max)) = range(s, "integer") {
+        Ok(ValueType::Int { min, max })
+    } else if let Some((min, max)) = range(s, "unsigned integer") {
+        Ok(ValueType::Uint { min, max })
+    } else if let Some((min, max)) = range(s, "number") {
+        Ok(ValueType::Float { min, max })
+    } else if let Some(values) = s.strip_prefix("one of ") {
+        Ok(ValueType::one_of(values.split(", ")))
+    } else if let Some(pattern) = s.strip_prefix("pattern ") {
+        Ok(ValueType::Pattern(pattern.to_owned()))
+    } else if let Some(list) = s.strip_prefix("list of ") {
+        let (item, separator) = list.rsplit_once(" separated by ").ok_or_else(invalid)?;
+        // The separator is written like a char literal.
+        let separator = match separator
+            .strip_prefix('\'')
+            .and_then(|c| c.strip_suffix('\''))
+        {
+            Some("\\t") => '\t',
+            Some("\\n") => '\n',
+            Some("\\'") => '\'',
+            Some("\\\\") => '\\',
+            Some(c) if c.chars().count() == 1 => c.chars().next().unwrap_or_default(),
+            _ => return Err(invalid()),
+        };
+        Ok(ValueType::list_of(parse_value_type(item)?, separator))
+    } else {
+        Err(invalid())
+    }
+}
@@ -0,0 +1,185 @@
+//!A command-line tool to query and edit ini-syntax files from shell scripts, built on `configparser`.
+//!Edits only touch the lines of the changed keys and sections, see `configparser::edit::Document`.
+mod common;
+
+use common::{Failure, parse_option, read};
+use configparser::edit::Document;
+use configparser::ini::{Ini, IniDefault};
+
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: configparser [OPTIONS] <COMMAND> <FILE> [ARGS]
+
+Commands:
+  get <FILE> <SECTION> <KEY>          Print the value of a key
+  set <FILE> <SECTION> <KEY> [VALUE]  Set a key, adding it and its section if needed (no value: a valueless key)
+  del <FILE> <SECTION> [KEY]          Delete a key, or a whole section
+  list-sections <FILE>                Print the names of all sections
+  list-keys <FILE> <SECTION>          Print the keys of a section
+  merge <FILE> [SOURCE]               Set every key of SOURCE (default: standard input) in FILE
+
+FILE can be `-` to read standard input for get, list-sections and list-keys.
+Keys before the first section header are in the default section.
+
+Options:
+  --cascade-defaults                Let get fall back to the default section
+  -h, --help                        Print this help
+{PARSE_OPTIONS}
+Exit codes:
+  0  success
+  1  the section or key doesn't exist
+  2  invalid usage
+  3  a file couldn't be parsed or the edit couldn't be made
+  4  a file couldn't be read or written
+";
+
+fn main() -> ExitCode {
+    let usage = USAGE.replace("{PARSE_OPTIONS}", common::PARSE_OPTIONS);
+    let result = run(env::args().skip(1).collect(), &usage);
+    common::exit("configparser", &usage, result)
+}
+
+fn run(args: Vec<String>, usage: &str) -> Result<(), Failure> {
+    let mut defaults = IniDefault::default();
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", usage);
+                return Ok(());
+            }
+            "--cascade-defaults" => defaults.cascade_defaults = true,
+            "--" => positional.extend(args.by_ref()),
+            _ if parse_option(&arg, &mut args, &mut defaults)? => {}
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {}", arg)));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let positional: Vec<&str> = positional.iter().map(String::as_str).collect();
+    match positional[..] {
+        ["get", file, section, key] => get(&defaults, file, section, key),
+        ["set", file, section, key] => {
+            edit(&defaults, file, |document| document.set(section, key, None))
+        }
+        ["set", file, section, key, value] => edit(&defaults, file, |document| {
+            document.set(section, key, Some(value))
+        }),
+        ["del", file, section] => edit(&defaults, file, |document| {
+            document.remove_section(section).map(|_| ())
+        }),
+        ["del", file, section, key] => edit(&defaults, file, |document| {
+            document.remove_key(section, key).map(|_| ())
+        }),
+        ["list-sections", file] => {
+            let parse_error = |why| Failure::Parse(format!("couldn't parse {}: {}", file, why));
+            print_lines(document(&defaults, file)?.sections().map_err(parse_error)?)
+        }
+        ["list-keys", file, section] => {
+            let parse_error = |why| Failure::Parse(format!("couldn't parse {}: {}", file, why));
+            let document = document(&defaults, file)?;
+            if !document.has_section(section).map_err(parse_error)? {
+                return Err(Failure::Check(format!(
+                    "no section {} in {}",
+                    section, file
+                )));
+            }
+            print_lines(document.keys(section).map_err(parse_error)?)
+        }
+        ["merge", file] => merge(&defaults, file, "-"),
+        ["merge", file, source] => merge(&defaults, file, source),
+        [] => Err(Failure::Usage(String::from("missing command"))),
+        [command, ..] => match command {
+            "get" | "set" | "del" | "list-sections" | "list-keys" | "merge" => Err(Failure::Usage(
+                format!("wrong number of arguments for {}", command),
+            )),
+            _ => Err(Failure::Usage(format!("unknown command {}", command))),
+        },
+    }
+}
+
+///Private function that reads and parses a file, or standard input for `-`.
+fn document(defaults: &IniDefault, file: &str) -> Result<Document, Failure> {
+    Document::new(read(file)?, defaults.clone())
+        .map_err(|why| Failure::Parse(format!("couldn't parse {}: {}", file, why)))
+}
+
+///Private function that prints one line per name.
+fn print_lines(lines: Vec<String>) -> Result<(), Failure> {
+    let mut stdout = io::stdout().lock();
+    for line in lines {
+        writeln!(stdout, "{}", line).map_err(|why| Failure::Io(why.to_string()))?;
+    }
+    Ok(())
+}
+
+fn get(defaults: &IniDefault, file: &str, section: &str, key: &str) -> Result<(), Failure> {
+    let mut config = Ini::new_from_defaults(defaults.clone());
+    config
+        .read_ref(read(file)?)
+        .map_err(|why| Failure::Parse(format!("couldn't parse {}: {}", file, why)))?;
+    let value = match config.get(section, key) {
+        Some(value) => value,
+        // A key without a value prints an empty line.
+        None if config.section(section).is_some_and(|s| s.contains_key(key)) => String::new(),
+        None => {
+            return Err(Failure::Check(format!(
+                "no key {}:{} in {}",
+                section, key, file
+            )));
+        }
+    };
+    print_lines(vec![value])
+}
+
+///Private function that edits a file in place, creating it if it doesn't exist. The file is only written if the
+///edit changed it, and it is replaced atomically so readers never see a partial file.
+fn edit(
+    defaults: &IniDefault,
+    file: &str,
+    change: impl FnOnce(&mut Document) -> Result<(), String>,
+) -> Result<(), Failure> {
+    if file == "-" {
+        return Err(Failure::Usage(String::from("can't edit standard input")));
+    }
+    let text = if Path::new(file).exists() {
+        read(file)?
+    } else {
+        String::new()
+    };
+    let mut document = Document::new(text, defaults.clone())
+        .map_err(|why| Failure::Parse(format!("couldn't parse {}: {}", file, why)))?;
+    let original = document.clone();
+    change(&mut document).map_err(Failure::Parse)?;
+    if document == original {
+        return Ok(());
+    }
+
+    common::write(file, document.text())
+}
+
+fn merge(defaults: &IniDefault, file: &str, source: &str) -> Result<(), Failure> {
+    let text = read(source)?;
+    let parse_error = |why| Failure::Parse(format!("couldn't parse {}: {}", source, why));
+    let mut config = Ini::new_from_defaults(defaults.clone());
+    config.read_ref(text.clone()).map_err(parse_error)?;
+    // The source is also read as a document to merge its sections and keys in file order.
+    let source = Document::new(text, defaults.clone()).map_err(parse_error)?;
+    edit(defaults, file, |document| {
+        for section in source.sections()? {
+            let values = config.section(&section);
+            for key in source.keys(&section)? {
+                let value = values.and_then(|values| values.get(&key));
+                document.set(&section, &key, value)?;
+            }
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,455 @@
+//!The desktop module provides reading and writing of freedesktop.org desktop entries (`.desktop` files) following the
+//!Desktop Entry Specification: case-sensitive groups and keys, localized keys like `Name[de]`, escaped strings and
+//!semicolon-separated lists.
+//!See the [`DesktopEntry`] documentation for more details.
+use crate::dialect::Dialect;
+use crate::ini::{Escapes, Ini, LINE_ENDING};
+use crate::parser::{EventKind, Lexer, StrEvents};
+use crate::schema::{Violation, ViolationKind};
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+///The name of the group every desktop entry must start with.
+pub const DESKTOP_ENTRY: &str = "Desktop Entry";
+
+///The keys of the `Desktop Entry` group whose values are booleans.
+const BOOLEAN_KEYS: [&str; 7] = [
+    "NoDisplay",
+    "Hidden",
+    "DBusActivatable",
+    "Terminal",
+    "StartupNotify",
+    "PrefersNonDefaultGPU",
+    "SingleMainWindow",
+];
+
+///The `DesktopEntry` struct holds a desktop entry file. Values are stored as written and unescaped when read:
+///`get()` turns `\s`, `\n`, `\t`, `\r` and `\\` into the characters they stand for, `get_list()` also splits the value
+///at semicolons that aren't escaped as `\;`, and the setters escape values the same way.
+///
+///Group and key names are case-sensitive, keys must belong to a group and groups and keys can't be repeated.
+///Localized values are stored under keys like `Name[de]` and looked up with `get_localized()`.
+///## Example
+///```rust
+///use configparser::desktop::DesktopEntry;
+///
+///let mut entry = DesktopEntry::new();
+///entry.read(String::from(
+///    "[Desktop Entry]\nType=Application\nName=Text Editor\nName[de]=Texteditor\nExec=edit %F\n\
+///     Categories=Utility;TextEditor;\nComment=Edit\\sfiles\\nquickly\n",
+///)).unwrap();
+///assert_eq!(entry.get_localized("Desktop Entry", "Name", "de_AT.UTF-8").unwrap(), "Texteditor");
+///assert_eq!(entry.get_list("Desktop Entry", "Categories").unwrap(), ["Utility", "TextEditor"]);
+///assert_eq!(entry.get("Desktop Entry", "Comment").unwrap(), "Edit files\nquickly");
+///assert_eq!(entry.get("desktop entry", "name"), None);
+///assert!(entry.validate().is_ok());
+///```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct DesktopEntry {
+    config: Ini,
+}
+
+impl Default for DesktopEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DesktopEntry {
+    ///Creates an empty desktop entry.
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new() -> DesktopEntry {
+        let mut defaults = Dialect::Desktop.defaults();
+        // Escapes are resolved on access, since lists are split before their items are unescaped.
+        defaults.escapes = Escapes::None;
+        DesktopEntry {
+            config: Ini::new_from_defaults(defaults),
+        }
+    }
+
+    ///Loads a desktop entry from a file, see `read()`.
+    ///Returns `Ok(entry)` or an `Err(error_string)` if the file can't be read or parsed.
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<DesktopEntry, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+        let mut entry = DesktopEntry::new();
+        entry
+            .read(text)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+        Ok(entry)
+    }
+
+    ///Parses desktop entry text, replacing the previous content of the entry.
+    ///## Example
+    ///```rust
+    ///use configparser::desktop::DesktopEntry;
+    ///
+    ///let mut entry = DesktopEntry::new();
+    ///assert_eq!(
+    ///    entry.read(String::from("Name=Editor\n[Desktop Entry]")),
+    ///    Err(String::from("line 1:1: Key Name is outside of a group"))
+    ///);
+    ///assert_eq!(
+    ///    entry.read(String::from("[Desktop Entry]\n[Desktop Action new]\n[Desktop Entry]")),
+    ///    Err(String::from("line 3:1: Group Desktop Entry is already defined"))
+    ///);
+    ///```
+    ///Returns `Ok(())` or an `Err(error_string)` if the text can't be parsed, in which case the entry is unchanged.
+    pub fn read(&mut self, input: String) -> Result<(), String> {
+        let lexer = Lexer::from_defaults(&self.config.defaults());
+        let mut groups = HashSet::new();
+        let mut in_group = false;
+        for event in StrEvents::new(&input, lexer) {
+            let event = event?;
+            match event.kind {
+                EventKind::SectionStart { name } => {
+                    if !groups.insert(name.clone()) {
+                        return Err(format!(
+                            "line {}:{}: Group {} is already defined",
+                            event.line, event.column, name
+                        ));
+                    }
+                    in_group = true;
+                }
+                EventKind::KeyValue { key, .. } if !in_group => {
+                    return Err(format!(
+                        "line {}:{}: Key {} is outside of a group",
+                        event.line, event.column, key
+                    ));
+                }
+                EventKind::KeyValue {
+                    key, value: None, ..
+                } => {
+                    return Err(format!(
+                        "line {}:{}: Missing '=' after key {}",
+                        event.line, event.column, key
+                    ));
+                }
+                _ => {}
+            }
+        }
+        let mut config = Ini::new_from_defaults(self.config.defaults());
+        config.read_ref(input)?;
+        self.config = config;
+        Ok(())
+    }
+
+    ///Returns the unescaped value of a key, or `None` if the group or key is missing.
+    pub fn get(&self, group: &str, key: &str) -> Option<String> {
+        self.raw(group, key).map(|value| unescape(value, false))
+    }
+
+    ///Private function that returns the value of a key as written.
+    fn raw(&self, group: &str, key: &str) -> Option<&str> {
+        self.config
+            .get_map_ref()
+            .get(group)
+            .and_then(|keys| keys.get(key))
+            .and_then(|value| value.as_deref())
+    }
+
+    ///Returns the unescaped items of a list value like `Utility;TextEditor;`, or `None` if the group or key is
+    ///missing. A semicolon that is part of an item is escaped as `\;`.
+    pub fn get_list(&self, group: &str, key: &str) -> Option<Vec<String>> {
+        self.raw(group, key).map(split_list)
+    }
+
+    ///Returns the value of a boolean key, which is either `true` or `false`.
+    ///Returns `Ok(Some(value))` if the key is found or else `Ok(None)`, and an `Err(string)` for any other value.
+    pub fn getbool(&self, group: &str, key: &str) -> Result<Option<bool>, String> {
+        self.raw(group, key)
+            .map(|value| value.parse::<bool>().map_err(|why| why.to_string()))
+            .transpose()
+    }
+
+    ///Returns the key holding the value of a key for a locale like `sr_RS.UTF-8@latin`, as the specification looks
+    ///it up: the encoding is ignored and `Key[lang_COUNTRY@MODIFIER]`, `Key[lang_COUNTRY]`, `Key[lang@MODIFIER]` and
+    ///`Key[lang]` are tried in this order, as far as the locale has these parts, before the unlocalized `Key`.
+    ///Returns `None` if none of these keys is set.
+    pub fn localized_key(&self, group: &str, key: &str, locale: &str) -> Option<String> {
+        locale_variants(locale)
+            .into_iter()
+            .map(|variant| format!("{}[{}]", key, variant))
+            .chain(std::iter::once(key.to_owned()))
+            .find(|candidate| self.raw(group, candidate).is_some())
+    }
+
+    ///Returns the unescaped value of a key for a locale, see `localized_key()` for the fallback order.
+    ///## Example
+    ///```rust
+    ///use configparser::desktop::DesktopEntry;
+    ///
+    ///let mut entry = DesktopEntry::new();
+    ///entry.read(String::from(
+    ///    "[Desktop Entry]\nName=Viewer\nName[sr]=Preglednik\nName[sr_RS@latin]=Pregledač\nName[pt_BR]=Visualizador",
+    ///)).unwrap();
+    ///assert_eq!(entry.get_localized("Desktop Entry", "Name", "sr_RS.UTF-8@latin").unwrap(), "Pregledač");
+    ///assert_eq!(entry.get_localized("Desktop Entry", "Name", "sr_RS").unwrap(), "Preglednik");
+    ///assert_eq!(entry.get_localized("Desktop Entry", "Name", "pt_PT").unwrap(), "Viewer");
+    ///assert_eq!(entry.get_localized("Desktop Entry", "Name", "C").unwrap(), "Viewer");
+    ///```
+    pub fn get_localized(&self, group: &str, key: &str, locale: &str) -> Option<String> {
+        let key = self.localized_key(group, key, locale)?;
+        self.get(group, &key)
+    }
+
+    ///Returns the unescaped items of a list value for a locale, like `Keywords[de]`, see `localized_key()` for the
+    ///fallback order.
+    pub fn get_localized_list(&self, group: &str, key: &str, locale: &str) -> Option<Vec<String>> {
+        let key = self.localized_key(group, key, locale)?;
+        self.get_list(group, &key)
+    }
+
+    ///Sets a key to a string, escaping backslashes, newlines, tabs, carriage returns and leading or trailing spaces.
+    ///Returns the previous value as written, if any.
+    pub fn set(&mut self, group: &str, key: &str, value: &str) -> Option<String> {
+        let value = escape(value, false);
+        self.config.set(group, key, Some(value)).flatten()
+    }
+
+    ///Sets the value of a key for a locale like `de` or `pt_BR`, i.e. the key `Key[locale]`, see `set()`.
+    pub fn set_localized(
+        &mut self,
+        group: &str,
+        key: &str,
+        locale: &str,
+        value: &str,
+    ) -> Option<String> {
+        self.set(group, &format!("{}[{}]", key, locale), value)
+    }
+
+    ///Sets a key to a list, escaping its items like `set()` and their semicolons, and ending each with a semicolon.
+    ///## Example
+    ///```rust
+    ///use configparser::desktop::DesktopEntry;
+    ///
+    ///let mut entry = DesktopEntry::new();
+    ///entry.set_list("Desktop Entry", "Keywords", &["text", "notes; drafts"]);
+    ///assert_eq!(entry.writes(), "[Desktop Entry]\nKeywords=text;notes\\; drafts;\n");
+    ///```
+    ///Returns the previous value as written, if any.
+    pub fn set_list(&mut self, group: &str, key: &str, items: &[&str]) -> Option<String> {
+        let value = items.iter().map(|item| escape(item, true) + ";").collect();
+        self.config.set(group, key, Some(value)).flatten()
+    }
+
+    ///Removes a key.
+    ///Returns the value as written, if the key was set.
+    pub fn remove(&mut self, group: &str, key: &str) -> Option<String> {
+        self.config.remove_key(group, key).flatten()
+    }
+
+    ///Returns the names of all groups.
+    pub fn groups(&self) -> Vec<String> {
+        self.config.sections()
+    }
+
+    ///Returns the underlying `Ini`, which stores the values as written.
+    pub fn as_ini(&self) -> &Ini {
+        &self.config
+    }
+
+    ///Checks the keys the specification requires in the `Desktop Entry` group: `Type`, which must be
+    ///`Application`, `Link` or `Directory`, `Name`, `Exec` for applications that aren't `DBusActivatable` and `URL`
+    ///for links. Keys like `Terminal` must be booleans.
+    ///## Example
+    ///```rust
+    ///use configparser::desktop::DesktopEntry;
+    ///
+    ///let mut entry = DesktopEntry::new();
+    ///entry.read(String::from("[Desktop Entry]\nType=Link\nName=Docs\nNoDisplay=yes")).unwrap();
+    ///let violations = entry.validate().unwrap_err();
+    ///assert_eq!(violations[0].to_string(), "line 1:1: Desktop Entry:URL: missing required key");
+    ///assert_eq!(violations[1].to_string(), "line 4:11: Desktop Entry:NoDisplay: yes is not a boolean");
+    ///```
+    ///Returns `Ok(())` or an `Err(violations)` with every problem found.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        let mut violation = |key: Option<&str>, kind| {
+            let mut violation = Violation {
+                section: DESKTOP_ENTRY.to_owned(),
+                key: key.map(str::to_owned),
+                kind,
+                line: None,
+                column: None,
+            };
+            self.config.locate(&mut violation);
+            violations.push(violation);
+        };
+        if !self.config.get_map_ref().contains_key(DESKTOP_ENTRY) {
+            violation(None, ViolationKind::MissingSection);
+            return Err(violations);
+        }
+        let kind = self.raw(DESKTOP_ENTRY, "Type");
+        let mut required = vec!["Type", "Name"];
+        match kind {
+            Some("Application")
+                if self.getbool(DESKTOP_ENTRY, "DBusActivatable") != Ok(Some(true)) =>
+            {
+                required.push("Exec")
+            }
+            Some("Link") => required.push("URL"),
+            Some("Application") | Some("Directory") | None => {}
+            Some(other) => violation(
+                Some("Type"),
+                ViolationKind::InvalidValue(format!("unknown type {}", other)),
+            ),
+        }
+        for key in required {
+            if self.raw(DESKTOP_ENTRY, key).is_none() {
+                violation(Some(key), ViolationKind::MissingKey);
+            }
+        }
+        for key in BOOLEAN_KEYS {
+            if let Some(value) = self
+                .raw(DESKTOP_ENTRY, key)
+                .filter(|value| value.parse::<bool>().is_err())
+            {
+                let reason = format!("{} is not a boolean", value);
+                violation(Some(key), ViolationKind::InvalidValue(reason));
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    ///Writes the entry in desktop entry syntax, with the `Desktop Entry` group first.
+    ///Returns a `String` with the file text.
+    pub fn writes(&self) -> String {
+        let map = self.config.get_map_ref();
+        let first = map.get_key_value(DESKTOP_ENTRY);
+        let rest = map
+            .iter()
+            .filter(|(group, _)| group.as_str() != DESKTOP_ENTRY);
+        let mut out = String::new();
+        for (group, keys) in first.into_iter().chain(rest) {
+            if !out.is_empty() {
+                out.push_str(LINE_ENDING);
+            }
+            out.push('[');
+            out.push_str(group);
+            out.push(']');
+            out.push_str(LINE_ENDING);
+            for (key, value) in keys {
+                out.push_str(key);
+                out.push('=');
+                out.push_str(value.as_deref().unwrap_or_default());
+                out.push_str(LINE_ENDING);
+            }
+        }
+        out
+    }
+
+    ///Writes the entry to a file, see `writes()`.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not.
+    pub fn write<T: AsRef<Path>>(&self, path: T) -> std::io::Result<()> {
+        fs::write(path.as_ref(), self.writes())
+    }
+}
+
+///Private function that returns the localized key suffixes to try for a locale, from the most to the least specific.
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or_default();
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+    let mut variants = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{}@{}", lang, modifier));
+    }
+    if !lang.is_empty() {
+        variants.push(lang.to_owned());
+    }
+    variants
+}
+
+///Private function that resolves the escapes of a value, and `\;` too in list items. Other escapes are kept.
+fn unescape(value: &str, list: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(';') if list => out.push(';'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+///Private function that splits a list value at the semicolons that aren't escaped and unescapes the items. The
+///semicolon after the last item is optional.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ';' => {
+                items.push(unescape(&value[start..index], true));
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < value.len() {
+        items.push(unescape(&value[start..], true));
+    }
+    items
+}
+
+///Private function that escapes a value, and its semicolons too if it's a list item. Spaces are only escaped at the
+///start and end, where they would be trimmed.
+fn escape(value: &str, list: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    let content = value.trim_matches(' ');
+    let leading = value.len() - value.trim_start_matches(' ').len();
+    out.push_str(&"\\s".repeat(leading));
+    for c in content.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            ';' if list => out.push_str("\\;"),
+            c => out.push(c),
+        }
+    }
+    if !content.is_empty() {
+        let trailing = value.len() - value.trim_end_matches(' ').len();
+        out.push_str(&"\\s".repeat(trailing));
+    }
+    out
+}
@@ -0,0 +1,211 @@
+//!The dialect module provides named presets of `IniDefault` for the ini flavours of common programs, so that files
+//!written for them are parsed (and written back) the way those programs do: with their comment symbols, delimiters,
+//!case handling, multiline values, repeated keys and escapes.
+//!See the [`Dialect`] documentation for more details.
+use crate::ini::{CaseFolding, CasePolicy, Escapes, Ini, IniDefault, KeyStrategy};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+///The ini flavours with a preset, each identified by a short name such as `git`.
+///
+///Presets only cover what `IniDefault` can express, so some features of these formats are left to the caller:
+///interpolation, git's subsections and includes, PHP's arrays and constants, backslash line continuations and the
+///list values and localized keys of desktop entries. The `systemd` and `desktop` modules implement the last two.
+///## Example
+///```rust
+///use configparser::dialect::Dialect;
+///use configparser::ini::Ini;
+///
+///let mut config = Ini::with_dialect(Dialect::Git);
+///config.read(String::from("[remote \"origin\"]\n\tfetch = +refs/heads/*\n\tfetch = +refs/tags/*\n")).unwrap();
+///let fetch = config.get("remote \"origin\"", "FETCH").unwrap();
+///assert_eq!(fetch.lines().count(), 2);
+///assert_eq!("git".parse::<Dialect>(), Ok(Dialect::Git));
+///```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Dialect {
+    ///Python's `configparser` with its default options: a `DEFAULT` section whose keys every section inherits,
+    ///case-sensitive sections, lowercased keys, indented multiline values, full-line comments only and an error for
+    ///repeated keys (`strict=True`).
+    Python,
+    ///PHP's `parse_ini_file()`: `;` comments (also inline), `=` only, case-sensitive names, double-quoted values and
+    ///the last of repeated keys wins.
+    Php,
+    ///The Windows profile API (`GetPrivateProfileString()`): full-line `;` comments only, `=` only,
+    ///case-insensitive names that keep their spelling and the first of repeated keys wins.
+    Windows,
+    ///git's config files: `#` and `;` comments (also inline), `=` only, case-insensitive section and key names,
+    ///double-quoted values with backslash escapes and repeated keys collected as multi-valued keys.
+    Git,
+    ///systemd unit files: full-line `#` and `;` comments only, `=` only, case-sensitive names and repeated keys
+    ///collected as lists.
+    Systemd,
+    ///MySQL's `my.cnf` option files: `#` inline comments, `=` only, case-insensitive group names, quoted values with
+    ///backslash escapes and the last of repeated options wins.
+    MySql,
+    ///Samba's `smb.conf`: full-line `#` and `;` comments only, `=` only, case-insensitive names and a `global`
+    ///section whose parameters every share inherits.
+    Samba,
+    ///freedesktop.org desktop entries (`.desktop` files): full-line `#` comments only, `=` only, case-sensitive
+    ///names, backslash escapes, only `true` and `false` as booleans and an error for repeated keys.
+    Desktop,
+}
+
+impl Dialect {
+    ///Every dialect, in the order of the enum.
+    pub const ALL: [Dialect; 8] = [
+        Dialect::Python,
+        Dialect::Php,
+        Dialect::Windows,
+        Dialect::Git,
+        Dialect::Systemd,
+        Dialect::MySql,
+        Dialect::Samba,
+        Dialect::Desktop,
+    ];
+
+    ///Returns the short name of the dialect, as accepted by `FromStr`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Dialect::Python => "python",
+            Dialect::Php => "php",
+            Dialect::Windows => "windows",
+            Dialect::Git => "git",
+            Dialect::Systemd => "systemd",
+            Dialect::MySql => "mysql",
+            Dialect::Samba => "samba",
+            Dialect::Desktop => "desktop",
+        }
+    }
+
+    ///Returns the defaults of the dialect, which can be adjusted further before creating an `Ini` with
+    ///`Ini::new_from_defaults()`.
+    ///## Example
+    ///```rust
+    ///use configparser::dialect::Dialect;
+    ///use configparser::ini::{Ini, KeyStrategy};
+    ///
+    ///let mut defaults = Dialect::Python.defaults();
+    ///assert_eq!(defaults.default_section, "DEFAULT");
+    ///defaults.duplicate_keys = KeyStrategy::Overwrite; // like `strict=False`
+    ///let mut config = Ini::new_from_defaults(defaults);
+    ///config.read(String::from("[DEFAULT]\nuser = root\n[Server]\nport = 22\nport = 2222")).unwrap();
+    ///assert_eq!(config.get("Server", "user").unwrap(), "root");
+    ///assert_eq!(config.get("Server", "PORT").unwrap(), "2222");
+    ///assert_eq!(config.get("server", "port"), None);
+    ///```
+    pub fn defaults(self) -> IniDefault {
+        let mut defaults = IniDefault {
+            delimiters: vec!['='],
+            ..Default::default()
+        };
+        match self {
+            Dialect::Python => {
+                defaults.default_section = String::from("DEFAULT");
+                defaults.delimiters = vec!['=', ':'];
+                defaults.enable_inline_comments = false;
+                defaults.section_case = CasePolicy::Sensitive;
+                defaults.multiline = true;
+                defaults.cascade_defaults = true;
+                defaults.duplicate_keys = KeyStrategy::Error;
+                defaults.boolean_values =
+                    boolean_values(&["1", "yes", "true", "on"], &["0", "no", "false", "off"]);
+            }
+            Dialect::Php => {
+                defaults.comment_symbols = vec![';'];
+                defaults.case_sensitive = true;
+                defaults.escapes = Escapes::Quoted;
+                defaults.boolean_values = boolean_values(
+                    &["1", "on", "yes", "true"],
+                    &["", "0", "off", "no", "false", "none"],
+                );
+            }
+            Dialect::Windows => {
+                defaults.comment_symbols = vec![';'];
+                defaults.enable_inline_comments = false;
+                defaults.section_case = CasePolicy::Preserve;
+                defaults.key_case = CasePolicy::Preserve;
+                defaults.duplicate_keys = KeyStrategy::KeepFirst;
+            }
+            Dialect::Git => {
+                defaults.case_folding = CaseFolding::Ascii;
+                defaults.duplicate_keys = KeyStrategy::Append;
+                defaults.escapes = Escapes::Quoted;
+                defaults.boolean_values = boolean_values(
+                    &["true", "yes", "on", "1"],
+                    &["false", "no", "off", "0", ""],
+                );
+            }
+            Dialect::Systemd => {
+                defaults.enable_inline_comments = false;
+                defaults.case_sensitive = true;
+                defaults.duplicate_keys = KeyStrategy::Append;
+            }
+            Dialect::MySql => {
+                defaults.inline_comment_symbols = Some(vec!['#']);
+                defaults.key_case = CasePolicy::Sensitive;
+                defaults.escapes = Escapes::Quoted;
+            }
+            Dialect::Samba => {
+                defaults.default_section = String::from("global");
+                defaults.enable_inline_comments = false;
+                defaults.cascade_defaults = true;
+            }
+            Dialect::Desktop => {
+                defaults.comment_symbols = vec!['#'];
+                defaults.enable_inline_comments = false;
+                defaults.case_sensitive = true;
+                defaults.duplicate_keys = KeyStrategy::Error;
+                defaults.escapes = Escapes::Backslash;
+                defaults.boolean_values = boolean_values(&["true"], &["false"]);
+            }
+        }
+        defaults
+    }
+}
+
+///Private function that builds the `boolean_values` of `IniDefault`.
+fn boolean_values(truthy: &[&str], falsy: &[&str]) -> HashMap<bool, Vec<String>> {
+    let strings = |values: &[&str]| values.iter().map(|&value| value.to_owned()).collect();
+    HashMap::from([(true, strings(truthy)), (false, strings(falsy))])
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    ///Parses the short name of a dialect, such as `mysql`.
+    fn from_str(s: &str) -> Result<Dialect, String> {
+        Dialect::ALL
+            .into_iter()
+            .find(|dialect| dialect.name() == s)
+            .ok_or_else(|| format!("unknown dialect {:?}", s))
+    }
+}
+
+impl Ini {
+    ///Creates a new `Ini` with the defaults of a dialect, exactly like `Ini::new_from_defaults(dialect.defaults())`.
+    ///## Example
+    ///```rust
+    ///use configparser::dialect::Dialect;
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::with_dialect(Dialect::Desktop);
+    ///config.read(String::from("[Desktop Entry]\nName=Text\\sEditor\nTerminal=false")).unwrap();
+    ///assert_eq!(config.get("Desktop Entry", "Name").unwrap(), "Text Editor");
+    ///assert_eq!(config.getbool("Desktop Entry", "Terminal").unwrap(), Some(false));
+    ///assert!(config.read(String::from("[Desktop Entry]\nName=A\nName=B")).is_err());
+    ///```
+    ///Returns the struct and stores it in the calling variable.
+    pub fn with_dialect(dialect: Dialect) -> Ini {
+        Ini::new_from_defaults(dialect.defaults())
+    }
+}
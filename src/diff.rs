@@ -0,0 +1,371 @@
+//!The diff module provides structural comparison of configurations. Unlike a text diff, an `IniDiff` only lists
+//!sections and keys whose presence or value changed, regardless of ordering, whitespace or comments.
+//!See the [`IniDiff`] documentation for more details.
+use crate::ini::Ini;
+
+use std::fmt;
+
+///What happened to a single key between two configurations. Values are stored as they were in each
+///configuration, so a key without a value is `None`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum KeyChange {
+    Added(Option<String>),
+    Removed(Option<String>),
+    Changed {
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+///The changes to a single key.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct KeyDiff {
+    pub key: String,
+    pub change: KeyChange,
+}
+
+///What happened to a section between two configurations.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum SectionChange {
+    ///The section only exists in the new configuration, all its keys are listed as added.
+    Added,
+    ///The section only exists in the old configuration, all its keys are listed as removed.
+    Removed,
+    ///The section exists in both configurations but some of its keys differ.
+    Modified,
+}
+
+///The changes to a single section.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct SectionDiff {
+    pub name: String,
+    pub change: SectionChange,
+    pub keys: Vec<KeyDiff>,
+}
+
+///The `IniDiff` struct lists the semantic differences between two `Ini` objects, as returned by `Ini::diff()`.
+///Sections and keys are matched using the case handling of the `Ini` the diff was created from, so with the
+///default case-insensitive constructor `[Section]` and `[section]` are the same section.
+///
+///Its `Display` implementation renders a human-readable summary in which unchanged section headers are indented,
+///added lines are prefixed with `+` and removed lines with `-`. An `IniDiff` can also be applied as a patch with
+///`Ini::patch()`.
+///## Example
+///```rust
+///use configparser::ini::Ini;
+///
+///let mut old = Ini::new();
+///old.read(String::from("[server]\nhost = localhost\nport = 80")).unwrap();
+///let mut new = Ini::new();
+///new.read(String::from("[SERVER]\nport = 8080\nhost = localhost\n[client]\nretries")).unwrap();
+///
+///let diff = old.diff(&new);
+///assert_eq!(diff.sections.len(), 2);
+///assert!(diff.to_string().contains("  [server]\n- port = 80\n+ port = 8080\n"));
+///assert!(diff.to_string().contains("+ [client]\n+ retries\n"));
+///```
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub struct IniDiff {
+    pub sections: Vec<SectionDiff>,
+}
+
+impl IniDiff {
+    ///Returns `true` if both configurations were equal.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    ///Returns the diff that undoes this one, i.e. the diff from the new configuration back to the old one.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut old = Ini::new();
+    ///old.read(String::from("[section]\nkey = old")).unwrap();
+    ///let mut new = old.clone();
+    ///new.setstr("section", "key", Some("new"));
+    ///
+    ///let mut config = new.clone();
+    ///config.patch(&old.diff(&new).reversed()).unwrap();
+    ///assert_eq!(config.get("section", "key").unwrap(), "old");
+    ///```
+    pub fn reversed(&self) -> IniDiff {
+        let sections = self
+            .sections
+            .iter()
+            .map(|section| SectionDiff {
+                name: section.name.clone(),
+                change: match section.change {
+                    SectionChange::Added => SectionChange::Removed,
+                    SectionChange::Removed => SectionChange::Added,
+                    SectionChange::Modified => SectionChange::Modified,
+                },
+                keys: section
+                    .keys
+                    .iter()
+                    .map(|key| KeyDiff {
+                        key: key.key.clone(),
+                        change: match &key.change {
+                            KeyChange::Added(value) => KeyChange::Removed(value.clone()),
+                            KeyChange::Removed(value) => KeyChange::Added(value.clone()),
+                            KeyChange::Changed { old, new } => KeyChange::Changed {
+                                old: new.clone(),
+                                new: old.clone(),
+                            },
+                        },
+                    })
+                    .collect(),
+            })
+            .collect();
+        IniDiff { sections }
+    }
+}
+
+///Private function that renders a key the way `Ini::writes()` would, with the sign in front of every line of a
+///multiline value.
+fn write_key(
+    f: &mut fmt::Formatter<'_>,
+    sign: char,
+    key: &str,
+    value: &Option<String>,
+) -> fmt::Result {
+    match value {
+        Some(value) => {
+            let mut lines = value.lines();
+            match lines.next().unwrap_or_default() {
+                // An empty value ends the line at the delimiter, like `Ini` writes it.
+                "" => writeln!(f, "{} {} =", sign, key)?,
+                first => writeln!(f, "{} {} = {}", sign, key, first)?,
+            }
+            for line in lines {
+                writeln!(f, "{}     {}", sign, line)?;
+            }
+            Ok(())
+        }
+        None => writeln!(f, "{} {}", sign, key),
+    }
+}
+
+impl fmt::Display for IniDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for section in self.sections.iter() {
+            let sign = match section.change {
+                SectionChange::Added => '+',
+                SectionChange::Removed => '-',
+                SectionChange::Modified => ' ',
+            };
+            writeln!(f, "{} [{}]", sign, section.name)?;
+            for key in section.keys.iter() {
+                match &key.change {
+                    KeyChange::Added(value) => write_key(f, '+', &key.key, value)?,
+                    KeyChange::Removed(value) => write_key(f, '-', &key.key, value)?,
+                    KeyChange::Changed { old, new } => {
+                        write_key(f, '-', &key.key, old)?;
+                        write_key(f, '+', &key.key, new)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Ini {
+    ///Compares the stored configuration with `other` and returns the sections and keys that were added, removed
+    ///or changed in `other`. Names are matched with the case handling of both objects (so a case-insensitive
+    ///`Ini` matches `[Section]` with `[section]`) and reported with the spelling of the object they came from.
+    ///
+    ///With the `indexmap` feature, sections and keys are listed in the order of `self` followed by additions in
+    ///the order of `other`, otherwise they are sorted by name.
+    ///## Example
+    ///```rust
+    ///use configparser::diff::{KeyChange, SectionChange};
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut old = Ini::new();
+    ///old.read(String::from("[section]\nkey = old")).unwrap();
+    ///let mut new = Ini::new();
+    ///new.read(String::from("[section]\nkey = new\n[added]")).unwrap();
+    ///
+    ///let diff = old.diff(&new);
+    ///assert_eq!(diff.sections.len(), 2);
+    ///let added = diff.sections.iter().find(|section| section.name == "added").unwrap();
+    ///assert_eq!(added.change, SectionChange::Added);
+    ///let changed = diff.sections.iter().find(|section| section.name == "section").unwrap();
+    ///assert_eq!(
+    ///    changed.keys[0].change,
+    ///    KeyChange::Changed { old: Some("old".to_owned()), new: Some("new".to_owned()) }
+    ///);
+    ///```
+    ///Returns an `IniDiff`, which is empty if both configurations are equal.
+    pub fn diff(&self, other: &Ini) -> IniDiff {
+        let old = self.get_map_ref();
+        let new = other.get_map_ref();
+        let mut sections = Vec::new();
+
+        for (name, old_secmap) in old.iter() {
+            let other_name = other.section_name(name);
+            let Some(new_secmap) = new.get(&other_name) else {
+                sections.push(SectionDiff {
+                    name: name.clone(),
+                    change: SectionChange::Removed,
+                    keys: old_secmap
+                        .iter()
+                        .map(|(key, value)| KeyDiff {
+                            key: key.clone(),
+                            change: KeyChange::Removed(value.clone()),
+                        })
+                        .collect(),
+                });
+                continue;
+            };
+            let mut keys = Vec::new();
+            for (key, old_val) in old_secmap.iter() {
+                let change = match new_secmap.get(&other.key_name(&other_name, key)) {
+                    None => KeyChange::Removed(old_val.clone()),
+                    Some(new_val) if new_val != old_val => KeyChange::Changed {
+                        old: old_val.clone(),
+                        new: new_val.clone(),
+                    },
+                    Some(_) => continue,
+                };
+                keys.push(KeyDiff {
+                    key: key.clone(),
+                    change,
+                });
+            }
+            for (key, new_val) in new_secmap.iter() {
+                if !old_secmap.contains_key(&self.key_name(name, key)) {
+                    keys.push(KeyDiff {
+                        key: key.clone(),
+                        change: KeyChange::Added(new_val.clone()),
+                    });
+                }
+            }
+            if !keys.is_empty() {
+                sections.push(SectionDiff {
+                    name: name.clone(),
+                    change: SectionChange::Modified,
+                    keys,
+                });
+            }
+        }
+
+        for (name, new_secmap) in new.iter() {
+            if !old.contains_key(&self.section_name(name)) {
+                sections.push(SectionDiff {
+                    name: name.clone(),
+                    change: SectionChange::Added,
+                    keys: new_secmap
+                        .iter()
+                        .map(|(key, value)| KeyDiff {
+                            key: key.clone(),
+                            change: KeyChange::Added(value.clone()),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        #[cfg(not(feature = "indexmap"))]
+        {
+            sections.sort_by(|a, b| a.name.cmp(&b.name));
+            for section in sections.iter_mut() {
+                section.keys.sort_by(|a, b| a.key.cmp(&b.key));
+            }
+        }
+        IniDiff { sections }
+    }
+
+    ///Applies an `IniDiff` to the stored configuration like a patch. Every change is checked before anything is
+    ///modified: removed and changed keys must still have their old value (or already have been removed or changed)
+    ///and added keys must be missing (or already have the added value). A removed section is only removed once
+    ///all of its keys are gone.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut old = Ini::new();
+    ///old.read(String::from("[server]\nport = 80")).unwrap();
+    ///let mut new = Ini::new();
+    ///new.read(String::from("[server]\nport = 8080\n[client]\nretries = 3")).unwrap();
+    ///
+    ///let mut staging = Ini::new();
+    ///staging.read(String::from("[server]\nport = 80\nhost = staging")).unwrap();
+    ///staging.patch(&old.diff(&new)).unwrap();
+    ///assert_eq!(staging.get("server", "port").unwrap(), "8080");
+    ///assert_eq!(staging.get("server", "host").unwrap(), "staging");
+    ///assert_eq!(staging.get("client", "retries").unwrap(), "3");
+    ///
+    ///let mut production = Ini::new();
+    ///production.read(String::from("[server]\nport = 443")).unwrap();
+    ///assert!(production.patch(&old.diff(&new)).is_err());
+    ///```
+    ///Returns `Ok(())` if the diff was applied or else `Err(error_string)`, in which case nothing was changed.
+    pub fn patch(&mut self, diff: &IniDiff) -> Result<(), String> {
+        for section in diff.sections.iter() {
+            let name = self.section_name(&section.name);
+            for key in section.keys.iter() {
+                let current = self
+                    .get_map_ref()
+                    .get(&name)
+                    .and_then(|secmap| secmap.get(&self.key_name(&name, &key.key)));
+                let applies = match &key.change {
+                    KeyChange::Added(value) | KeyChange::Removed(value) => {
+                        current.is_none_or(|current| current == value)
+                    }
+                    KeyChange::Changed { old, new } => {
+                        current.is_some_and(|current| current == old || current == new)
+                    }
+                };
+                if !applies {
+                    return Err(format!(
+                        "Unable to apply diff at {}:{}: found {}",
+                        section.name,
+                        key.key,
+                        match current {
+                            Some(Some(value)) => format!("value {:?}", value),
+                            Some(None) => String::from("no value"),
+                            None => String::from("no key"),
+                        }
+                    ));
+                }
+            }
+        }
+
+        for section in diff.sections.iter() {
+            let name = self.section_name(&section.name);
+            for key in section.keys.iter() {
+                match &key.change {
+                    KeyChange::Added(value) | KeyChange::Changed { new: value, .. } => {
+                        let key_name = self.key_name(&name, &key.key);
+                        self.get_mut_map()
+                            .entry(name.clone())
+                            .or_default()
+                            .insert(key_name, value.clone());
+                    }
+                    KeyChange::Removed(_) => {
+                        self.remove_key(&section.name, &key.key);
+                    }
+                }
+            }
+            match section.change {
+                SectionChange::Added => {
+                    self.get_mut_map().entry(name).or_default();
+                }
+                SectionChange::Removed => {
+                    if self.get_mut_map().get(&name).is_some_and(|s| s.is_empty()) {
+                        self.remove_section(&section.name);
+                    }
+                }
+                SectionChange::Modified => {}
+            }
+        }
+        Ok(())
+    }
+}
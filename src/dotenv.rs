@@ -0,0 +1,376 @@
+//!The dotenv module reads `.env` files into a section of an `Ini` and writes sections back in that syntax, so that
+//!local overrides kept as environment files can be used with the typed getters like any other configuration.
+//!See [`Ini::read_dotenv()`] for more details.
+use crate::ini::{Ini, LINE_ENDING};
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap as Map;
+#[cfg(not(feature = "indexmap"))]
+use std::collections::HashMap as Map;
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+///Private enum of the ways a value can be quoted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Quote {
+    None,
+    Single,
+    Double,
+}
+
+///Private struct of an assignment found in a `.env` file, with its value as written between the quotes.
+#[derive(Debug)]
+struct Assignment {
+    key: String,
+    value: Option<(Quote, String)>,
+}
+
+impl Ini {
+    ///Reads the text of a `.env` file into a section, adding its variables to the section or overwriting them. Each
+    ///line holds a `KEY=value` assignment, optionally prefixed with `export `, and lines starting with `#` are
+    ///comments. Values can be:
+    ///- unquoted, where a `#` after whitespace starts a comment and surrounding whitespace is trimmed,
+    ///- in single quotes, which are taken literally and may span several lines,
+    ///- in double quotes, which may span several lines and understand the escapes `\n`, `\t`, `\r`, `\"`, `\\` and `\$`.
+    ///
+    ///Unquoted and double-quoted values expand `$VAR` and `${VAR}`, as well as `${VAR:-default}` (used if the variable
+    ///is unset or empty) and `${VAR-default}` (used if it is unset). Variables are looked up in the section, which
+    ///includes those of earlier lines, and then in the environment of the process; unset variables expand to nothing.
+    ///A line with just a key stores the key without a value. Keys are stored with the case handling of the `Ini`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new_cs();
+    ///config.read_dotenv(String::from(
+    ///    "# local overrides\nexport DB_HOST=localhost\nDB_PORT=5433 # not the default\n\
+    ///     DB_URL=\"postgres://${DB_HOST}:$DB_PORT/app\"\nGREETING='Hello, $USER'\n",
+    ///), "env").unwrap();
+    ///assert_eq!(config.getuint("env", "DB_PORT").unwrap(), Some(5433));
+    ///assert_eq!(config.get("env", "DB_URL").unwrap(), "postgres://localhost:5433/app");
+    ///assert_eq!(config.get("env", "GREETING").unwrap(), "Hello, $USER");
+    ///```
+    ///Returns `Ok(map)` with a clone of the stored `Map` or an `Err(error_string)` if the text can't be parsed, in
+    ///which case the configuration is unchanged.
+    pub fn read_dotenv(
+        &mut self,
+        input: String,
+        section: &str,
+    ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        for assignment in parse_dotenv(&input)? {
+            let value = assignment.value.map(|(quote, raw)| match quote {
+                Quote::Single => raw,
+                _ => self.expand(section, &raw, quote == Quote::Double),
+            });
+            self.setstr(section, &assignment.key, value.as_deref());
+        }
+        Ok(self.get_map_ref().clone())
+    }
+
+    ///Loads a `.env` file into a section, see `read_dotenv()`.
+    ///Returns `Ok(map)` with a clone of the stored `Map` or an `Err(error_string)` if the file can't be read or parsed.
+    pub fn load_dotenv<T: AsRef<Path>>(
+        &mut self,
+        path: T,
+        section: &str,
+    ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+        self.read_dotenv(text, section)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))
+    }
+
+    ///Private function that resolves the escapes (in double-quoted values) and the variables of a value.
+    fn expand(&self, section: &str, raw: &str, escapes: bool) -> String {
+        let lookup = |name: &str| self.get(section, name).or_else(|| std::env::var(name).ok());
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+            match c {
+                '\\' if escapes => {
+                    let Some(escaped) = rest.chars().next() else {
+                        out.push('\\');
+                        break;
+                    };
+                    rest = &rest[escaped.len_utf8()..];
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        '"' | '\\' | '$' => out.push(escaped),
+                        other => {
+                            out.push('\\');
+                            out.push(other);
+                        }
+                    }
+                }
+                '$' if rest.starts_with('{') => match rest.find('}') {
+                    Some(end) => {
+                        let expression = &rest[1..end];
+                        rest = &rest[end + 1..];
+                        let (name, default) = match expression.split_once('-') {
+                            Some((name, default)) => (name, Some(default)),
+                            None => (expression, None),
+                        };
+                        let value = match (name.strip_suffix(':'), default) {
+                            (Some(name), Some(default)) => lookup(name)
+                                .filter(|value| !value.is_empty())
+                                .unwrap_or_else(|| default.to_owned()),
+                            (None, Some(default)) => {
+                                lookup(name).unwrap_or_else(|| default.to_owned())
+                            }
+                            _ => lookup(name).unwrap_or_default(),
+                        };
+                        out.push_str(&value);
+                    }
+                    // An unterminated `${` is kept as written.
+                    None => out.push('$'),
+                },
+                '$' if rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => {
+                    let end = rest
+                        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                        .unwrap_or(rest.len());
+                    out.push_str(&lookup(&rest[..end]).unwrap_or_default());
+                    rest = &rest[end..];
+                }
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    ///Writes the keys of a section as a `.env` file. Keys are written as stored, with characters other than ASCII
+    ///letters, digits and `_` replaced by `_`, and keys without a value are written without `=`. Values that aren't
+    ///made of letters, digits and `_./:,@%+-` only are put in single quotes, or in double quotes with escapes if they
+    ///contain a single quote, so that they read back unchanged.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new_cs();
+    ///config.set("env", "NAME", Some(String::from("my app")));
+    ///assert_eq!(config.writes_dotenv("env").unwrap(), "NAME='my app'\n");
+    ///assert_eq!(config.writes_dotenv("missing"), None);
+    ///```
+    ///Returns `Some(text)`, or `None` if the section doesn't exist.
+    pub fn writes_dotenv(&self, section: &str) -> Option<String> {
+        let keys = self.section(section)?;
+        let mut out = String::new();
+        for (key, value) in keys.iter() {
+            write_assignment(&mut out, &env_name(key, false), value);
+        }
+        Some(out)
+    }
+
+    ///Writes the whole configuration as a `.env` file, prefixing the keys of each section with the section name and
+    ///`_`, so that the key `host` of the section `db` becomes `DB_HOST`. Keys of the default section get no prefix.
+    ///Names are uppercased and written like in `writes_dotenv()`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read(String::from("debug = true\n[db]\nhost = db.local\npassword = it's secret")).unwrap();
+    ///let env = config.writes_dotenv_prefixed();
+    ///assert!(env.contains("DEBUG=true\n"));
+    ///assert!(env.contains("DB_HOST=db.local\n"));
+    ///assert!(env.contains("DB_PASSWORD=\"it's secret\"\n"));
+    ///```
+    ///Returns a `String` with the file text.
+    pub fn writes_dotenv_prefixed(&self) -> String {
+        let default_section = self.default_section_name();
+        let map = self.get_map_ref();
+        let first = map.get_key_value(&default_section);
+        let rest = map
+            .iter()
+            .filter(|(section, _)| **section != default_section);
+        let mut out = String::new();
+        for (section, keys) in first.into_iter().chain(rest) {
+            for (key, value) in keys {
+                let name = if *section == default_section {
+                    env_name(key, true)
+                } else {
+                    env_name(&format!("{}_{}", section, key), true)
+                };
+                write_assignment(&mut out, &name, value.as_deref());
+            }
+        }
+        out
+    }
+
+    ///Writes a section to a `.env` file, see `writes_dotenv()`.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not; writing a missing
+    ///section fails with `ErrorKind::NotFound`.
+    pub fn write_dotenv<T: AsRef<Path>>(&self, path: T, section: &str) -> std::io::Result<()> {
+        let text = self.writes_dotenv(section).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("section {} doesn't exist", section),
+            )
+        })?;
+        fs::write(path.as_ref(), text)
+    }
+}
+
+///Private function that splits `.env` text into its assignments.
+fn parse_dotenv(input: &str) -> Result<Vec<Assignment>, String> {
+    let mut assignments = Vec::new();
+    let mut lines = input.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        // The 1-based column of a part of the line.
+        let column = |part: &str| {
+            line[..part.as_ptr() as usize - line.as_ptr() as usize]
+                .chars()
+                .count()
+                + 1
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let statement = trimmed
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .map_or(trimmed, str::trim_start);
+        let (key, value) = match statement.split_once('=') {
+            Some((key, value)) => (key.trim_end(), Some(value.trim_start())),
+            None => (statement, None),
+        };
+        let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-');
+        if key.is_empty()
+            || key.starts_with(|c: char| c.is_ascii_digit())
+            || !key.chars().all(valid)
+        {
+            return Err(format!(
+                "line {}:{}: Invalid variable name {:?}",
+                line_number,
+                column(key),
+                key
+            ));
+        }
+        let value = match value {
+            None => None,
+            Some(value) if value.starts_with(['\'', '"']) => {
+                let quote = if value.starts_with('"') {
+                    Quote::Double
+                } else {
+                    Quote::Single
+                };
+                let mut raw = value[1..].to_owned();
+                let end = loop {
+                    if let Some(end) = closing_quote(&raw, quote) {
+                        break end;
+                    }
+                    let (_, next) = lines.next().ok_or_else(|| {
+                        format!(
+                            "line {}:{}: Unterminated quoted value",
+                            line_number,
+                            column(value)
+                        )
+                    })?;
+                    raw.push('\n');
+                    raw.push_str(next);
+                };
+                let rest = raw[end + 1..].trim_start();
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    // Continuation lines are joined as they are, so positions in `raw` map back to the input.
+                    let at = raw.len() - rest.len();
+                    let (line_number, column) = match raw[..at].rfind('\n') {
+                        Some(newline) => (
+                            line_number + raw[..at].matches('\n').count(),
+                            raw[newline + 1..at].chars().count() + 1,
+                        ),
+                        None => (line_number, column(value) + 1 + raw[..at].chars().count()),
+                    };
+                    return Err(format!(
+                        "line {}:{}: Unexpected characters after the closing quote",
+                        line_number, column
+                    ));
+                }
+                raw.truncate(end);
+                Some((quote, raw))
+            }
+            Some(value) => {
+                let comment = value
+                    .char_indices()
+                    .find(|&(index, c)| c == '#' && value[..index].ends_with([' ', '\t']))
+                    .map_or(value.len(), |(index, _)| index);
+                Some((Quote::None, value[..comment].trim_end().to_owned()))
+            }
+        };
+        assignments.push(Assignment {
+            key: key.to_owned(),
+            value,
+        });
+    }
+    Ok(assignments)
+}
+
+///Private function that finds the closing quote of a quoted value, skipping escaped quotes in double quotes.
+fn closing_quote(raw: &str, quote: Quote) -> Option<usize> {
+    if quote == Quote::Single {
+        return raw.find('\'');
+    }
+    let mut escaped = false;
+    for (index, c) in raw.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+///Private function that turns a key into a variable name, replacing other characters than ASCII letters, digits and
+///`_` with `_` and uppercasing it if asked to.
+fn env_name(key: &str, uppercase: bool) -> String {
+    key.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c,
+            _ => '_',
+        })
+        .map(|c| if uppercase { c.to_ascii_uppercase() } else { c })
+        .collect()
+}
+
+///Private function that writes an assignment, quoting the value if needed.
+fn write_assignment(out: &mut String, name: &str, value: Option<&str>) {
+    out.push_str(name);
+    if let Some(value) = value {
+        out.push('=');
+        out.push_str(&quote(value));
+    }
+    out.push_str(LINE_ENDING);
+}
+
+///Private function that quotes a value unless it only has characters that are safe without quotes.
+fn quote(value: &str) -> Cow<'_, str> {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_./:,@%+-".contains(c);
+    if value.chars().all(safe) {
+        Cow::Borrowed(value)
+    } else if !value.contains('\'') {
+        Cow::Owned(format!("'{}'", value))
+    } else {
+        let mut quoted = String::from("\"");
+        for c in value.chars() {
+            match c {
+                '\\' | '"' | '$' => {
+                    quoted.push('\\');
+                    quoted.push(c);
+                }
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        Cow::Owned(quoted)
+    }
+}
@@ -0,0 +1,567 @@
+//!The edit module provides lossless editing of ini-syntax text. Unlike `Ini`, which only keeps the parsed values and
+//!rewrites the whole file when writing it, a `Document` changes the lines of the edited keys and sections only, so
+//!comments, blank lines, spacing and the order of everything else survive.
+//!See the [`Document`] documentation for more details.
+use crate::ini::{Annotation, Escapes, Ini, IniDefault, LINE_ENDING, WriteOptions, escape};
+use crate::parser::{EventKind, Lexer, StrEvents};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+///The `Document` struct holds ini-syntax text and edits it in place. Sections and keys are matched with the case
+///handling of the given defaults, and keys before the first section header belong to the default section.
+///
+///Setting a key changes the value of its last occurrence (which is the one `Ini` reads), keeping the key, the
+///delimiter, the spacing around it and any inline comment. New keys are added after the last key of their section,
+///using the spacing around the delimiter of a neighbouring key, and new sections are added at the end.
+///## Example
+///```rust
+///use configparser::edit::Document;
+///use configparser::ini::IniDefault;
+///
+///let text = "; managed by ops\n[server]\nport = 80 ; the public port\n\n[log]\nlevel: info\n";
+///let mut document = Document::new(text.to_owned(), IniDefault::default()).unwrap();
+///document.set("Server", "port", Some("8080")).unwrap();
+///document.set("server", "host", Some("example.com")).unwrap();
+///document.set("cache", "enabled", None).unwrap();
+///assert!(document.remove_key("log", "LEVEL").unwrap());
+///assert_eq!(
+///    document.text(),
+///    "; managed by ops\n[server]\nport = 8080 ; the public port\nhost = example.com\n\n[log]\n\n[cache]\nenabled\n"
+///);
+///```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Document {
+    text: String,
+    defaults: IniDefault,
+}
+
+///Private struct describing a section header or a key found in the text.
+#[derive(Debug)]
+struct Entry {
+    section: String,
+    ///`None` for a section header.
+    key: Option<String>,
+    ///The 0-based lines of the entry, including multiline continuations.
+    lines: Range<usize>,
+    ///The byte offset right after the key.
+    key_end: usize,
+    ///The byte range of the value on the first line, if the key has a delimiter.
+    value: Option<Range<usize>>,
+}
+
+impl Document {
+    ///Creates a document from ini-syntax text, which is parsed with the given defaults.
+    ///Returns `Ok(document)` or an `Err(error_string)` if the text can't be parsed.
+    pub fn new(text: String, defaults: IniDefault) -> Result<Document, String> {
+        let document = Document { text, defaults };
+        document.entries()?;
+        Ok(document)
+    }
+
+    ///Loads a document from a file, see `new()`.
+    ///Returns `Ok(document)` or an `Err(error_string)` if the file can't be read or parsed.
+    pub fn load<T: AsRef<Path>>(path: T, defaults: IniDefault) -> Result<Document, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+        Document::new(text, defaults)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))
+    }
+
+    ///Returns the current text of the document.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    ///Writes the current text of the document to a file.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not.
+    pub fn write<T: AsRef<Path>>(&self, path: T) -> std::io::Result<()> {
+        fs::write(path.as_ref(), &self.text)
+    }
+
+    ///Parses the current text of the document into an `Ini` with the defaults of the document.
+    ///Returns `Ok(config)` or an `Err(error_string)` if the text can't be parsed.
+    pub fn to_ini(&self) -> Result<Ini, String> {
+        let mut config = Ini::new_from_defaults(self.defaults.clone());
+        config.read_ref(self.text.clone())?;
+        Ok(config)
+    }
+
+    ///Returns the names of all sections in the order they first appear, spelled as they are first written.
+    ///The default section is only listed if it has a header or any keys.
+    ///Returns `Ok(sections)` or an `Err(error_string)` if the text can't be parsed.
+    pub fn sections(&self) -> Result<Vec<String>, String> {
+        Ok(self.sections_in(&self.entries()?))
+    }
+
+    ///Returns `Ok(true)` if the section has a header or any keys, or an `Err(error_string)` if the text can't be
+    ///parsed.
+    pub fn has_section(&self, section: &str) -> Result<bool, String> {
+        Ok(self
+            .entries()?
+            .iter()
+            .any(|entry| self.in_section(&entry.section, section)))
+    }
+
+    ///Returns the keys of a section in the order they first appear, spelled as they are first written.
+    ///Returns `Ok(keys)` or an `Err(error_string)` if the text can't be parsed.
+    pub fn keys(&self, section: &str) -> Result<Vec<String>, String> {
+        Ok(self.keys_in(&self.entries()?, section))
+    }
+
+    ///Sets the value of a key, adding the key (and its section) if it doesn't exist yet. A `None` value leaves the
+    ///key without a delimiter and value, and values with several lines are written as multiline continuations.
+    ///With escapes, values are escaped like `Ini` writes them. Without, values that wouldn't read back as they are,
+    ///like ones with surrounding whitespace or inline comment symbols, are rejected.
+    ///Returns `Ok(())` or an `Err(error_string)` if the section, key or value can't be written.
+    pub fn set(&mut self, section: &str, key: &str, value: Option<&str>) -> Result<(), String> {
+        self.check_names(section, key)?;
+        let value = value
+            .map(|value| self.writable_value(section, key, value))
+            .transpose()?;
+        let value = value.as_deref();
+        let lines: Vec<&str> = value
+            .map(|value| value.lines().collect())
+            .unwrap_or_default();
+        if lines.len() > 1 && !self.defaults.multiline {
+            return Err(format!(
+                "Unable to set {}:{}: values with several lines need multiline support",
+                section, key
+            ));
+        }
+        let newline = self.newline();
+        let continuation: String = lines
+            .iter()
+            .skip(1)
+            .map(|line| {
+                if line.trim().is_empty() {
+                    newline.to_owned()
+                } else {
+                    format!("    {}{}", line.trim(), newline)
+                }
+            })
+            .collect();
+        let first = lines.first().map_or("", |line| line.trim());
+
+        let entries = self.entries()?;
+        let starts = self.line_starts();
+        let existing = entries
+            .iter()
+            .rev()
+            .find(|entry| self.is_key(entry, section, key));
+        if let Some(entry) = existing {
+            // Replace the continuation lines first, so the offsets on the first line stay valid.
+            let rest = starts[entry.lines.start + 1]..starts[entry.lines.end];
+            if rest.start == self.text.len() && !continuation.is_empty() {
+                self.insert_line(rest.start, &continuation);
+            } else {
+                self.text.replace_range(rest, &continuation);
+            }
+            match (&entry.value, value) {
+                (Some(range), Some(_)) => self.text.replace_range(range.clone(), first),
+                (Some(range), None) => self.text.replace_range(entry.key_end..range.end, ""),
+                (None, Some(_)) => {
+                    let separator = self.separator(&entries, section);
+                    self.text
+                        .insert_str(entry.key_end, &format!("{}{}", separator, first));
+                }
+                (None, None) => {}
+            }
+            return Ok(());
+        }
+
+        let mut line = key.to_owned();
+        if value.is_some() {
+            line.push_str(&self.separator(&entries, section));
+            line.push_str(first);
+        }
+        line.push_str(newline);
+        line.push_str(&continuation);
+
+        let last = entries
+            .iter()
+            .filter(|entry| self.in_section(&entry.section, section))
+            .map(|entry| entry.lines.end)
+            .max();
+        match last {
+            Some(end) => self.insert_line(starts[end], &line),
+            // A missing default section goes before the first section header.
+            None if self.in_section(&self.defaults.default_section, section) => {
+                self.insert_line(0, &line)
+            }
+            None => {
+                let mut text = String::new();
+                if !self.text.is_empty() {
+                    text.push_str(newline);
+                }
+                text.push('[');
+                text.push_str(section);
+                text.push(']');
+                text.push_str(newline);
+                text.push_str(&line);
+                self.insert_line(self.text.len(), &text);
+            }
+        }
+        Ok(())
+    }
+
+    ///Removes every occurrence of a key from a section.
+    ///Returns `Ok(true)` if the key was found, or an `Err(error_string)` if the text can't be parsed.
+    pub fn remove_key(&mut self, section: &str, key: &str) -> Result<bool, String> {
+        let entries = self.entries()?;
+        let lines: Vec<Range<usize>> = entries
+            .iter()
+            .filter(|entry| self.is_key(entry, section, key))
+            .map(|entry| entry.lines.clone())
+            .collect();
+        Ok(self.remove_lines(lines))
+    }
+
+    ///Removes every occurrence of a section, from its header up to the next section header. For the default
+    ///section, the keys before the first section header are removed as well.
+    ///Returns `Ok(true)` if the section was found, or an `Err(error_string)` if the text can't be parsed.
+    pub fn remove_section(&mut self, section: &str) -> Result<bool, String> {
+        let entries = self.entries()?;
+        let line_count = self.line_starts().len() - 1;
+        let mut lines = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            if !self.in_section(&entry.section, section) {
+                continue;
+            }
+            if entry.key.is_none() {
+                let end = entries[i + 1..]
+                    .iter()
+                    .find(|next| next.key.is_none())
+                    .map_or(line_count, |next| next.lines.start);
+                lines.push(entry.lines.start..end);
+            } else if lines
+                .last()
+                .is_none_or(|last| last.end <= entry.lines.start)
+            {
+                lines.push(entry.lines.clone());
+            }
+        }
+        Ok(self.remove_lines(lines))
+    }
+
+    ///Reformats the whole document like `Ini::pretty_writes()`, keeping its comments. Comment lines stay before the
+    ///section header or key that follows them (or at the end, after the last key), and inline comments stay at the
+    ///end of their line. Sections and keys keep the order they first appear in, or are sorted by name if `sort` is
+    ///`true`; repeated sections and keys are merged the way `Ini` reads them. Blank lines are replaced by the
+    ///spacing of `write_options`.
+    ///## Example
+    ///```rust
+    ///use configparser::edit::Document;
+    ///use configparser::ini::{CasePolicy, IniDefault, WriteOptions};
+    ///
+    ///let mut defaults = IniDefault::default();
+    ///defaults.key_case = CasePolicy::Preserve;
+    ///let text = "[server]\n# where to listen\nHost=localhost\n\n\nport :80 ; public\n";
+    ///let mut document = Document::new(text.to_owned(), defaults).unwrap();
+    ///let mut write_options = WriteOptions::new_with_params(true, 4, 1);
+    ///write_options.align_delimiters = true;
+    ///document.format(&write_options, false).unwrap();
+    ///assert_eq!(
+    ///    document.text(),
+    ///    "[server]\n# where to listen\nHost = localhost\nport = 80 ; public\n"
+    ///        .replace('\n', if cfg!(windows) { "\r\n" } else { "\n" })
+    ///);
+    ///```
+    ///Returns `Ok(())` or an `Err(error_string)` if the text can't be parsed.
+    pub fn format(&mut self, write_options: &WriteOptions, sort: bool) -> Result<(), String> {
+        let config = self.to_ini()?;
+        let default_section = config.default_section_name();
+
+        // Attach every comment to the section header or key after it, and inline comments to their line.
+        let mut annotations: HashMap<(String, Option<String>), Annotation> = HashMap::new();
+        let mut pending = Vec::new();
+        let mut current: Option<(String, Option<String>)> = None;
+        let mut current_line = 0;
+        let mut section = default_section.clone();
+        for event in StrEvents::new(&self.text, Lexer::from_defaults(&self.defaults)) {
+            let event = event?;
+            let id = match event.kind {
+                EventKind::SectionStart { name } => {
+                    section = config.section_name(&name);
+                    (section.clone(), None)
+                }
+                EventKind::KeyValue { key, .. } => {
+                    (section.clone(), Some(config.key_name(&section, &key)))
+                }
+                EventKind::Comment { text } => {
+                    match &current {
+                        Some(id) if event.line == current_line => {
+                            let inline =
+                                &mut annotations.entry(id.clone()).or_default().inline_comment;
+                            match inline {
+                                Some(inline) => {
+                                    inline.push(' ');
+                                    inline.push_str(&text);
+                                }
+                                None => *inline = Some(text.into_owned()),
+                            }
+                        }
+                        _ => pending.push(text.into_owned()),
+                    }
+                    continue;
+                }
+                EventKind::Continuation { .. } => {
+                    current_line = event.line;
+                    continue;
+                }
+                _ => continue,
+            };
+            annotations
+                .entry(id.clone())
+                .or_default()
+                .comments
+                .append(&mut pending);
+            current = Some(id);
+            current_line = event.line;
+        }
+
+        let entries = self.entries()?;
+        let mut order: Vec<(String, Vec<String>)> = self
+            .sections_in(&entries)
+            .iter()
+            .map(|section| {
+                let name = if self.in_section(section, &self.defaults.default_section) {
+                    default_section.clone()
+                } else {
+                    config.section_name(section)
+                };
+                let keys = self
+                    .keys_in(&entries, section)
+                    .iter()
+                    .map(|key| config.key_name(&name, key))
+                    .collect();
+                (name, keys)
+            })
+            .collect();
+        if sort {
+            order.sort();
+            for (_, keys) in order.iter_mut() {
+                keys.sort();
+            }
+        }
+
+        let mut text = config.unparse_annotated(write_options, Some(&order), &|section, key| {
+            let id = (section.to_owned(), key.map(str::to_owned));
+            annotations.get(&id).cloned().unwrap_or_default()
+        });
+        for comment in pending {
+            text.push_str(&comment);
+            text.push_str(LINE_ENDING);
+        }
+        self.text = text;
+        Ok(())
+    }
+
+    ///Private function that returns the sections of the entries, see `sections()`.
+    fn sections_in(&self, entries: &[Entry]) -> Vec<String> {
+        let mut sections: Vec<String> = Vec::new();
+        for entry in entries {
+            if !sections
+                .iter()
+                .any(|section| self.defaults.same_section(section, &entry.section))
+            {
+                sections.push(entry.section.clone());
+            }
+        }
+        sections
+    }
+
+    ///Private function that returns the keys of a section in the entries, see `keys()`.
+    fn keys_in(&self, entries: &[Entry], section: &str) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for entry in entries {
+            if let Some(key) = entry
+                .key
+                .as_ref()
+                .filter(|_| self.in_section(&entry.section, section))
+                && !keys.iter().any(|k| self.defaults.same_key(k, key))
+            {
+                keys.push(key.clone());
+            }
+        }
+        keys
+    }
+
+    ///Private function that returns whether an entry is the key in the section.
+    fn is_key(&self, entry: &Entry, section: &str, key: &str) -> bool {
+        self.in_section(&entry.section, section)
+            && entry
+                .key
+                .as_deref()
+                .is_some_and(|k| self.defaults.same_key(k, key))
+    }
+
+    ///Private function that returns whether a section name refers to the section.
+    fn in_section(&self, name: &str, section: &str) -> bool {
+        self.defaults.same_section(name, section)
+    }
+
+    ///Private function that rejects names which can't be written as they are.
+    fn check_names(&self, section: &str, key: &str) -> Result<(), String> {
+        if section.trim() != section || section.contains(['\n', '\r', ']']) {
+            return Err(format!("Unable to write section name {:?}", section));
+        }
+        if key.is_empty()
+            || key.trim() != key
+            || key.contains(['\n', '\r'])
+            || key.contains(&self.defaults.delimiters[..])
+            || key.starts_with('[')
+            || key.starts_with(&self.defaults.comment_symbols[..])
+        {
+            return Err(format!("Unable to write key {:?}", key));
+        }
+        Ok(())
+    }
+
+    ///Private function that returns a value as it has to be written to read back as it is. With escapes, the value
+    ///is escaped like `Ini` writes it; without, values with surrounding whitespace or inline comment symbols are
+    ///rejected.
+    fn writable_value<'a>(
+        &self,
+        section: &str,
+        key: &str,
+        value: &'a str,
+    ) -> Result<Cow<'a, str>, String> {
+        let defaults = &self.defaults;
+        let inline_comment_symbols = match (
+            &defaults.inline_comment_symbols,
+            defaults.enable_inline_comments,
+        ) {
+            (_, false) => &[][..],
+            (Some(symbols), true) => &symbols[..],
+            (None, true) => &defaults.comment_symbols[..],
+        };
+        if defaults.escapes != Escapes::None {
+            return Ok(escape(value, defaults.escapes, inline_comment_symbols));
+        }
+        let unreadable = value.trim() != value
+            || value.contains(|c| c == '\r' || inline_comment_symbols.contains(&c))
+            || value.split('\n').enumerate().any(|(idx, line)| {
+                line.trim() != line
+                    || (idx > 0
+                        && (line.starts_with('[')
+                            || line.starts_with(&defaults.comment_symbols[..])))
+            });
+        if unreadable {
+            return Err(format!(
+                "Unable to set {}:{}: value {:?} wouldn't read back as written",
+                section, key, value
+            ));
+        }
+        Ok(Cow::Borrowed(value))
+    }
+
+    ///Private function that returns the line ending used by the text.
+    fn newline(&self) -> &'static str {
+        if self.text.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    ///Private function that returns the text between the key and the value of a key in the section, or of any
+    ///key if the section has none.
+    fn separator(&self, entries: &[Entry], section: &str) -> String {
+        let with_value = |entry: &&Entry| entry.key.is_some() && entry.value.is_some();
+        entries
+            .iter()
+            .rev()
+            .filter(with_value)
+            .find(|entry| self.in_section(&entry.section, section))
+            .or_else(|| entries.iter().find(with_value))
+            .map_or_else(
+                || {
+                    self.defaults
+                        .delimiters
+                        .first()
+                        .copied()
+                        .unwrap_or('=')
+                        .to_string()
+                },
+                |entry| {
+                    let value = entry.value.as_ref().unwrap();
+                    self.text[entry.key_end..value.start].to_owned()
+                },
+            )
+    }
+
+    ///Private function that inserts whole lines at a byte offset at the start of a line (or the end of the text).
+    fn insert_line(&mut self, offset: usize, text: &str) {
+        if offset == self.text.len() && !self.text.is_empty() && !self.text.ends_with('\n') {
+            let newline = self.newline();
+            self.text.push_str(newline);
+            self.text.push_str(text);
+        } else {
+            self.text.insert_str(offset, text);
+        }
+    }
+
+    ///Private function that removes ranges of lines, which must be sorted and not overlap.
+    ///Returns `true` if there were any lines to remove.
+    fn remove_lines(&mut self, lines: Vec<Range<usize>>) -> bool {
+        let starts = self.line_starts();
+        for range in lines.iter().rev() {
+            self.text
+                .replace_range(starts[range.start]..starts[range.end], "");
+        }
+        !lines.is_empty()
+    }
+
+    ///Private function that returns the byte offset of the start of every line, followed by the length of the text.
+    fn line_starts(&self) -> Vec<usize> {
+        let mut starts = vec![0];
+        starts.extend(self.text.match_indices('\n').map(|(i, _)| i + 1));
+        if starts.last() != Some(&self.text.len()) {
+            starts.push(self.text.len());
+        }
+        starts
+    }
+
+    ///Private function that finds all section headers and keys in the text.
+    fn entries(&self) -> Result<Vec<Entry>, String> {
+        let base = self.text.as_ptr() as usize;
+        let offset = |s: &str| s.as_ptr() as usize - base;
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut section = self.defaults.default_section.clone();
+        for event in StrEvents::new(&self.text, Lexer::from_defaults(&self.defaults)) {
+            let event = event?;
+            let line = event.line - 1;
+            match event.kind {
+                EventKind::SectionStart { name } => {
+                    section = name.into_owned();
+                    entries.push(Entry {
+                        section: section.clone(),
+                        key: None,
+                        lines: line..line + 1,
+                        key_end: 0,
+                        value: None,
+                    });
+                }
+                EventKind::KeyValue { key, value, .. } => entries.push(Entry {
+                    section: section.clone(),
+                    key: Some(key.to_string()),
+                    lines: line..line + 1,
+                    key_end: offset(&key) + key.len(),
+                    value: value.map(|value| offset(&value)..offset(&value) + value.len()),
+                }),
+                EventKind::Continuation { .. } => {
+                    if let Some(entry) = entries.last_mut() {
+                        entry.lines.end = line + 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(entries)
+    }
+}
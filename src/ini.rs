@@ -11,6 +11,9 @@ use std::collections::HashMap as Map;
 #[cfg(feature = "tokio")]
 use tokio::fs as async_fs;
 
+use crate::parser::{Event, EventKind, Events, Lexer, Span, StrEvents};
+
+use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
 use std::convert::AsRef;
 use std::fmt::Write;
@@ -34,9 +37,16 @@ pub struct Ini {
     delimiters: Vec<char>,
     boolean_values: HashMap<bool, Vec<String>>,
     case_sensitive: bool,
+    section_case: CasePolicy,
+    key_case: CasePolicy,
+    case_folding: CaseFolding,
     multiline: bool,
     enable_inline_comments: bool,
     cascade_defaults: bool,
+    duplicate_keys: KeyStrategy,
+    escapes: Escapes,
+    spans: Spans,
+    names: Option<NameIndex>,
 }
 
 #[cfg(all(feature = "serde", not(feature = "indexmap")))]
@@ -90,6 +100,50 @@ impl<'de> Deserialize<'de> for Ini {
     }
 }
 
+///Denotes how section names or keys are cased by an `Ini` object that is not `case_sensitive`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub enum CasePolicy {
+    ///Names are folded to lowercase when stored and looked up, so the original spelling is lost. This is the
+    ///default and the only behaviour of previous versions.
+    #[default]
+    Lowercase,
+    ///Names are stored and looked up exactly as written.
+    Sensitive,
+    ///Names are looked up case-insensitively but stored (and written back) with the spelling they were first
+    ///stored with. Lookups that don't match the stored spelling exactly fall back to a linear scan of the
+    ///section or map.
+    Preserve,
+}
+
+///Denotes how names are folded when comparing them case-insensitively.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub enum CaseFolding {
+    ///Full Unicode lowercasing, so `Ä` matches `ä`. This is the default.
+    #[default]
+    Unicode,
+    ///Only ASCII letters are folded, so `Ä` and `ä` are different names.
+    Ascii,
+}
+
+///Denotes how special characters in values are escaped.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub enum Escapes {
+    ///Values are stored exactly as written. This is the default.
+    #[default]
+    None,
+    ///Backslash escapes in values are replaced when parsing: `\n`, `\t` and `\r` by a line break, tab and carriage
+    ///return, `\s` by a space and a backslash before any other ASCII punctuation (like `\;` or `\\`) by that
+    ///character. Other escapes are kept as written. An escaped comment symbol doesn't start an inline comment, and
+    ///writing escapes values again.
+    Backslash,
+    ///Like `Backslash`, and double quotes in values are removed, keeping the whitespace and comment symbols between
+    ///them as written. Writing quotes values with surrounding whitespace or comment symbols.
+    Quoted,
+}
+
 ///The `IniDefault` struct serves as a template to create other `Ini` objects from. It can be used to store and load
 ///default properties from different `Ini` objects.
 ///## Example
@@ -155,6 +209,39 @@ pub struct IniDefault {
     ///assert_eq!(default.case_sensitive, false);
     ///```
     pub case_sensitive: bool,
+    ///Denotes how section names are cased when `case_sensitive` is `false` (a case-sensitive object always
+    ///treats section names case-sensitively).
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{CasePolicy, Ini};
+    ///
+    ///let mut config = Ini::new();
+    ///let default = config.defaults();
+    ///assert_eq!(default.section_case, CasePolicy::Lowercase);
+    ///```
+    pub section_case: CasePolicy,
+    ///Denotes how keys are cased when `case_sensitive` is `false` (a case-sensitive object always treats keys
+    ///case-sensitively).
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{CasePolicy, Ini};
+    ///
+    ///let mut config = Ini::new();
+    ///let default = config.defaults();
+    ///assert_eq!(default.key_case, CasePolicy::Lowercase);
+    ///```
+    pub key_case: CasePolicy,
+    ///Denotes how section names and keys are folded for `CasePolicy::Lowercase` and compared for
+    ///`CasePolicy::Preserve`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{CaseFolding, Ini};
+    ///
+    ///let mut config = Ini::new();
+    ///let default = config.defaults();
+    ///assert_eq!(default.case_folding, CaseFolding::Unicode);
+    ///```
+    pub case_folding: CaseFolding,
     ///Denotes if the `Ini` object parses multiline strings.
     ///## Example
     ///```rust
@@ -187,6 +274,26 @@ pub struct IniDefault {
     ///assert_eq!(default.cascade_defaults, false);
     ///```
     pub cascade_defaults: bool,
+    ///Denotes what happens when a key is repeated in a section of the same source.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{Ini, KeyStrategy};
+    ///
+    ///let mut config = Ini::new();
+    ///let default = config.defaults();
+    ///assert_eq!(default.duplicate_keys, KeyStrategy::Overwrite);
+    ///```
+    pub duplicate_keys: KeyStrategy,
+    ///Denotes how special characters in values are escaped.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{Escapes, Ini};
+    ///
+    ///let mut config = Ini::new();
+    ///let default = config.defaults();
+    ///assert_eq!(default.escapes, Escapes::None);
+    ///```
+    pub escapes: Escapes,
 }
 
 impl Default for IniDefault {
@@ -217,8 +324,51 @@ impl Default for IniDefault {
             .cloned()
             .collect(),
             case_sensitive: false,
+            section_case: CasePolicy::Lowercase,
+            key_case: CasePolicy::Lowercase,
+            case_folding: CaseFolding::Unicode,
             enable_inline_comments: true, // retain compatibility with previous versions
             cascade_defaults: false,      // retain backwards compatibility
+            duplicate_keys: KeyStrategy::Overwrite,
+            escapes: Escapes::None,
+        }
+    }
+}
+
+impl IniDefault {
+    ///Tells whether two section names refer to the same section under these defaults, the way `Ini` matches them.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{CasePolicy, IniDefault};
+    ///
+    ///let mut defaults = IniDefault::default();
+    ///assert!(defaults.same_section("Server", "server"));
+    ///defaults.section_case = CasePolicy::Sensitive;
+    ///assert!(!defaults.same_section("Server", "server"));
+    ///```
+    pub fn same_section(&self, a: &str, b: &str) -> bool {
+        let casing = Casing::from_defaults(self);
+        match casing.section {
+            CasePolicy::Sensitive => a == b,
+            _ => fold_eq(a, b, casing.folding),
+        }
+    }
+
+    ///Tells whether two keys refer to the same key under these defaults, the way `Ini` matches them.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::IniDefault;
+    ///
+    ///let mut defaults = IniDefault::default();
+    ///assert!(defaults.same_key("Port", "PORT"));
+    ///defaults.case_sensitive = true;
+    ///assert!(!defaults.same_key("Port", "PORT"));
+    ///```
+    pub fn same_key(&self, a: &str, b: &str) -> bool {
+        let casing = Casing::from_defaults(self);
+        match casing.key {
+            CasePolicy::Sensitive => a == b,
+            _ => fold_eq(a, b, casing.folding),
         }
     }
 }
@@ -229,7 +379,7 @@ impl Default for IniDefault {
 pub struct WriteOptions {
     ///If true then the keys and values will be separated by " = ". In the special case where the value is empty, the
     ///line ends with " =".
-    ///If false then keys and values will be separated by "=".
+    ///If false then keys and values will be separated by "=". The delimiter character itself is set by `delimiter`.
     ///Default is `false`.
     ///## Example
     ///```rust
@@ -261,6 +411,57 @@ pub struct WriteOptions {
     ///assert_eq!(write_options.blank_lines_between_sections, 0);
     ///```
     pub blank_lines_between_sections: usize,
+
+    ///Defines the delimiter character written between keys and values. Keep in mind that the output can only
+    ///be read back if the character is also one of the delimiters of the reading `Ini` object.
+    ///Default is `=`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::WriteOptions;
+    ///
+    ///let mut write_options = WriteOptions::default();
+    ///assert_eq!(write_options.delimiter, '=');
+    ///```
+    pub delimiter: char,
+
+    ///If true then the delimiters of all keys with values in a section are aligned to the same column by padding
+    ///the keys with spaces.
+    ///Default is `false`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::WriteOptions;
+    ///
+    ///let mut write_options = WriteOptions::default();
+    ///assert_eq!(write_options.align_delimiters, false);
+    ///```
+    pub align_delimiters: bool,
+
+    ///Defines the maximum line width for values. Value lines longer than this are wrapped at whitespace onto
+    ///continuation lines, which only happens when the `Ini` object has multiline support enabled (otherwise the
+    ///continuation lines could not be read back). Lines that fit are written unchanged and words longer than the
+    ///width are never split. Note that a wrapped value reads back as a multiline value, with a newline in place
+    ///of the whitespace at every break.
+    ///Default is `None`, which never wraps.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::WriteOptions;
+    ///
+    ///let mut write_options = WriteOptions::default();
+    ///assert_eq!(write_options.wrap_width, None);
+    ///```
+    pub wrap_width: Option<usize>,
+
+    ///If true then the keys of the default section are written under an explicit `[default_section]` header
+    ///instead of being written section-less at the top of the output.
+    ///Default is `false`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::WriteOptions;
+    ///
+    ///let mut write_options = WriteOptions::default();
+    ///assert_eq!(write_options.write_default_section_header, false);
+    ///```
+    pub write_default_section_header: bool,
 }
 
 impl Default for WriteOptions {
@@ -269,6 +470,10 @@ impl Default for WriteOptions {
             space_around_delimiters: false,
             multiline_line_indentation: 4,
             blank_lines_between_sections: 0,
+            delimiter: '=',
+            align_delimiters: false,
+            wrap_width: None,
+            write_default_section_header: false,
         }
     }
 }
@@ -309,188 +514,1028 @@ impl WriteOptions {
             space_around_delimiters,
             multiline_line_indentation,
             blank_lines_between_sections,
+            ..Default::default()
         }
     }
 }
 
-#[cfg(windows)]
-const LINE_ENDING: &str = "\r\n";
-#[cfg(not(windows))]
-const LINE_ENDING: &str = "\n";
+///Denotes what happens when an appended key already exists in its section, or when a key is repeated within one
+///source (see `IniDefault::duplicate_keys`).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub enum KeyStrategy {
+    ///The stored value is kept and the appended value is ignored.
+    KeepFirst,
+    ///The appended value replaces the stored value. This is the default and the behaviour of
+    ///`load_and_append()` and `read_and_append()`.
+    #[default]
+    Overwrite,
+    ///Appending fails if a key already exists with a different value, and nothing is changed. Within one source,
+    ///parsing fails on any repeated key.
+    Error,
+    ///Both values are kept as one multi-valued key, joined by a newline like a multiline value. Keys without a
+    ///value don't add a line.
+    Append,
+}
 
-impl Ini {
-    ///Creates a new `Map` of `Map<String, Map<String, Option<String>>>` type for the struct.
-    ///All values in the Map are stored in `String` type.
-    ///
-    ///By default, [`std::collections::HashMap`] is used for the Map object.
-    ///The `indexmap` feature can be used to use an [`indexmap::map::IndexMap`] instead, which
-    ///allows keeping the insertion order for sections and keys.
-    ///
+///Denotes what happens when an appended section already exists.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub enum SectionStrategy {
+    ///The keys of both sections are merged according to the `KeyStrategy`. This is the default.
+    #[default]
+    Merge,
+    ///The appended section replaces the stored section with all its keys.
+    Replace,
+}
+
+/// Use this struct to define merge strategies for the `*_and_append_with` functions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub struct AppendOptions {
+    ///Defines what happens to keys that exist in both the stored and the appended configuration.
+    ///Default is `KeyStrategy::Overwrite`.
     ///## Example
     ///```rust
-    ///use configparser::ini::Ini;
+    ///use configparser::ini::{AppendOptions, KeyStrategy};
     ///
-    ///let mut config = Ini::new();
+    ///let append_options = AppendOptions::default();
+    ///assert_eq!(append_options.keys, KeyStrategy::Overwrite);
     ///```
-    ///Returns the struct and stores it in the calling variable.
-    pub fn new() -> Ini {
-        Ini::new_from_defaults(IniDefault::default())
-    }
+    pub keys: KeyStrategy,
 
-    ///Creates a new **case-sensitive** `Map` of `Map<String, Map<String, Option<String>>>` type for the struct.
-    ///All values in the Map are stored in `String` type.
+    ///Defines what happens to sections that exist in both the stored and the appended configuration.
+    ///Default is `SectionStrategy::Merge`.
     ///## Example
     ///```rust
-    ///use configparser::ini::Ini;
+    ///use configparser::ini::{AppendOptions, SectionStrategy};
     ///
-    ///let mut config = Ini::new_cs();
+    ///let append_options = AppendOptions::default();
+    ///assert_eq!(append_options.sections, SectionStrategy::Merge);
     ///```
-    ///Returns the struct and stores it in the calling variable.
-    pub fn new_cs() -> Ini {
-        Ini::new_from_defaults(IniDefault {
-            case_sensitive: true,
-            ..Default::default()
-        })
-    }
+    pub sections: SectionStrategy,
+}
 
-    ///Creates a new `Ini` with the given defaults from an existing `IniDefault` object.
+impl AppendOptions {
+    ///Creates a new `AppendOptions` object with the default values.
     ///## Example
     ///```rust
-    ///use configparser::ini::Ini;
-    ///use configparser::ini::IniDefault;
-    ///
-    ///let mut default = IniDefault::default();
-    ///default.comment_symbols = vec![';'];
-    ///default.delimiters = vec!['='];
-    ///let mut config = Ini::new_from_defaults(default.clone());
-    ///// Now, load as usual with new defaults:
-    ///let map = config.load("tests/test.ini").unwrap();
-    ///assert_eq!(config.defaults(), default);
+    ///use configparser::ini::{AppendOptions, KeyStrategy, SectionStrategy};
     ///
+    ///let append_options = AppendOptions::new();
+    ///assert_eq!(append_options.keys, KeyStrategy::Overwrite);
+    ///assert_eq!(append_options.sections, SectionStrategy::Merge);
     ///```
-    pub fn new_from_defaults(defaults: IniDefault) -> Ini {
-        Ini {
-            map: Map::new(),
-            default_section: defaults.default_section,
-            comment_symbols: defaults.comment_symbols,
-            inline_comment_symbols: defaults.inline_comment_symbols,
-            delimiters: defaults.delimiters,
-            boolean_values: defaults.boolean_values,
-            case_sensitive: defaults.case_sensitive,
-            multiline: defaults.multiline,
-            enable_inline_comments: defaults.enable_inline_comments,
-            cascade_defaults: defaults.cascade_defaults,
-        }
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new() -> AppendOptions {
+        AppendOptions::default()
     }
 
-    ///Fetches the defaults from the current `Ini` object and stores it as a `IniDefault` struct for usage elsewhere.
+    ///Creates a new `AppendOptions` object with the given parameters.
     ///## Example
     ///```rust
-    ///use configparser::ini::Ini;
+    ///use configparser::ini::{AppendOptions, KeyStrategy, SectionStrategy};
     ///
-    ///let mut config = Ini::new();
-    ///let default = config.defaults();
+    ///let append_options = AppendOptions::new_with_params(KeyStrategy::KeepFirst, SectionStrategy::Replace);
+    ///assert_eq!(append_options.keys, KeyStrategy::KeepFirst);
+    ///assert_eq!(append_options.sections, SectionStrategy::Replace);
     ///```
-    ///Returns an `IniDefault` object. Keep in mind that it will get borrowed since it has non-`Copy` types.
-    pub fn defaults(&self) -> IniDefault {
-        IniDefault {
-            default_section: self.default_section.to_owned(),
-            comment_symbols: self.comment_symbols.to_owned(),
-            inline_comment_symbols: self.inline_comment_symbols.to_owned(),
-            delimiters: self.delimiters.to_owned(),
-            boolean_values: self.boolean_values.to_owned(),
-            case_sensitive: self.case_sensitive,
-            multiline: self.multiline,
-            enable_inline_comments: self.enable_inline_comments,
-            cascade_defaults: self.cascade_defaults,
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new_with_params(keys: KeyStrategy, sections: SectionStrategy) -> AppendOptions {
+        AppendOptions { keys, sections }
+    }
+}
+
+///A key that existed with a different value before a configuration was appended.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Override {
+    pub section: String,
+    pub key: String,
+    ///The value stored before appending.
+    pub previous: Option<String>,
+    ///The value found in the appended configuration.
+    pub appended: Option<String>,
+    ///The value stored after appending, which depends on the `KeyStrategy`.
+    pub value: Option<String>,
+}
+
+///The `AppendReport` struct describes what the `*_and_append_with` functions changed in existing sections.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub struct AppendReport {
+    ///Every key of a merged section that already existed with a different value, in the order of the appended
+    ///configuration.
+    pub overridden: Vec<Override>,
+    ///Every section that was replaced by `SectionStrategy::Replace`.
+    pub replaced_sections: Vec<String>,
+}
+
+///The `KeySpan` struct holds where a key and its value were found in the parsed source, as returned by
+///`Ini::span_of()`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct KeySpan {
+    ///The span of the key itself.
+    pub key: Span,
+    ///The span of the value, from its first character to the end of its last continuation line, or `None` if the
+    ///key has no value.
+    pub value: Option<Span>,
+}
+
+///Private struct holding the spans recorded by `build_map()`, keyed by the stored section and key names.
+#[derive(Debug, Clone, Default)]
+struct Spans {
+    sections: HashMap<String, Span>,
+    keys: HashMap<String, HashMap<String, KeySpan>>,
+}
+
+// Spans only describe where the configuration came from, so they never make two `Ini` objects unequal.
+impl PartialEq for Spans {
+    fn eq(&self, _: &Spans) -> bool {
+        true
+    }
+}
+
+impl Eq for Spans {}
+
+///Private struct indexing the stored spellings of section and key names by their folded spelling, so that
+///`CasePolicy::Preserve` can find the stored spelling of a name without comparing it to every other name.
+#[derive(Debug, Clone)]
+struct NameIndex {
+    folding: CaseFolding,
+    sections: HashMap<String, String>,
+    keys: HashMap<String, HashMap<String, String>>,
+}
+
+impl NameIndex {
+    fn new(folding: CaseFolding) -> NameIndex {
+        NameIndex {
+            folding,
+            sections: HashMap::new(),
+            keys: HashMap::new(),
         }
     }
 
-    ///Takes an `IniDefault` object and stores its properties in the calling `Ini` object. This happens in-place and
-    ///does not work retroactively, only future operations are affected.
-    ///## Example
-    ///```rust
-    ///use configparser::ini::Ini;
-    ///use configparser::ini::IniDefault;
-    ///
-    ///let mut config = Ini::new();
-    ///let mut default = IniDefault::default();
-    ///default.case_sensitive = true;
-    ///// This is equivalent to ini_cs() defaults
-    ///config.load_defaults(default.clone());
-    ///assert_eq!(config.defaults(), default);
-    ///```
-    ///Returns nothing.
-    pub fn load_defaults(&mut self, defaults: IniDefault) {
-        self.default_section = defaults.default_section;
-        self.comment_symbols = defaults.comment_symbols;
-        self.inline_comment_symbols = defaults.inline_comment_symbols;
-        self.delimiters = defaults.delimiters;
-        self.boolean_values = defaults.boolean_values;
-        self.case_sensitive = defaults.case_sensitive;
+    fn from_map(map: &Map<String, Map<String, Option<String>>>, folding: CaseFolding) -> NameIndex {
+        let mut names = NameIndex::new(folding);
+        for (section, secmap) in map.iter() {
+            names.add_section(section);
+            for key in secmap.keys() {
+                names.add_key(section, key);
+            }
+        }
+        names
     }
 
-    ///Sets the default section header to the defined string (the default is `default`).
-    ///It must be set before `load()` or `read()` is called in order to take effect.
-    ///## Example
-    ///```rust
-    ///use configparser::ini::Ini;
-    ///
-    ///let mut config = Ini::new();
-    ///
-    ///config.set_default_section("topsecret");
-    ///let map = config.load("tests/test.ini").unwrap();
-    ///```
-    ///Returns nothing.
-    pub fn set_default_section(&mut self, section: &str) {
-        self.default_section = section.to_owned();
+    fn folded(&self, name: &str) -> String {
+        name.to_owned().fold(self.folding)
     }
 
-    ///Sets the default comment symbols to the defined character slice (the defaults are `;` and `#`).
-    ///Keep in mind that this will remove the default symbols. It must be set before `load()` or `read()` is called in order to take effect.
-    ///## Example
-    ///```rust
-    ///use configparser::ini::Ini;
-    ///
-    ///let mut config = Ini::new();
-    ///config.set_comment_symbols(&['!', '#']);
-    ///let map = config.load("tests/test.ini").unwrap();
-    ///```
-    ///Returns nothing.
-    pub fn set_comment_symbols(&mut self, symlist: &[char]) {
-        self.comment_symbols = symlist.to_vec();
+    ///Returns the index of the keys of a stored section.
+    fn keys(&self, section: &str) -> Option<&HashMap<String, String>> {
+        self.keys.get(section)
     }
 
-    ///Sets the default inline comment symbols to the defined character slice (the default is `None` which falls back to the normal comment symbols).
-    ///Keep in mind that this will remove the default symbols. It must be set before `load()` or `read()` is called in order to take effect.
-    ///## Example
-    ///```rust
-    ///use configparser::ini::Ini;
-    ///
-    ///let mut config = Ini::new();
-    ///config.set_inline_comment_symbols(Some(&['!', '#']));
-    ///let map = config.load("tests/test.ini").unwrap();
-    ///```
-    ///Returns nothing.
-    pub fn set_inline_comment_symbols(&mut self, symlist: Option<&[char]>) {
-        self.inline_comment_symbols = symlist.map(|val| val.to_vec());
+    fn add_section(&mut self, section: &str) {
+        if !self.keys.contains_key(section) {
+            let folded = self.folded(section);
+            self.sections
+                .entry(folded)
+                .or_insert_with(|| section.to_owned());
+            self.keys.insert(section.to_owned(), HashMap::new());
+        }
     }
 
-    ///Sets multiline string support.
-    ///It must be set before `load()` or `read()` is called in order to take effect.
-    ///## Example
-    ///```rust
-    ///use configparser::ini::Ini;
-    ///
-    ///let mut config = Ini::new();
-    ///config.set_multiline(true);
-    ///let map = config.load("tests/test.ini").unwrap();
+    fn add_key(&mut self, section: &str, key: &str) {
+        let folded = self.folded(key);
+        if let Some(keys) = self.keys.get_mut(section) {
+            keys.entry(folded).or_insert_with(|| key.to_owned());
+        }
+    }
+
+    fn remove_section(&mut self, section: &str) {
+        let folded = self.folded(section);
+        if self
+            .sections
+            .get(&folded)
+            .is_some_and(|stored| stored == section)
+        {
+            self.sections.remove(&folded);
+        }
+        self.keys.remove(section);
+    }
+
+    fn remove_key(&mut self, section: &str, key: &str) {
+        let folded = self.folded(key);
+        if let Some(keys) = self.keys.get_mut(section)
+            && keys.get(&folded).is_some_and(|stored| stored == key)
+        {
+            keys.remove(&folded);
+        }
+    }
+}
+
+// The index is derived from the map, so it never makes two objects unequal.
+impl PartialEq for NameIndex {
+    fn eq(&self, _: &NameIndex) -> bool {
+        true
+    }
+}
+
+impl Eq for NameIndex {}
+
+///Private struct describing the comments `unparse_annotated()` writes around a section header or key.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Annotation {
+    ///Comment lines written before the section header or key, including their comment symbols.
+    pub(crate) comments: Vec<String>,
+    ///A comment written at the end of the section header or the first line of the key.
+    pub(crate) inline_comment: Option<String>,
+    ///Whether the key itself is written as a comment. Ignored for section headers.
+    pub(crate) commented_out: bool,
+}
+
+#[cfg(windows)]
+pub(crate) const LINE_ENDING: &str = "\r\n";
+#[cfg(not(windows))]
+pub(crate) const LINE_ENDING: &str = "\n";
+
+///Private alias for what `Ini` parses: the Map, the spans of its sections and keys and the index of its names.
+type Parsed = (
+    Map<String, Map<String, Option<String>>>,
+    Spans,
+    Option<NameIndex>,
+);
+
+///Private alias for what `build_map()` builds: the Map and the index of its names.
+type Built<S> = (Map<S, Map<S, Option<S>>>, Option<NameIndex>);
+
+///Private trait over the string types the parser can store, which lets `Ini` own its strings while `IniRef`
+///borrows them from the input wherever possible.
+trait ParsedStr: Clone + Eq + std::hash::Hash + Borrow<str> {
+    fn from_string(val: String) -> Self;
+    fn fold(self, folding: CaseFolding) -> Self;
+    fn push_str(&mut self, val: &str);
+}
+
+impl ParsedStr for String {
+    fn from_string(val: String) -> Self {
+        val
+    }
+
+    fn fold(self, folding: CaseFolding) -> Self {
+        if folding == CaseFolding::Ascii || self.is_ascii() {
+            if self.bytes().any(|b| b.is_ascii_uppercase()) {
+                self.to_ascii_lowercase()
+            } else {
+                self
+            }
+        } else {
+            self.to_lowercase()
+        }
+    }
+
+    fn push_str(&mut self, val: &str) {
+        String::push_str(self, val);
+    }
+}
+
+impl ParsedStr for Cow<'_, str> {
+    fn from_string(val: String) -> Self {
+        Cow::Owned(val)
+    }
+
+    fn fold(self, folding: CaseFolding) -> Self {
+        // Most keys are already lowercase, so only allocate when folding actually changes something.
+        if folding == CaseFolding::Ascii || self.is_ascii() {
+            if self.bytes().any(|b| b.is_ascii_uppercase()) {
+                Cow::Owned(self.to_ascii_lowercase())
+            } else {
+                self
+            }
+        } else {
+            let lowered = self.to_lowercase();
+            if lowered == self {
+                self
+            } else {
+                Cow::Owned(lowered)
+            }
+        }
+    }
+
+    fn push_str(&mut self, val: &str) {
+        self.to_mut().push_str(val);
+    }
+}
+
+///Private function that parses a value case-insensitively into a `bool`.
+fn parse_bool(val: &str) -> Result<bool, String> {
+    val.to_lowercase()
+        .parse::<bool>()
+        .map_err(|why| why.to_string())
+}
+
+///Private function that parses a value into any `FromStr` type, stringifying the error.
+fn parse_value<T: std::str::FromStr>(val: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    val.parse::<T>().map_err(|why| why.to_string())
+}
+
+///Private function that coerces a value into a `bool` using the given boolean values.
+fn coerce_bool(
+    boolean_values: &HashMap<bool, Vec<String>>,
+    val: &str,
+    section: &str,
+    key: &str,
+) -> Result<bool, String> {
+    let boolval = val.to_lowercase();
+    for boolean in [true, false] {
+        if boolean_values[&boolean].contains(&boolval) {
+            return Ok(boolean);
+        }
+    }
+    Err(format!(
+        "Unable to parse value into bool at {}:{}",
+        section, key
+    ))
+}
+
+///Private struct holding the effective case handling of an `Ini` or `IniRef`.
+#[derive(Debug, Clone, Copy)]
+struct Casing {
+    section: CasePolicy,
+    key: CasePolicy,
+    folding: CaseFolding,
+}
+
+impl Casing {
+    fn new(
+        case_sensitive: bool,
+        section_case: CasePolicy,
+        key_case: CasePolicy,
+        folding: CaseFolding,
+    ) -> Casing {
+        if case_sensitive {
+            Casing {
+                section: CasePolicy::Sensitive,
+                key: CasePolicy::Sensitive,
+                folding,
+            }
+        } else {
+            Casing {
+                section: section_case,
+                key: key_case,
+                folding,
+            }
+        }
+    }
+
+    fn from_defaults(defaults: &IniDefault) -> Casing {
+        Casing::new(
+            defaults.case_sensitive,
+            defaults.section_case,
+            defaults.key_case,
+            defaults.case_folding,
+        )
+    }
+}
+
+///Private function that compares two names case-insensitively with the given folding.
+fn fold_eq(a: &str, b: &str, folding: CaseFolding) -> bool {
+    if folding == CaseFolding::Ascii || (a.is_ascii() && b.is_ascii()) {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a.to_lowercase() == b.to_lowercase()
+    }
+}
+
+///Private function that finds the stored spelling of a name in a map with the given policy, using the index of
+///the map's names (from `NameIndex`) if there is one. Returns the name the map stores (or would store) it under.
+fn resolve_name<'n, K: Borrow<str> + std::hash::Hash + Eq, V>(
+    map: Option<&Map<K, V>>,
+    name: &'n str,
+    policy: CasePolicy,
+    folding: CaseFolding,
+    index: Option<&HashMap<String, String>>,
+) -> Cow<'n, str> {
+    match policy {
+        CasePolicy::Sensitive => Cow::Borrowed(name),
+        CasePolicy::Lowercase => Cow::Borrowed(name).fold(folding),
+        CasePolicy::Preserve => match map {
+            Some(map) if !map.contains_key(name) => match find_folded(map, name, folding, index) {
+                Some(stored) => Cow::Owned(stored.to_owned()),
+                None => Cow::Borrowed(name),
+            },
+            _ => Cow::Borrowed(name),
+        },
+    }
+}
+
+///Private function that finds a stored name that folds to the same name, through the index if there is one.
+fn find_folded<'m, K: Borrow<str> + std::hash::Hash + Eq, V>(
+    map: &'m Map<K, V>,
+    name: &str,
+    folding: CaseFolding,
+    index: Option<&'m HashMap<String, String>>,
+) -> Option<&'m str> {
+    match index {
+        Some(index) => index
+            .get(&name.to_owned().fold(folding))
+            .map(String::as_str),
+        None => map
+            .keys()
+            .map(|stored| stored.borrow())
+            .find(|stored| fold_eq(stored, name, folding)),
+    }
+}
+
+///Private function that cases a freshly parsed name of any `ParsedStr` type for insertion into a map.
+fn case_parsed<S: ParsedStr, V>(
+    map: &Map<S, V>,
+    name: S,
+    policy: CasePolicy,
+    folding: CaseFolding,
+    index: Option<&HashMap<String, String>>,
+) -> S {
+    match policy {
+        CasePolicy::Sensitive => name,
+        CasePolicy::Lowercase => name.fold(folding),
+        CasePolicy::Preserve => {
+            if map.contains_key(name.borrow()) {
+                return name;
+            }
+            match find_folded(map, name.borrow(), folding, index) {
+                Some(stored) => S::from_string(stored.to_owned()),
+                None => name,
+            }
+        }
+    }
+}
+
+///Private function that replaces the escapes of a value of any `ParsedStr` type, only allocating if it has any.
+fn unescape<S: ParsedStr>(value: S, escapes: Escapes) -> S {
+    let raw: &str = value.borrow();
+    let quoted = escapes == Escapes::Quoted;
+    if escapes == Escapes::None || !raw.contains(|c| c == '\\' || (quoted && c == '"')) {
+        return value;
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('r') => out.push('\r'),
+                Some('s') => out.push(' '),
+                Some(c) if c.is_ascii_punctuation() => out.push(c),
+                Some(c) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                None => out.push('\\'),
+            },
+            '"' if quoted => {}
+            c => out.push(c),
+        }
+    }
+    S::from_string(out)
+}
+
+///Private function that escapes a value for writing, so that parsing it with the same escapes and inline comment
+///symbols gives it back. Only allocates if anything needs escaping.
+pub(crate) fn escape<'a>(
+    value: &'a str,
+    escapes: Escapes,
+    inline_comment_symbols: &[char],
+) -> Cow<'a, str> {
+    let is_comment = |c: char| inline_comment_symbols.contains(&c);
+    let padded = value.starts_with(char::is_whitespace) || value.ends_with(char::is_whitespace);
+    let special =
+        |c: char| matches!(c, '\\' | '\n' | '\r') || (c == '"' && escapes == Escapes::Quoted);
+    if escapes == Escapes::None || !(padded || value.contains(|c| special(c) || is_comment(c))) {
+        return Cow::Borrowed(value);
+    }
+    // Quoting protects surrounding whitespace and comment symbols, which otherwise need their own escapes.
+    let quote = escapes == Escapes::Quoted && (padded || value.contains(is_comment));
+    let last = value.chars().count().saturating_sub(1);
+    let mut out = String::with_capacity(value.len() + 2);
+    if quote {
+        out.push('"');
+    }
+    for (idx, c) in value.chars().enumerate() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            ' ' if !quote && (idx == 0 || idx == last) => out.push_str("\\s"),
+            '\t' if !quote && (idx == 0 || idx == last) => out.push_str("\\t"),
+            c if special(c) || (!quote && is_comment(c)) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    if quote {
+        out.push('"');
+    }
+    Cow::Owned(out)
+}
+
+///Private function that wraps `line` at whitespace if it does not fit in `width` characters, where the first
+///line already starts at column `first_offset` and continuation lines at column `indent`. Lines that fit are kept
+///as they are and whitespace runs inside a line are preserved, only the run at each break is dropped. Every output
+///line holds at least one word.
+fn wrap_line(line: &str, width: usize, first_offset: usize, indent: usize) -> Vec<String> {
+    if first_offset + line.chars().count() <= width {
+        return vec![line.to_owned()];
+    }
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_width = first_offset;
+    let mut rest = line;
+    while !rest.is_empty() {
+        let (gap, after) = rest.split_at(rest.len() - rest.trim_start().len());
+        let (word, after) = after.split_at(after.find(char::is_whitespace).unwrap_or(after.len()));
+        rest = after;
+        let gap_width = gap.chars().count();
+        let word_width = word.chars().count();
+        if !word.is_empty()
+            && !current.trim().is_empty()
+            && current_width + gap_width + word_width > width
+        {
+            lines.push(std::mem::take(&mut current));
+            current_width = indent;
+        } else {
+            current.push_str(gap);
+            current_width += gap_width;
+        }
+        current.push_str(word);
+        current_width += word_width;
+    }
+    lines.push(current);
+    lines
+}
+
+///Private function that writes the comment lines of an annotation.
+fn write_comments(out: &mut String, annotation: &Annotation) {
+    for comment in annotation.comments.iter() {
+        out.push_str(comment);
+        out.push_str(LINE_ENDING);
+    }
+}
+
+///Private function that appends the inline comment of an annotation to the first line written since `start`.
+fn write_inline_comment(out: &mut String, start: usize, annotation: &Annotation) {
+    if let Some(comment) = &annotation.inline_comment {
+        let end = out[start..]
+            .find(LINE_ENDING)
+            .map_or(out.len(), |end| start + end);
+        out.insert_str(end, &format!(" {}", comment));
+    }
+}
+
+///Private function that builds a Map of any `ParsedStr` type from parser events, along with the index of its
+///names if any of them are case-preserving.
+fn build_map<S: ParsedStr>(
+    events: impl Iterator<Item = Result<Event<S>, String>>,
+    default_section: &str,
+    casing: Casing,
+    multiline: bool,
+    duplicate_keys: KeyStrategy,
+    escapes: Escapes,
+    mut spans: Option<&mut Spans>,
+) -> Result<Built<S>, String> {
+    let mut map: Map<S, Map<S, Option<S>>> = Map::new();
+    let mut names = (casing.section == CasePolicy::Preserve || casing.key == CasePolicy::Preserve)
+        .then(|| NameIndex::new(casing.folding));
+    let mut section = S::from_string(default_section.to_owned());
+    // Tracks whether `section` is already present in `map`. Section headers
+    // always insert their section, so the only section that can still be
+    // missing is the (lazily created) default section.
+    let mut section_exists = false;
+    let mut current_key: Option<S> = None;
+    // Set while the continuation lines of a repeated key that is ignored are skipped.
+    let mut skipping = false;
+
+    // Track blank lines to preserve them in multiline values.
+    let mut blank_lines = 0usize;
+
+    for event in events {
+        let event = event?;
+        match event.kind {
+            EventKind::Comment { .. } => continue,
+            EventKind::Blank => {
+                blank_lines += 1;
+                continue;
+            }
+            EventKind::SectionStart { name } => {
+                section = case_parsed(
+                    &map,
+                    name,
+                    casing.section,
+                    casing.folding,
+                    names.as_ref().map(|names| &names.sections),
+                );
+                map.entry(section.clone()).or_default();
+                if let Some(names) = names.as_mut() {
+                    names.add_section(section.borrow());
+                }
+                section_exists = true;
+                if let Some(spans) = spans.as_deref_mut() {
+                    let name: &str = section.borrow();
+                    spans.sections.entry(name.to_owned()).or_insert(event.span);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        // Ensure the current section exists, then take a mutable handle to
+        // it. Using `get_mut` on the common path avoids cloning the section
+        // name on every single entry.
+        if !section_exists {
+            map.entry(section.clone()).or_default();
+            if let Some(names) = names.as_mut() {
+                names.add_section(section.borrow());
+            }
+            section_exists = true;
+        }
+        let valmap = map.get_mut(section.borrow()).unwrap();
+
+        match event.kind {
+            EventKind::Continuation { text } => {
+                // The lexer only yields continuations after an entry, so there always is a current key.
+                let Some(key) = current_key.as_ref() else {
+                    continue;
+                };
+                if skipping {
+                    blank_lines = 0;
+                    continue;
+                }
+                let text = unescape(text, escapes);
+                if let Some(spans) = spans.as_deref_mut() {
+                    let found = spans
+                        .keys
+                        .get_mut(section.borrow())
+                        .and_then(|keys| keys.get_mut(key.borrow()));
+                    if let Some(found) = found {
+                        found.value = Some(found.value.map_or(event.span, |v| v.to(event.span)));
+                    }
+                }
+
+                let val = valmap
+                    .entry(key.clone())
+                    .or_insert_with(|| Some(S::from_string(String::new())));
+
+                match val {
+                    Some(s) => {
+                        for _ in 0..blank_lines {
+                            s.push_str(LINE_ENDING);
+                        }
+                        s.push_str(LINE_ENDING);
+                        s.push_str(text.borrow());
+                    }
+                    None => {
+                        let text: &str = text.borrow();
+                        let mut s = String::with_capacity(
+                            (blank_lines + 1) * LINE_ENDING.len() + text.len(),
+                        );
+                        for _ in 0..blank_lines {
+                            s.push_str(LINE_ENDING);
+                        }
+                        s.push_str(LINE_ENDING);
+                        s.push_str(text);
+                        *val = Some(S::from_string(s));
+                    }
+                }
+            }
+            EventKind::KeyValue { key, value, .. } => {
+                let key = case_parsed(
+                    valmap,
+                    key,
+                    casing.key,
+                    casing.folding,
+                    names
+                        .as_ref()
+                        .and_then(|names| names.keys(section.borrow())),
+                );
+                let value = value.map(|value| unescape(value, escapes));
+                // Repeated keys only need to be looked up if they aren't simply overwritten.
+                let repeated =
+                    duplicate_keys != KeyStrategy::Overwrite && valmap.contains_key(key.borrow());
+                skipping = repeated && duplicate_keys == KeyStrategy::KeepFirst;
+                if repeated && duplicate_keys == KeyStrategy::Error {
+                    let (key, section): (&str, &str) = (key.borrow(), section.borrow());
+                    return Err(format!(
+                        "line {}:{}: Key {} is already set in section {}",
+                        event.line, event.column, key, section
+                    ));
+                }
+                if let Some(spans) = spans.as_deref_mut().filter(|_| !skipping) {
+                    let (name, key): (&str, &str) = (section.borrow(), key.borrow());
+                    let keys = spans.keys.entry(name.to_owned()).or_default();
+                    match keys.get_mut(key) {
+                        // Appended values extend the span of the first one.
+                        Some(found) if repeated => {
+                            if let Some(span) = event.value_span {
+                                found.value = Some(found.value.map_or(span, |v| v.to(span)));
+                            }
+                        }
+                        _ => {
+                            let found = KeySpan {
+                                key: event.span,
+                                value: event.value_span,
+                            };
+                            keys.insert(key.to_owned(), found);
+                        }
+                    }
+                }
+                // `current_key` is only read when stitching together
+                // multiline values, so only clone the key then.
+                if multiline {
+                    current_key = Some(key.clone());
+                }
+                if !repeated {
+                    if let Some(names) = names.as_mut() {
+                        names.add_key(section.borrow(), key.borrow());
+                    }
+                    valmap.insert(key, value);
+                } else if duplicate_keys == KeyStrategy::Append {
+                    let stored = valmap.get_mut(key.borrow()).unwrap();
+                    match (stored.as_mut(), value) {
+                        (Some(stored), Some(value)) => {
+                            stored.push_str(LINE_ENDING);
+                            stored.push_str(value.borrow());
+                        }
+                        (None, value) => *stored = value,
+                        _ => {}
+                    }
+                }
+            }
+            _ => unreachable!("only entries are left"),
+        }
+
+        blank_lines = 0;
+    }
+
+    Ok((map, names))
+}
+
+impl Ini {
+    ///Creates a new `Map` of `Map<String, Map<String, Option<String>>>` type for the struct.
+    ///All values in the Map are stored in `String` type.
+    ///
+    ///By default, [`std::collections::HashMap`] is used for the Map object.
+    ///The `indexmap` feature can be used to use an [`indexmap::map::IndexMap`] instead, which
+    ///allows keeping the insertion order for sections and keys.
+    ///
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///```
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new() -> Ini {
+        Ini::new_from_defaults(IniDefault::default())
+    }
+
+    ///Creates a new **case-sensitive** `Map` of `Map<String, Map<String, Option<String>>>` type for the struct.
+    ///All values in the Map are stored in `String` type.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new_cs();
+    ///```
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new_cs() -> Ini {
+        Ini::new_from_defaults(IniDefault {
+            case_sensitive: true,
+            ..Default::default()
+        })
+    }
+
+    ///Creates a new **case-preserving** `Map` of `Map<String, Map<String, Option<String>>>` type for the struct.
+    ///Section names and keys are looked up case-insensitively, but stored and written back with their original
+    ///spelling (see `CasePolicy::Preserve`).
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new_case_preserving();
+    ///config.read(String::from("[Server]\nMaxConnections = 10")).unwrap();
+    ///assert_eq!(config.getint("server", "maxconnections").unwrap(), Some(10));
+    ///assert_eq!(config.writes(), "[Server]\nMaxConnections=10\n");
+    ///```
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new_case_preserving() -> Ini {
+        Ini::new_from_defaults(IniDefault {
+            section_case: CasePolicy::Preserve,
+            key_case: CasePolicy::Preserve,
+            ..Default::default()
+        })
+    }
+
+    ///Creates a new `Ini` with the given defaults from an existing `IniDefault` object.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::ini::IniDefault;
+    ///
+    ///let mut default = IniDefault::default();
+    ///default.comment_symbols = vec![';'];
+    ///default.delimiters = vec!['='];
+    ///let mut config = Ini::new_from_defaults(default.clone());
+    ///// Now, load as usual with new defaults:
+    ///let map = config.load("tests/test.ini").unwrap();
+    ///assert_eq!(config.defaults(), default);
+    ///
+    ///```
+    pub fn new_from_defaults(defaults: IniDefault) -> Ini {
+        Ini {
+            map: Map::new(),
+            default_section: defaults.default_section,
+            comment_symbols: defaults.comment_symbols,
+            inline_comment_symbols: defaults.inline_comment_symbols,
+            delimiters: defaults.delimiters,
+            boolean_values: defaults.boolean_values,
+            case_sensitive: defaults.case_sensitive,
+            section_case: defaults.section_case,
+            key_case: defaults.key_case,
+            case_folding: defaults.case_folding,
+            multiline: defaults.multiline,
+            enable_inline_comments: defaults.enable_inline_comments,
+            cascade_defaults: defaults.cascade_defaults,
+            duplicate_keys: defaults.duplicate_keys,
+            escapes: defaults.escapes,
+            spans: Spans::default(),
+            names: None,
+        }
+    }
+
+    ///Fetches the defaults from the current `Ini` object and stores it as a `IniDefault` struct for usage elsewhere.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///let default = config.defaults();
+    ///```
+    ///Returns an `IniDefault` object. Keep in mind that it will get borrowed since it has non-`Copy` types.
+    pub fn defaults(&self) -> IniDefault {
+        IniDefault {
+            default_section: self.default_section.to_owned(),
+            comment_symbols: self.comment_symbols.to_owned(),
+            inline_comment_symbols: self.inline_comment_symbols.to_owned(),
+            delimiters: self.delimiters.to_owned(),
+            boolean_values: self.boolean_values.to_owned(),
+            case_sensitive: self.case_sensitive,
+            section_case: self.section_case,
+            key_case: self.key_case,
+            case_folding: self.case_folding,
+            multiline: self.multiline,
+            enable_inline_comments: self.enable_inline_comments,
+            cascade_defaults: self.cascade_defaults,
+            duplicate_keys: self.duplicate_keys,
+            escapes: self.escapes,
+        }
+    }
+
+    ///Takes an `IniDefault` object and stores its properties in the calling `Ini` object. This happens in-place and
+    ///does not work retroactively, only future operations are affected.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::ini::IniDefault;
+    ///
+    ///let mut config = Ini::new();
+    ///let mut default = IniDefault::default();
+    ///default.case_sensitive = true;
+    ///// This is equivalent to ini_cs() defaults
+    ///config.load_defaults(default.clone());
+    ///assert_eq!(config.defaults(), default);
+    ///```
+    ///Returns nothing.
+    pub fn load_defaults(&mut self, defaults: IniDefault) {
+        self.default_section = defaults.default_section;
+        self.comment_symbols = defaults.comment_symbols;
+        self.inline_comment_symbols = defaults.inline_comment_symbols;
+        self.delimiters = defaults.delimiters;
+        self.boolean_values = defaults.boolean_values;
+        self.case_sensitive = defaults.case_sensitive;
+        self.section_case = defaults.section_case;
+        self.key_case = defaults.key_case;
+        self.case_folding = defaults.case_folding;
+        self.multiline = defaults.multiline;
+        self.enable_inline_comments = defaults.enable_inline_comments;
+        self.cascade_defaults = defaults.cascade_defaults;
+        self.names = None;
+        self.duplicate_keys = defaults.duplicate_keys;
+        self.escapes = defaults.escapes;
+    }
+
+    ///Sets the default section header to the defined string (the default is `default`).
+    ///It must be set before `load()` or `read()` is called in order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///
+    ///config.set_default_section("topsecret");
+    ///let map = config.load("tests/test.ini").unwrap();
+    ///```
+    ///Returns nothing.
+    pub fn set_default_section(&mut self, section: &str) {
+        self.default_section = section.to_owned();
+    }
+
+    ///Sets the default comment symbols to the defined character slice (the defaults are `;` and `#`).
+    ///Keep in mind that this will remove the default symbols. It must be set before `load()` or `read()` is called in order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.set_comment_symbols(&['!', '#']);
+    ///let map = config.load("tests/test.ini").unwrap();
+    ///```
+    ///Returns nothing.
+    pub fn set_comment_symbols(&mut self, symlist: &[char]) {
+        self.comment_symbols = symlist.to_vec();
+    }
+
+    ///Sets the default inline comment symbols to the defined character slice (the default is `None` which falls back to the normal comment symbols).
+    ///Keep in mind that this will remove the default symbols. It must be set before `load()` or `read()` is called in order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.set_inline_comment_symbols(Some(&['!', '#']));
+    ///let map = config.load("tests/test.ini").unwrap();
+    ///```
+    ///Returns nothing.
+    pub fn set_inline_comment_symbols(&mut self, symlist: Option<&[char]>) {
+        self.inline_comment_symbols = symlist.map(|val| val.to_vec());
+    }
+
+    ///Sets multiline string support.
+    ///It must be set before `load()` or `read()` is called in order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.set_multiline(true);
+    ///let map = config.load("tests/test.ini").unwrap();
     ///```
     ///Returns nothing.
     pub fn set_multiline(&mut self, multiline: bool) {
         self.multiline = multiline;
     }
 
+    ///Sets the case policies for section names and keys separately. They only apply while `case_sensitive` is
+    ///`false`. It must be set before `load()` or `read()` is called in order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{CasePolicy, Ini};
+    ///
+    ///let mut config = Ini::new();
+    ///config.set_case_policy(CasePolicy::Lowercase, CasePolicy::Preserve);
+    ///config.read(String::from("[Section]\nKey=value")).unwrap();
+    ///assert_eq!(config.get("SECTION", "KEY").unwrap(), "value");
+    ///assert_eq!(config.writes(), "[section]\nKey=value\n");
+    ///```
+    ///Returns nothing.
+    pub fn set_case_policy(&mut self, section_case: CasePolicy, key_case: CasePolicy) {
+        self.section_case = section_case;
+        self.key_case = key_case;
+        self.names = None;
+    }
+
+    ///Sets how names are folded for case-insensitive handling (the default is `CaseFolding::Unicode`).
+    ///It must be set before `load()` or `read()` is called in order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{CaseFolding, Ini};
+    ///
+    ///let mut config = Ini::new();
+    ///config.set_case_folding(CaseFolding::Ascii);
+    ///config.read(String::from("[Ärger]\nKey=value")).unwrap();
+    ///assert_eq!(config.sections(), vec!["Ärger"]);
+    ///```
+    ///Returns nothing.
+    pub fn set_case_folding(&mut self, case_folding: CaseFolding) {
+        self.case_folding = case_folding;
+        self.names = None;
+    }
+
     ///Sets the behavior around the cascading of defaults.  If this is set
     ///to `true`, a get*() call for a section without a matching key value will
     ///attempt to get that key value from the [DEFAULT] section if it exists
@@ -510,6 +1555,46 @@ impl Ini {
         self.cascade_defaults = cascade_defaults;
     }
 
+    ///Sets what happens when a key is repeated in a section of the same source (the default is
+    ///`KeyStrategy::Overwrite`, where the last value wins). It must be set before `load()` or `read()` is called in
+    ///order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{Ini, KeyStrategy};
+    ///
+    ///let mut config = Ini::new();
+    ///config.set_duplicate_keys(KeyStrategy::Append);
+    ///config.read(String::from("[remote]\nfetch = main\nfetch = dev")).unwrap();
+    ///assert_eq!(config.get("remote", "fetch").unwrap().lines().collect::<Vec<_>>(), ["main", "dev"]);
+    ///
+    ///config.set_duplicate_keys(KeyStrategy::Error);
+    ///let error = config.read(String::from("[remote]\nfetch = main\nfetch = dev")).unwrap_err();
+    ///assert_eq!(error, "line 3:1: Key fetch is already set in section remote");
+    ///```
+    ///Returns nothing.
+    pub fn set_duplicate_keys(&mut self, duplicate_keys: KeyStrategy) {
+        self.duplicate_keys = duplicate_keys;
+    }
+
+    ///Sets how special characters in values are escaped (the default is `Escapes::None`). It must be set before
+    ///`load()` or `read()` is called in order to take effect.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{Escapes, Ini};
+    ///
+    ///let mut config = Ini::new();
+    ///config.set_escapes(Escapes::Quoted);
+    ///config.read(String::from(r#"[alias]
+    ///greet = "echo hi; echo there "
+    ///path = C:\\Temp\tdir ; inline comment"#)).unwrap();
+    ///assert_eq!(config.get("alias", "greet").unwrap(), "echo hi; echo there ");
+    ///assert_eq!(config.get("alias", "path").unwrap(), "C:\\Temp\tdir");
+    ///```
+    ///Returns nothing.
+    pub fn set_escapes(&mut self, escapes: Escapes) {
+        self.escapes = escapes;
+    }
+
     ///Gets all the sections of the currently-stored `Map` in a vector.
     ///## Example
     ///```rust
@@ -524,6 +1609,63 @@ impl Ini {
         self.map.keys().cloned().collect()
     }
 
+    ///Iterates over the sections of the currently-stored `Map` without cloning them.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read_ref(String::from("[section]\nkey=value")).unwrap();
+    ///assert_eq!(config.iter_sections().collect::<Vec<_>>(), vec!["section"]);
+    ///```
+    ///Returns an iterator over `&str`.
+    pub fn iter_sections(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    ///Iterates over the key-value pairs stored in the defined section without cloning them.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read_ref(String::from("[Section]\nkey=value")).unwrap();
+    ///let pairs: Vec<_> = config.iter_section("SECTION").unwrap().collect();
+    ///assert_eq!(pairs, vec![("key", Some("value"))]);
+    ///```
+    ///Returns `Some(iterator)` over `(&str, Option<&str>)` if the section exists or else `None`.
+    pub fn iter_section(
+        &self,
+        section: &str,
+    ) -> Option<impl Iterator<Item = (&str, Option<&str>)>> {
+        let secmap = self.map.get(&self.section_name(section))?;
+        Some(
+            secmap
+                .iter()
+                .map(|(key, val)| (key.as_str(), val.as_deref())),
+        )
+    }
+
+    ///Iterates over every stored entry as `(section, key, value)` without cloning anything.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read_ref(String::from("[a]\nkey=value\n[b]\nvalueless")).unwrap();
+    ///let mut entries: Vec<_> = config.iter().collect();
+    ///entries.sort();
+    ///assert_eq!(entries, vec![("a", "key", Some("value")), ("b", "valueless", None)]);
+    ///```
+    ///Returns an iterator over `(&str, &str, Option<&str>)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, Option<&str>)> {
+        self.map.iter().flat_map(|(section, secmap)| {
+            secmap
+                .iter()
+                .map(move |(key, val)| (section.as_str(), key.as_str(), val.as_deref()))
+        })
+    }
+
     ///Loads a file from a defined path, parses it and puts the hashmap into our struct.
     ///At one time, it only stores one configuration, so each call to `load()` or `read()` will clear the existing `Map`, if present.
     ///## Example
@@ -541,7 +1683,23 @@ impl Ini {
         &mut self,
         path: T,
     ) -> Result<Map<String, Map<String, Option<String>>>, String> {
-        self.map = match self.parse(match fs::read_to_string(&path) {
+        self.load_ref(path)?;
+        Ok(self.map.clone())
+    }
+
+    ///Loads a file from a defined path, parses it and puts the hashmap into our struct, exactly like `load()` but
+    ///without cloning the stored `Map`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///let value = config.load_ref("tests/test.ini").unwrap().get("values", "int");
+    ///assert_eq!(value, Some(String::from("-31415")));
+    ///```
+    ///Returns `Ok(&self)` if no errors are thrown or else `Err(error_string)`.
+    pub fn load_ref<T: AsRef<Path>>(&mut self, path: T) -> Result<&Ini, String> {
+        (self.map, self.spans, self.names) = match self.parse(match fs::read_to_string(&path) {
             Err(why) => {
                 return Err(format!(
                     "couldn't read {}: {}",
@@ -558,14 +1716,14 @@ impl Ini {
                     why
                 ));
             }
-            Ok(map) => map,
+            Ok(parsed) => parsed,
         };
-        Ok(self.map.clone())
+        Ok(self)
     }
 
     /// Loads configuration data from any stream implementing `std::io::Read`, parses it, and applies it to the internal map.
     ///
-    /// This function reads the entire stream into a string, parses it as an INI file, and updates the internal map.
+    /// This function parses the stream one line at a time as an INI file, and updates the internal map.
     /// On error, returns a descriptive message. The previous map is replaced with the new one.
     ///
     /// # Arguments
@@ -586,22 +1744,34 @@ impl Ini {
     /// ```
     pub fn load_from_stream<R: std::io::Read>(
         &mut self,
-        mut reader: R,
+        reader: R,
     ) -> Result<Map<String, Map<String, Option<String>>>, String> {
-        let mut buf = String::new();
-        if let Err(err) = reader.read_to_string(&mut buf) {
-            return Err(format!("couldn't read from stream: {}", err));
-        }
-
-        self.map = match self.parse(buf) {
-            Err(why) => {
-                return Err(format!("couldn't read from stream: {}", why));
-            }
-            Ok(map) => map,
-        };
+        self.load_from_stream_ref(reader)?;
         Ok(self.map.clone())
     }
 
+    ///Loads configuration data from any stream implementing `std::io::Read`, exactly like `load_from_stream()` but
+    ///without cloning the stored `Map`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///let config = config.load_from_stream_ref("[section]\nkey=value".as_bytes()).unwrap();
+    ///assert_eq!(config.get("section", "key").unwrap(), "value");
+    ///```
+    ///Returns `Ok(&self)` if no errors are thrown or else `Err(error_string)`.
+    pub fn load_from_stream_ref<R: std::io::Read>(&mut self, reader: R) -> Result<&Ini, String> {
+        (self.map, self.spans, self.names) =
+            match self.parse_stream(std::io::BufReader::new(reader)) {
+                Err(why) => {
+                    return Err(format!("couldn't read from stream: {}", why));
+                }
+                Ok(parsed) => parsed,
+            };
+        Ok(self)
+    }
+
     ///Loads a file from a defined path, parses it and applies it to the existing hashmap in our struct.
     ///While `load()` will clear the existing `Map`, `load_and_append()` applies the new values on top of
     ///the existing hashmap, preserving previous values.
@@ -623,6 +1793,47 @@ impl Ini {
         &mut self,
         path: T,
     ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        self.load_and_append_ref(path)?;
+        Ok(self.map.clone())
+    }
+
+    ///Loads a file from a defined path, parses it and applies it to the existing hashmap in our struct, exactly like
+    ///`load_and_append()` but without cloning the stored `Map`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.load_ref("tests/test.ini").unwrap();
+    ///let config = config.load_and_append_ref("tests/test_more.ini").unwrap();
+    ///assert_eq!(config.get("topsecret", "kfc"), Some(String::from("redacted")));
+    ///```
+    ///Returns `Ok(&self)` if no errors are thrown or else `Err(error_string)`.
+    pub fn load_and_append_ref<T: AsRef<Path>>(&mut self, path: T) -> Result<&Ini, String> {
+        self.load_and_append_with(path, &AppendOptions::default())?;
+        Ok(self)
+    }
+
+    ///Loads a file from a defined path, parses it and applies it to the existing hashmap in our struct like
+    ///`load_and_append()`, but resolves keys and sections that already exist with the given strategies.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{AppendOptions, Ini, KeyStrategy};
+    ///
+    ///let mut config = Ini::new();
+    ///config.load("tests/test.ini").unwrap();
+    ///let options = AppendOptions::new_with_params(KeyStrategy::KeepFirst, Default::default());
+    ///let report = config.load_and_append_with("tests/test_more.ini", &options).unwrap();
+    ///assert_eq!(config.get("topsecret", "kfc").unwrap(), "the secret herb is orega-");
+    ///assert!(report.overridden.iter().any(|o| o.key == "kfc" && o.appended.as_deref() == Some("redacted")));
+    ///```
+    ///Returns `Ok(report)` with an `AppendReport` of the keys and sections that already existed if no errors are
+    ///thrown or else `Err(error_string)`, in which case nothing was changed.
+    pub fn load_and_append_with<T: AsRef<Path>>(
+        &mut self,
+        path: T,
+        options: &AppendOptions,
+    ) -> Result<AppendReport, String> {
         let loaded = match self.parse(match fs::read_to_string(&path) {
             Err(why) => {
                 return Err(format!(
@@ -640,14 +1851,10 @@ impl Ini {
                     why
                 ));
             }
-            Ok(map) => map,
+            Ok(parsed) => parsed,
         };
-
-        for (section, section_map) in loaded {
-            self.map.entry(section).or_default().extend(section_map);
-        }
-
-        Ok(self.map.clone())
+        self.append(loaded, options)
+            .map_err(|why| format!("couldn't append {}: {}", &path.as_ref().display(), why))
     }
 
     ///Reads an input string, parses it and puts the hashmap into our struct.
@@ -672,10 +1879,31 @@ impl Ini {
         &mut self,
         input: String,
     ) -> Result<Map<String, Map<String, Option<String>>>, String> {
-        self.map = self.parse(input)?;
+        self.read_ref(input)?;
         Ok(self.map.clone())
     }
 
+    ///Reads an input string, parses it and puts the hashmap into our struct, exactly like `read()` but without
+    ///cloning the stored `Map`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///let this_year = config
+    ///    .read_ref(String::from(
+    ///        "[2000s]
+    ///        2020 = bad"))
+    ///    .unwrap()
+    ///    .get("2000s", "2020");
+    ///assert_eq!(this_year, Some(String::from("bad"))); // value accessible!
+    ///```
+    ///Returns `Ok(&self)` if no errors are thrown or else `Err(error_string)`.
+    pub fn read_ref(&mut self, input: String) -> Result<&Ini, String> {
+        (self.map, self.spans, self.names) = self.parse(input)?;
+        Ok(self)
+    }
+
     ///Reads an input string, parses it and applies it to the existing hashmap in our struct.
     ///While `read()` and `load()` will clear the existing `Map`, `read_and_append()` applies the new
     ///values on top of the existing hashmap, preserving previous values.
@@ -701,19 +1929,161 @@ impl Ini {
     ///assert_eq!(few_years_ago, "terrible"); // value updated!
     ///assert_eq!(this_year, "better"); // keeps old values!
     ///```
-    ///Returns `Ok(map)` with a clone of the stored `Map` if no errors are thrown or else `Err(error_string)`.
-    ///Use `get_mut_map()` if you want a mutable reference.
-    pub fn read_and_append(
+    ///Returns `Ok(map)` with a clone of the stored `Map` if no errors are thrown or else `Err(error_string)`.
+    ///Use `get_mut_map()` if you want a mutable reference.
+    pub fn read_and_append(
+        &mut self,
+        input: String,
+    ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        self.read_and_append_ref(input)?;
+        Ok(self.map.clone())
+    }
+
+    ///Reads an input string, parses it and applies it to the existing hashmap in our struct, exactly like
+    ///`read_and_append()` but without cloning the stored `Map`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read_ref(String::from("[2000s]\n2020 = bad\n2023 = better")).unwrap();
+    ///let config = config.read_and_append_ref(String::from("[2000s]\n2020 = terrible")).unwrap();
+    ///assert_eq!(config.get("2000s", "2020"), Some(String::from("terrible"))); // value updated!
+    ///assert_eq!(config.get("2000s", "2023"), Some(String::from("better"))); // keeps old values!
+    ///```
+    ///Returns `Ok(&self)` if no errors are thrown or else `Err(error_string)`.
+    pub fn read_and_append_ref(&mut self, input: String) -> Result<&Ini, String> {
+        self.read_and_append_with(input, &AppendOptions::default())?;
+        Ok(self)
+    }
+
+    ///Reads an input string, parses it and applies it to the existing hashmap in our struct like
+    ///`read_and_append()`, but resolves keys and sections that already exist with the given strategies.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{AppendOptions, Ini, KeyStrategy, SectionStrategy};
+    ///
+    ///let mut config = Ini::new();
+    ///config.read(String::from("[hosts]\nallow = 10.0.0.1\n[log]\nlevel = info\nfile = app.log")).unwrap();
+    ///
+    ///let append = AppendOptions::new_with_params(KeyStrategy::Append, SectionStrategy::Merge);
+    ///let report = config.read_and_append_with(String::from("[hosts]\nallow = 10.0.0.2"), &append).unwrap();
+    ///assert_eq!(config.get("hosts", "allow").unwrap(), "10.0.0.1\n10.0.0.2");
+    ///assert_eq!(report.overridden[0].previous.as_deref(), Some("10.0.0.1"));
+    ///
+    ///let replace = AppendOptions::new_with_params(KeyStrategy::Overwrite, SectionStrategy::Replace);
+    ///let report = config.read_and_append_with(String::from("[log]\nlevel = warn"), &replace).unwrap();
+    ///assert_eq!(config.get("log", "file"), None);
+    ///assert_eq!(report.replaced_sections, ["log"]);
+    ///
+    ///let error = AppendOptions::new_with_params(KeyStrategy::Error, SectionStrategy::Merge);
+    ///assert!(config.read_and_append_with(String::from("[log]\nlevel = debug"), &error).is_err());
+    ///assert_eq!(config.get("log", "level").unwrap(), "warn");
+    ///```
+    ///Returns `Ok(report)` with an `AppendReport` of the keys and sections that already existed if no errors are
+    ///thrown or else `Err(error_string)`, in which case nothing was changed.
+    pub fn read_and_append_with(
         &mut self,
         input: String,
-    ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        options: &AppendOptions,
+    ) -> Result<AppendReport, String> {
         let loaded = self.parse(input)?;
+        self.append(loaded, options)
+    }
 
+    ///Private function that applies a freshly parsed Map on top of the stored one with the given strategies. With
+    ///`KeyStrategy::Error`, every key is checked before anything is changed.
+    fn append(
+        &mut self,
+        (loaded, spans, _): Parsed,
+        options: &AppendOptions,
+    ) -> Result<AppendReport, String> {
+        let replace = options.sections == SectionStrategy::Replace;
+        let mut report = AppendReport::default();
+        // Loaded names are resolved to the spelling they are stored under, and every key to the value it ends up
+        // with, before anything is changed.
+        let mut resolved = Vec::with_capacity(loaded.len());
         for (section, section_map) in loaded {
-            self.map.entry(section).or_default().extend(section_map);
+            let section = self.section_name(&section);
+            let stored = self.map.get(&section);
+            if replace && stored.is_some() {
+                report.replaced_sections.push(section.clone());
+            }
+            let mut keys = Vec::with_capacity(section_map.len());
+            for (key, appended) in section_map {
+                let key = self.key_name(&section, &key);
+                let previous = stored
+                    .filter(|_| !replace)
+                    .and_then(|stored| stored.get(&key))
+                    .filter(|previous| **previous != appended);
+                let Some(previous) = previous else {
+                    keys.push((key, appended));
+                    continue;
+                };
+                let value = match options.keys {
+                    KeyStrategy::KeepFirst => previous.clone(),
+                    KeyStrategy::Overwrite => appended.clone(),
+                    KeyStrategy::Error => {
+                        return Err(format!(
+                            "Key {} in section {} is already set to a different value",
+                            key, section
+                        ));
+                    }
+                    KeyStrategy::Append => match (previous, &appended) {
+                        (Some(previous), Some(appended)) => {
+                            Some(format!("{}{}{}", previous, LINE_ENDING, appended))
+                        }
+                        (previous, appended) => previous.clone().or_else(|| appended.clone()),
+                    },
+                };
+                report.overridden.push(Override {
+                    section: section.clone(),
+                    key: key.clone(),
+                    previous: previous.clone(),
+                    appended,
+                    value: value.clone(),
+                });
+                keys.push((key, value));
+            }
+            resolved.push((section, keys));
         }
 
-        Ok(self.map.clone())
+        // Spans of appended keys point into the appended source, unless the stored value was kept.
+        let keep_first = options.keys == KeyStrategy::KeepFirst;
+        for (section, span) in spans.sections {
+            let section = self.section_name(&section);
+            self.spans.sections.entry(section).or_insert(span);
+        }
+        for (section, keys) in spans.keys {
+            let section = self.section_name(&section);
+            let keys: Vec<_> = keys
+                .into_iter()
+                .map(|(key, span)| (self.key_name(&section, &key), span))
+                .collect();
+            let stored = self.spans.keys.entry(section).or_default();
+            if replace {
+                stored.clear();
+            }
+            for (key, span) in keys {
+                if !(keep_first && stored.contains_key(&key)) {
+                    stored.insert(key, span);
+                }
+            }
+        }
+
+        // Appended names are not indexed, so the index is rebuilt when it is needed next.
+        self.names = None;
+        for (section, keys) in resolved {
+            if replace {
+                self.map.insert(section, keys.into_iter().collect());
+                continue;
+            }
+            let stored = self.map.entry(section).or_default();
+            for (key, value) in keys {
+                stored.insert(key, value);
+            }
+        }
+        Ok(report)
     }
 
     ///Writes the current configuation to the specified path using default formatting.
@@ -804,250 +2174,311 @@ impl Ini {
 
     ///Private function that converts the currently stored configuration into a valid ini-syntax string.
     fn unparse(&self, write_options: &WriteOptions) -> String {
-        // push key/value pairs in outmap to out string.
-        fn unparse_key_values(
-            out: &mut String,
-            outmap: &Map<String, Option<String>>,
-            multiline: bool,
-            space_around_delimiters: bool,
-            indent: usize,
-        ) {
-            let delimiter = if space_around_delimiters { " = " } else { "=" };
-            let empty_delimiter = delimiter.trim_end();
-            // Precompute the multiline indentation once rather than rebuilding
-            // it for every continuation line. Stays empty (no allocation) when
-            // multiline output is disabled.
-            let indent_str = if multiline {
-                " ".repeat(indent)
-            } else {
-                String::new()
-            };
-            for (key, val) in outmap.iter() {
-                out.push_str(key);
-
-                if let Some(value) = val {
-                    if value.is_empty() {
-                        out.push_str(empty_delimiter);
-                    } else {
-                        out.push_str(delimiter);
-                    }
-
-                    if multiline {
-                        let mut lines = value.lines();
-
-                        out.push_str(lines.next().unwrap_or_default());
-
-                        for line in lines {
-                            out.push_str(LINE_ENDING);
-                            if !line.is_empty() {
-                                out.push_str(&indent_str);
-                                out.push_str(line);
-                            }
-                        }
-                    } else {
-                        out.push_str(value);
-                    }
-                }
+        self.unparse_annotated(write_options, None, &|_, _| Annotation::default())
+    }
 
-                out.push_str(LINE_ENDING);
-            }
+    ///Private function that unparses like `unparse()`, asking `annotate` for the comments to write around every
+    ///section header (with no key) and every key, and whether to comment out the key itself. If `order` is given,
+    ///the sections and keys it lists are written in its order, followed by any others in map order.
+    pub(crate) fn unparse_annotated(
+        &self,
+        write_options: &WriteOptions,
+        order: Option<&[(String, Vec<String>)]>,
+        annotate: &dyn Fn(&str, Option<&str>) -> Annotation,
+    ) -> String {
+        // Orders the keys of a section, listed keys first.
+        fn ordered<'a>(
+            secmap: &'a Map<String, Option<String>>,
+            keys: &[String],
+        ) -> Vec<(&'a String, &'a Option<String>)> {
+            let mut entries: Vec<_> = keys
+                .iter()
+                .filter_map(|key| secmap.get_key_value(key))
+                .collect();
+            entries.extend(secmap.iter().filter(|(key, _)| !keys.contains(key)));
+            entries
         }
 
         let line_endings = LINE_ENDING.repeat(write_options.blank_lines_between_sections);
         let mut out = String::new();
 
-        if let Some(defaultmap) = self.map.get(&self.default_section) {
-            unparse_key_values(
+        let mut sections = Vec::new();
+        for (section, keys) in order.unwrap_or_default() {
+            if let Some((name, secmap)) = self.map.get_key_value(section) {
+                sections.push((name, ordered(secmap, keys)));
+            }
+        }
+        for (section, secmap) in self.map.iter() {
+            if order.is_none_or(|order| !order.iter().any(|(name, _)| name == section)) {
+                sections.push((section, ordered(secmap, &[])));
+            }
+        }
+
+        let default_section = self.default_section_name();
+        if let Some((_, defaultmap)) = sections.iter().find(|(name, _)| **name == default_section) {
+            let annotation = annotate(&default_section, None);
+            write_comments(&mut out, &annotation);
+            if write_options.write_default_section_header {
+                let start = out.len();
+                write!(out, "[{}]", default_section).unwrap();
+                out.push_str(LINE_ENDING);
+                write_inline_comment(&mut out, start, &annotation);
+            }
+            self.unparse_key_values(
                 &mut out,
+                &default_section,
                 defaultmap,
-                self.multiline,
-                write_options.space_around_delimiters,
-                write_options.multiline_line_indentation,
+                Ini::key_width(defaultmap, write_options),
+                write_options,
+                annotate,
             );
         }
 
-        let mut is_first = true;
-        for (section, secmap) in self.map.iter() {
-            if !is_first {
-                out.push_str(line_endings.as_ref());
-            }
-            if section != &self.default_section {
+        for (section, secmap) in sections.iter() {
+            if **section != default_section {
+                if !out.is_empty() {
+                    out.push_str(line_endings.as_ref());
+                }
+                let annotation = annotate(section, None);
+                write_comments(&mut out, &annotation);
+                let start = out.len();
                 write!(out, "[{}]", section).unwrap();
                 out.push_str(LINE_ENDING);
-                unparse_key_values(
+                write_inline_comment(&mut out, start, &annotation);
+                self.unparse_key_values(
                     &mut out,
+                    section,
                     secmap,
-                    self.multiline,
-                    write_options.space_around_delimiters,
-                    write_options.multiline_line_indentation,
+                    Ini::key_width(secmap, write_options),
+                    write_options,
+                    annotate,
                 );
             }
-            is_first = false;
         }
         out
     }
 
-    ///Private function that parses ini-style syntax into a Map.
-    fn parse(&self, input: String) -> Result<Map<String, Map<String, Option<String>>>, String> {
-        let inline_comment_symbols: &[char] = self
-            .inline_comment_symbols
-            .as_deref()
-            .unwrap_or_else(|| self.comment_symbols.as_ref());
-        let mut map: Map<String, Map<String, Option<String>>> = Map::new();
-        let mut section = self.default_section.clone();
-        // Tracks whether `section` is already present in `map`. Section headers
-        // always insert their section, so the only section that can still be
-        // missing is the (lazily created) default section.
-        let mut section_exists = false;
-        let mut current_key: Option<String> = None;
-
-        let caser = |val: &str| {
-            if self.case_sensitive {
-                val.to_owned()
-            } else {
-                val.to_lowercase()
-            }
+    ///Private function that pushes key/value pairs to the out string with the given formatting options and the
+    ///escapes of the `Ini`, asking `annotate` for the comments to write around every key. Keys with values are
+    ///padded to `key_width` characters, see `key_width()`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn unparse_key_values(
+        &self,
+        out: &mut String,
+        section: &str,
+        outmap: &[(&String, &Option<String>)],
+        key_width: usize,
+        write_options: &WriteOptions,
+        annotate: &dyn Fn(&str, Option<&str>) -> Annotation,
+    ) {
+        let multiline = self.multiline;
+        let comment_symbol = self.comment_symbols.first().copied().unwrap_or(';');
+        let inline_comment_symbols = match &self.inline_comment_symbols {
+            _ if !self.enable_inline_comments => &[][..],
+            Some(symbols) => symbols,
+            None => &self.comment_symbols,
         };
-
-        // Track blank lines to preserve them in multiline values.
-        let mut blank_lines = 0usize;
-
-        for (num, raw_line) in input.lines().enumerate() {
-            let line = raw_line.trim();
-
-            // If the line is _just_ a comment, skip it entirely. Only the first
-            // character can make this a full-line comment, so there's no need to
-            // scan the whole line.
-            if line.starts_with(|c: char| self.comment_symbols.contains(&c)) {
-                continue;
-            }
-
-            // Skip empty lines, but keep track of them for multiline values.
-            if line.is_empty() {
-                blank_lines += 1;
-                continue;
-            }
-
-            // Strip a trailing inline comment if enabled. `line` is already
-            // trimmed on both ends, so only the comment-stripped slice needs to
-            // be re-trimmed.
-            let trimmed = if self.enable_inline_comments {
-                match line.find(|c: char| inline_comment_symbols.contains(&c)) {
-                    Some(idx) => line[..idx].trim(),
-                    None => line,
+        let push_escaped = |out: &mut String, value: &str| {
+            out.push_str(&escape(value, self.escapes, inline_comment_symbols))
+        };
+        let delimiter = if write_options.space_around_delimiters {
+            format!(" {} ", write_options.delimiter)
+        } else {
+            write_options.delimiter.to_string()
+        };
+        let empty_delimiter = delimiter.trim_end();
+        // Precompute the multiline indentation once rather than rebuilding
+        // it for every continuation line. Stays empty (no allocation) when
+        // multiline output is disabled.
+        let indent_str = if multiline {
+            " ".repeat(write_options.multiline_line_indentation)
+        } else {
+            String::new()
+        };
+        let wrap_width = write_options.wrap_width.filter(|_| multiline);
+        for (key, val) in outmap.iter() {
+            let annotation = annotate(section, Some(key));
+            write_comments(out, &annotation);
+            let start = out.len();
+            out.push_str(key);
+
+            if let Some(value) = val {
+                let key_len = key.chars().count();
+                for _ in key_len..key_width {
+                    out.push(' ');
+                }
+                if value.is_empty() {
+                    out.push_str(empty_delimiter);
+                } else {
+                    out.push_str(&delimiter);
                 }
-            } else {
-                line
-            };
-
-            if trimmed.starts_with('[') {
-                match trimmed.rfind(']') {
-                    Some(end) => {
-                        section = caser(trimmed[1..end].trim());
-
-                        map.entry(section.clone()).or_default();
-                        section_exists = true;
 
-                        continue;
-                    }
-                    None => {
-                        return Err(format!(
-                            "line {}: Found opening bracket for section name but no closing bracket",
-                            num
-                        ));
+                if multiline {
+                    let first_offset = key_len.max(key_width) + delimiter.chars().count();
+                    let mut is_first_line = true;
+
+                    for line in value.lines() {
+                        let wrapped = match wrap_width {
+                            Some(width) => wrap_line(
+                                line,
+                                width,
+                                if is_first_line {
+                                    first_offset
+                                } else {
+                                    write_options.multiline_line_indentation
+                                },
+                                write_options.multiline_line_indentation,
+                            ),
+                            None => vec![line.to_owned()],
+                        };
+                        for part in wrapped {
+                            if !is_first_line {
+                                out.push_str(LINE_ENDING);
+                                if !part.is_empty() {
+                                    out.push_str(&indent_str);
+                                }
+                            }
+                            push_escaped(out, &part);
+                            is_first_line = false;
+                        }
                     }
+                } else {
+                    push_escaped(out, value);
                 }
             }
 
-            // Ensure the current section exists, then take a mutable handle to
-            // it. Using `get_mut` on the common path avoids cloning the section
-            // name on every single line.
-            if !section_exists {
-                map.entry(section.clone()).or_default();
-                section_exists = true;
+            out.push_str(LINE_ENDING);
+            write_inline_comment(out, start, &annotation);
+            // Commented out keys get the comment symbol on every line, including continuation lines.
+            if annotation.commented_out {
+                let written = out.split_off(start);
+                for line in written.split_inclusive(LINE_ENDING) {
+                    out.push(comment_symbol);
+                    out.push(' ');
+                    out.push_str(line);
+                }
             }
-            let valmap = map.get_mut(&section).unwrap();
+        }
+    }
 
-            if raw_line.starts_with(char::is_whitespace) && self.multiline {
-                let key = match current_key.as_ref() {
-                    Some(x) => x,
-                    None => {
-                        return Err(format!(
-                            "line {}: Started with indentation but there is no current entry",
-                            num,
-                        ));
-                    }
-                };
+    ///Private function that returns the width keys are padded to when writing the given key/value pairs, which is
+    ///`0` unless delimiters are aligned. Only keys with values are padded, valueless keys have no delimiter to align.
+    pub(crate) fn key_width(
+        outmap: &[(&String, &Option<String>)],
+        write_options: &WriteOptions,
+    ) -> usize {
+        if write_options.align_delimiters {
+            outmap
+                .iter()
+                .filter(|(_, val)| val.is_some())
+                .map(|(key, _)| key.chars().count())
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        }
+    }
 
-                let val = valmap
-                    .entry(key.clone())
-                    .or_insert_with(|| Some(String::new()));
+    ///Private function that parses ini-style syntax into a Map and the spans of its sections and keys.
+    fn parse(&self, input: String) -> Result<Parsed, String> {
+        self.parse_stream(input.as_bytes())
+    }
 
-                match val {
-                    Some(s) => {
-                        for _ in 0..blank_lines {
-                            s.push_str(LINE_ENDING);
-                        }
-                        s.push_str(LINE_ENDING);
-                        s.push_str(trimmed);
-                    }
-                    None => {
-                        let mut s = String::with_capacity(
-                            (blank_lines + 1) * LINE_ENDING.len() + trimmed.len(),
-                        );
-                        for _ in 0..blank_lines {
-                            s.push_str(LINE_ENDING);
-                        }
-                        s.push_str(LINE_ENDING);
-                        s.push_str(trimmed);
-                        *val = Some(s);
-                    }
-                }
-            } else {
-                match trimmed.find(&self.delimiters[..]) {
-                    Some(delimiter) => {
-                        let key = caser(trimmed[..delimiter].trim());
-
-                        if key.is_empty() {
-                            return Err(format!("line {}:{}: Key cannot be empty", num, delimiter));
-                        } else {
-                            // `current_key` is only read when stitching together
-                            // multiline values, so only clone the key then.
-                            if self.multiline {
-                                current_key = Some(key.clone());
-                            }
+    ///Private function that parses ini-style syntax from a stream into a Map and the spans of its sections and
+    ///keys, one line at a time.
+    fn parse_stream<R: std::io::BufRead>(&self, reader: R) -> Result<Parsed, String> {
+        let mut spans = Spans::default();
+        let (map, names) = build_map(
+            Events::from_lexer(reader, self.lexer()),
+            &self.default_section,
+            self.casing(),
+            self.multiline,
+            self.duplicate_keys,
+            self.escapes,
+            Some(&mut spans),
+        )?;
+        Ok((map, spans, names))
+    }
 
-                            let value = trimmed[delimiter + 1..].trim().to_owned();
+    ///Private function that creates a parser lexer with the current options.
+    fn lexer(&self) -> Lexer {
+        Lexer::new(
+            &self.comment_symbols,
+            self.inline_comment_symbols.as_deref(),
+            &self.delimiters,
+            self.multiline,
+            self.enable_inline_comments,
+            self.escapes,
+        )
+    }
 
-                            valmap.insert(key, Some(value));
-                        }
-                    }
-                    None => {
-                        let key = caser(trimmed);
-                        if self.multiline {
-                            current_key = Some(key.clone());
-                        }
+    ///Private function that cases things automatically depending on the set variables.
+    fn autocase(&self, section: &str, key: &str) -> (String, String) {
+        let section = self.section_name(section);
+        let key = self.key_name(&section, key);
+        (section, key)
+    }
 
-                        valmap.insert(key, None);
-                    }
-                }
-            }
+    ///Private function that returns the effective case handling.
+    fn casing(&self) -> Casing {
+        Casing::new(
+            self.case_sensitive,
+            self.section_case,
+            self.key_case,
+            self.case_folding,
+        )
+    }
 
-            blank_lines = 0;
+    ///Private function that returns the index of the stored names for updating, rebuilding it if it went stale.
+    ///Returns `None` (and drops the index) if no names are case-preserving, since nothing looks them up then.
+    fn names_mut(&mut self) -> Option<&mut NameIndex> {
+        let casing = self.casing();
+        if casing.section != CasePolicy::Preserve && casing.key != CasePolicy::Preserve {
+            self.names = None;
+            return None;
         }
+        Some(
+            self.names
+                .get_or_insert_with(|| NameIndex::from_map(&self.map, casing.folding)),
+        )
+    }
 
-        Ok(map)
+    ///Private function that returns the name a section is (or would be) stored under.
+    pub(crate) fn section_name(&self, section: &str) -> String {
+        let casing = self.casing();
+        resolve_name(
+            Some(&self.map),
+            section,
+            casing.section,
+            casing.folding,
+            self.names.as_ref().map(|names| &names.sections),
+        )
+        .into_owned()
     }
 
-    ///Private function that cases things automatically depending on the set variable.
-    fn autocase(&self, section: &str, key: &str) -> (String, String) {
-        if self.case_sensitive {
-            (section.to_owned(), key.to_owned())
-        } else {
-            (section.to_lowercase(), key.to_lowercase())
+    ///Private function that returns the name the default section is stored under. The default section name is
+    ///used as-is unless section names are case-preserving, where it may have been stored with another spelling.
+    pub(crate) fn default_section_name(&self) -> String {
+        let casing = self.casing();
+        match casing.section {
+            CasePolicy::Preserve => self.section_name(&self.default_section),
+            _ => self.default_section.clone(),
         }
     }
 
+    ///Private function that returns the name a key is (or would be) stored under in an already-cased section.
+    pub(crate) fn key_name(&self, section: &str, key: &str) -> String {
+        let casing = self.casing();
+        resolve_name(
+            self.map.get(section),
+            key,
+            casing.key,
+            casing.folding,
+            self.names.as_ref().and_then(|names| names.keys(section)),
+        )
+        .into_owned()
+    }
+
     ///Returns a clone of the stored value from the key stored in the defined section.
     ///Unlike accessing the map directly, `get()` can process your input to make case-insensitive access *if* the
     ///default constructor is used.
@@ -1064,19 +2495,7 @@ impl Ini {
     ///Returns `Some(value)` of type `String` if value is found or else returns `None`.
     pub fn get(&self, section: &str, key: &str) -> Option<String> {
         let (section, key) = self.autocase(section, key);
-        let val = match self.map.get(&section) {
-            Some(secmap) => match secmap.get(&key) {
-                Some(val) => val.clone(),
-                None => None,
-            },
-            None => None,
-        };
-
-        if val.is_none() && self.cascade_defaults {
-            return self.map.get(&self.default_section)?.get(&key)?.clone();
-        }
-
-        val
+        self.get_cased(&section, &key).map(String::from)
     }
 
     ///Parses the stored value from the key stored in the defined section to a `bool`.
@@ -1094,18 +2513,7 @@ impl Ini {
     ///If the parsing fails, it returns an `Err(string)`.
     pub fn getbool(&self, section: &str, key: &str) -> Result<Option<bool>, String> {
         let (section, key) = self.autocase(section, key);
-        match self.map.get(&section).and_then(|secmap| secmap.get(&key)) {
-            Some(Some(inner)) => match inner.to_lowercase().parse::<bool>() {
-                Err(why) => Err(why.to_string()),
-                Ok(boolean) => Ok(Some(boolean)),
-            },
-            _ => {
-                if self.cascade_defaults && section != self.default_section {
-                    return self.getbool(&self.default_section, &key);
-                }
-                Ok(None)
-            }
-        }
+        self.parse_cased(&section, &key, parse_bool)
     }
 
     ///Parses the stored value from the key stored in the defined section to a `bool`. For ease of use, the function converts the type coerces a match.
@@ -1124,39 +2532,7 @@ impl Ini {
     ///If the parsing fails, it returns an `Err(string)`.
     pub fn getboolcoerce(&self, section: &str, key: &str) -> Result<Option<bool>, String> {
         let (section, key) = self.autocase(section, key);
-        match self.map.get(&section).and_then(|secmap| secmap.get(&key)) {
-            Some(Some(inner)) => {
-                let boolval = &inner.to_lowercase()[..];
-                if self
-                    .boolean_values
-                    .get(&true)
-                    .unwrap()
-                    .iter()
-                    .any(|elem| elem == boolval)
-                {
-                    Ok(Some(true))
-                } else if self
-                    .boolean_values
-                    .get(&false)
-                    .unwrap()
-                    .iter()
-                    .any(|elem| elem == boolval)
-                {
-                    Ok(Some(false))
-                } else {
-                    Err(format!(
-                        "Unable to parse value into bool at {}:{}",
-                        section, key
-                    ))
-                }
-            }
-            _ => {
-                if self.cascade_defaults && section != self.default_section {
-                    return self.getboolcoerce(&self.default_section, &key);
-                }
-                Ok(None)
-            }
-        }
+        self.getboolcoerce_cased(&section, &key)
     }
 
     ///Parses the stored value from the key stored in the defined section to an `i64`.
@@ -1173,18 +2549,7 @@ impl Ini {
     ///If the parsing fails, it returns an `Err(string)`.
     pub fn getint(&self, section: &str, key: &str) -> Result<Option<i64>, String> {
         let (section, key) = self.autocase(section, key);
-        match self.map.get(&section).and_then(|secmap| secmap.get(&key)) {
-            Some(Some(inner)) => match inner.parse::<i64>() {
-                Err(why) => Err(why.to_string()),
-                Ok(int) => Ok(Some(int)),
-            },
-            _ => {
-                if self.cascade_defaults && section != self.default_section {
-                    return self.getint(&self.default_section, &key);
-                }
-                Ok(None)
-            }
-        }
+        self.parse_cased(&section, &key, parse_value::<i64>)
     }
 
     ///Parses the stored value from the key stored in the defined section to a `u64`.
@@ -1201,18 +2566,7 @@ impl Ini {
     ///If the parsing fails, it returns an `Err(string)`.
     pub fn getuint(&self, section: &str, key: &str) -> Result<Option<u64>, String> {
         let (section, key) = self.autocase(section, key);
-        match self.map.get(&section).and_then(|secmap| secmap.get(&key)) {
-            Some(Some(inner)) => match inner.parse::<u64>() {
-                Err(why) => Err(why.to_string()),
-                Ok(uint) => Ok(Some(uint)),
-            },
-            _ => {
-                if self.cascade_defaults && section != self.default_section {
-                    return self.getuint(&self.default_section, &key);
-                }
-                Ok(None)
-            }
-        }
+        self.parse_cased(&section, &key, parse_value::<u64>)
     }
 
     ///Parses the stored value from the key stored in the defined section to a `f64`.
@@ -1229,18 +2583,111 @@ impl Ini {
     ///If the parsing fails, it returns an `Err(string)`.
     pub fn getfloat(&self, section: &str, key: &str) -> Result<Option<f64>, String> {
         let (section, key) = self.autocase(section, key);
-        match self.map.get(&section).and_then(|secmap| secmap.get(&key)) {
-            Some(Some(inner)) => match inner.parse::<f64>() {
-                Err(why) => Err(why.to_string()),
-                Ok(float) => Ok(Some(float)),
-            },
-            _ => {
-                if self.cascade_defaults && section != self.default_section {
-                    return self.getfloat(&self.default_section, &key);
-                }
-                Ok(None)
-            }
+        self.parse_cased(&section, &key, parse_value::<f64>)
+    }
+
+    ///Private function that looks up a value by its already-cased section and key, cascading to the default
+    ///section if enabled.
+    fn get_cased(&self, section: &str, key: &str) -> Option<&str> {
+        let val = self
+            .map
+            .get(section)
+            .and_then(|secmap| secmap.get(key))
+            .and_then(|val| val.as_deref());
+
+        if val.is_none() && self.cascade_defaults {
+            let default_section = self.default_section_name();
+            let key = self.key_name(&default_section, key);
+            return self.map.get(&default_section)?.get(&key)?.as_deref();
+        }
+
+        val
+    }
+
+    ///Private function that coerces a value looked up by its already-cased section and key into a `bool`.
+    fn getboolcoerce_cased(&self, section: &str, key: &str) -> Result<Option<bool>, String> {
+        self.parse_cased(section, key, |inner| {
+            coerce_bool(&self.boolean_values, inner, section, key)
+        })
+    }
+
+    ///Private function that parses a value looked up by its already-cased section and key, prefixing errors with
+    ///the `line:column` position of the value if it is known.
+    fn parse_cased<T>(
+        &self,
+        section: &str,
+        key: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        let Some(value) = self.get_cased(section, key) else {
+            return Ok(None);
+        };
+        parse(value)
+            .map(Some)
+            .map_err(|why| match self.value_span_cased(section, key) {
+                Some(span) => format!("line {}:{}: {}", span.line, span.column, why),
+                None => why,
+            })
+    }
+
+    ///Private function that returns where the value `get_cased()` finds was parsed, which is in the default
+    ///section if it cascades there.
+    fn value_span_cased(&self, section: &str, key: &str) -> Option<Span> {
+        let span = |section: &str, key: &str| {
+            let span = self.spans.keys.get(section)?.get(key)?;
+            Some(span.value.unwrap_or(span.key))
+        };
+        let has_value = self
+            .map
+            .get(section)
+            .and_then(|secmap| secmap.get(key))
+            .is_some_and(Option::is_some);
+        if !has_value && self.cascade_defaults {
+            let default_section = self.default_section_name();
+            let key = self.key_name(&default_section, key);
+            return span(&default_section, &key);
         }
+        span(section, key)
+    }
+
+    ///Returns where the key of the defined section and its value were found when they were last loaded or read.
+    ///Byte offsets count from the start of the source the key came from, which is the appended source for keys
+    ///set by the `*_and_append` functions.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read(String::from("[section]\nKey = value")).unwrap();
+    ///let span = config.span_of("section", "key").unwrap();
+    ///assert_eq!((span.key.start, span.key.end), (10, 13));
+    ///let value = span.value.unwrap();
+    ///assert_eq!((value.line, value.column, value.end_column), (2, 7, 12));
+    ///assert!(config.span_of("section", "missing").is_none());
+    ///```
+    ///Returns `Some(KeySpan)` if the key exists and was parsed from a source or else `None`.
+    pub fn span_of(&self, section: &str, key: &str) -> Option<KeySpan> {
+        let (section, key) = self.autocase(section, key);
+        self.map.get(&section)?.get(&key)?;
+        self.spans.keys.get(&section)?.get(&key).copied()
+    }
+
+    ///Returns where the header of the defined section was found when it was last loaded or read.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read(String::from("key=value\n[ section ]")).unwrap();
+    ///let span = config.section_span("section").unwrap();
+    ///assert_eq!((span.start, span.end, span.line, span.column), (10, 21, 2, 1));
+    ///assert!(config.section_span("default").is_none()); // the default section has no header
+    ///```
+    ///Returns `Some(Span)` if the section exists and its header was parsed from a source or else `None`.
+    pub fn section_span(&self, section: &str) -> Option<Span> {
+        let section = self.section_name(section);
+        self.map.get(&section)?;
+        self.spans.sections.get(&section).copied()
     }
 
     ///Returns a clone of the `Map` stored in our struct.
@@ -1298,6 +2745,8 @@ impl Ini {
     ///```
     ///If you just need to access the map without mutating, use `get_map_ref()` or make a clone with `get_map()` instead.
     pub fn get_mut_map(&mut self) -> &mut Map<String, Map<String, Option<String>>> {
+        // Names can be changed behind our back, so the index is rebuilt when it is needed next.
+        self.names = None;
         &mut self.map
     }
 
@@ -1325,6 +2774,10 @@ impl Ini {
         value: Option<String>,
     ) -> Option<Option<String>> {
         let (section, key) = self.autocase(section, key);
+        if let Some(names) = self.names_mut() {
+            names.add_section(&section);
+            names.add_key(&section, &key);
+        }
         match self.map.get_mut(&section) {
             Some(secmap) => secmap.insert(key, value),
             None => {
@@ -1377,6 +2830,8 @@ impl Ini {
     ///Returns nothing.
     pub fn clear(&mut self) {
         self.map.clear();
+        self.spans = Spans::default();
+        self.names = None;
     }
 
     ///Removes a section from the hashmap, returning the properties stored in the section if the section was previously in the map.
@@ -1393,11 +2848,12 @@ impl Ini {
     ///```
     ///Returns `Some(section_map)` if the section exists or else, `None`.
     pub fn remove_section(&mut self, section: &str) -> Option<Map<String, Option<String>>> {
-        let section = if self.case_sensitive {
-            section.to_owned()
-        } else {
-            section.to_lowercase()
-        };
+        let section = self.section_name(section);
+        self.spans.sections.remove(&section);
+        self.spans.keys.remove(&section);
+        if let Some(names) = self.names.as_mut() {
+            names.remove_section(&section);
+        }
         #[cfg(not(feature = "indexmap"))]
         {
             self.map.remove(&section)
@@ -1422,16 +2878,97 @@ impl Ini {
     ///let val = config.remove_key("anothersection", "updog").unwrap().unwrap();
     ///assert_eq!(val, String::from("differentdog"));  // with the last section removed, our map is now empty!
     ///```
-    ///Returns `Some(Option<String>)` if the value exists or else, `None`.
-    pub fn remove_key(&mut self, section: &str, key: &str) -> Option<Option<String>> {
+    ///Returns `Some(Option<String>)` if the value exists or else, `None`.
+    pub fn remove_key(&mut self, section: &str, key: &str) -> Option<Option<String>> {
+        let (section, key) = self.autocase(section, key);
+        if let Some(keys) = self.spans.keys.get_mut(&section) {
+            keys.remove(&key);
+        }
+        if let Some(names) = self.names.as_mut() {
+            names.remove_key(&section, &key);
+        }
+        #[cfg(not(feature = "indexmap"))]
+        {
+            self.map.get_mut(&section)?.remove(&key)
+        }
+        #[cfg(feature = "indexmap")]
+        {
+            self.map.get_mut(&section)?.swap_remove(&key)
+        }
+    }
+
+    ///Returns a read-only view of the defined section, so that its keys can be accessed without repeating the
+    ///section name. The section name is cased once when the view is created.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.load("tests/test.ini");
+    ///let values = config.section("Values").unwrap();
+    ///assert_eq!(values.getint("Int").unwrap(), Some(-31415));
+    ///assert_eq!(values.len(), 5);
+    ///assert!(config.section("missing").is_none());
+    ///```
+    ///Returns `Some(SectionRef)` if the section exists or else `None`.
+    pub fn section(&self, section: &str) -> Option<SectionRef<'_>> {
+        let (name, map) = self.map.get_key_value(&self.section_name(section))?;
+        Some(SectionRef {
+            ini: self,
+            name,
+            map,
+        })
+    }
+
+    ///Returns a mutable view of the defined section, creating the section if it does not exist yet.
+    ///The section name is cased once when the view is created.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///let mut section = config.section_mut("Server");
+    ///section.setstr("Host", Some("localhost"));
+    ///section.setstr("port", Some("8080"));
+    ///assert_eq!(section.getuint("PORT").unwrap(), Some(8080));
+    ///assert_eq!(config.get("server", "host"), Some(String::from("localhost")));
+    ///```
+    ///Returns a `SectionMut` for the section.
+    pub fn section_mut(&mut self, section: &str) -> SectionMut<'_> {
+        let name = self.section_name(section);
+        if let Some(names) = self.names_mut() {
+            names.add_section(&name);
+        }
+        self.map.entry(name.clone()).or_default();
+        SectionMut { ini: self, name }
+    }
+
+    ///Returns an `Entry` for the key in the defined section for in-place manipulation, similar to the entry API
+    ///of maps. The section is only created once a value is actually inserted.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config
+    ///    .entry("counters", "Hits")
+    ///    .and_modify(|val| *val = Some(String::from("unused")))
+    ///    .or_insert(Some(String::from("1")));
+    ///config
+    ///    .entry("Counters", "hits")
+    ///    .and_modify(|val| *val = val.as_deref().map(|hits| hits.repeat(2)))
+    ///    .or_insert(None);
+    ///assert_eq!(config.get("counters", "hits"), Some(String::from("11")));
+    ///```
+    ///Returns an `Entry` for the cased section and key.
+    pub fn entry(&mut self, section: &str, key: &str) -> Entry<'_> {
         let (section, key) = self.autocase(section, key);
-        #[cfg(not(feature = "indexmap"))]
-        {
-            self.map.get_mut(&section)?.remove(&key)
-        }
-        #[cfg(feature = "indexmap")]
-        {
-            self.map.get_mut(&section)?.swap_remove(&key)
+        // The entry may insert names, so the index is rebuilt when it is needed next.
+        self.names = None;
+        Entry {
+            map: &mut self.map,
+            section,
+            key,
         }
     }
 }
@@ -1449,28 +2986,38 @@ impl Ini {
         &mut self,
         path: T,
     ) -> Result<Map<String, Map<String, Option<String>>>, String> {
-        self.map = match self.parse(match async_fs::read_to_string(&path).await {
-            Err(why) => {
-                return Err(format!(
-                    "couldn't read {}: {}",
-                    &path.as_ref().display(),
-                    why
-                ));
-            }
-            Ok(s) => s,
-        }) {
-            Err(why) => {
-                return Err(format!(
-                    "couldn't read {}: {}",
-                    &path.as_ref().display(),
-                    why
-                ));
-            }
-            Ok(map) => map,
-        };
+        self.load_async_ref(path).await?;
         Ok(self.map.clone())
     }
 
+    ///Loads a file asynchronously from a defined path, parses it and puts the hashmap into our struct, exactly like
+    ///`load_async()` but without cloning the stored `Map`.
+    ///
+    ///Returns `Ok(&self)` if no errors are thrown or else `Err(error_string)`.
+    pub async fn load_async_ref<T: AsRef<Path>>(&mut self, path: T) -> Result<&Ini, String> {
+        (self.map, self.spans, self.names) =
+            match self.parse(match async_fs::read_to_string(&path).await {
+                Err(why) => {
+                    return Err(format!(
+                        "couldn't read {}: {}",
+                        &path.as_ref().display(),
+                        why
+                    ));
+                }
+                Ok(s) => s,
+            }) {
+                Err(why) => {
+                    return Err(format!(
+                        "couldn't read {}: {}",
+                        &path.as_ref().display(),
+                        why
+                    ));
+                }
+                Ok(parsed) => parsed,
+            };
+        Ok(self)
+    }
+
     ///Loads a file from a defined path, parses it and applies it to the existing hashmap in our struct.
     ///While `load_async()` will clear the existing `Map`, `load_and_append_async()` applies the new values on top
     ///of the existing hashmap, preserving previous values.
@@ -1483,6 +3030,36 @@ impl Ini {
         &mut self,
         path: T,
     ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        self.load_and_append_async_ref(path).await?;
+        Ok(self.map.clone())
+    }
+
+    ///Loads a file asynchronously from a defined path, parses it and applies it to the existing hashmap in our
+    ///struct, exactly like `load_and_append_async()` but without cloning the stored `Map`.
+    ///
+    ///Returns `Ok(&self)` if no errors are thrown or else `Err(error_string)`.
+    pub async fn load_and_append_async_ref<T: AsRef<Path>>(
+        &mut self,
+        path: T,
+    ) -> Result<&Ini, String> {
+        self.load_and_append_async_with(path, &AppendOptions::default())
+            .await?;
+        Ok(self)
+    }
+
+    ///Loads a file asynchronously from a defined path, parses it and applies it to the existing hashmap in our
+    ///struct with the given strategies, exactly like `load_and_append_with()`.
+    ///
+    ///Usage is similar to `load_and_append_with`, but `.await` must be called after along with the usual async
+    ///rules.
+    ///
+    ///Returns `Ok(report)` with an `AppendReport` of the keys and sections that already existed if no errors are
+    ///thrown or else `Err(error_string)`, in which case nothing was changed.
+    pub async fn load_and_append_async_with<T: AsRef<Path>>(
+        &mut self,
+        path: T,
+        options: &AppendOptions,
+    ) -> Result<AppendReport, String> {
         let loaded = match self.parse(match async_fs::read_to_string(&path).await {
             Err(why) => {
                 return Err(format!(
@@ -1500,14 +3077,10 @@ impl Ini {
                     why
                 ));
             }
-            Ok(map) => map,
+            Ok(parsed) => parsed,
         };
-
-        for (section, section_map) in loaded {
-            self.map.entry(section).or_default().extend(section_map);
-        }
-
-        Ok(self.map.clone())
+        self.append(loaded, options)
+            .map_err(|why| format!("couldn't append {}: {}", &path.as_ref().display(), why))
     }
 
     ///Writes the current configuation to the specified path asynchronously using default formatting. If a file is not present, it is automatically created for you, if a file already
@@ -1534,3 +3107,512 @@ impl Ini {
         async_fs::write(path.as_ref(), self.unparse(write_options)).await
     }
 }
+
+///The `SectionRef` struct is a read-only view of a single section of an `Ini`, returned by `Ini::section()`.
+///Keys are cased the same way as in `Ini::get()`, and lookups cascade to the default section if enabled.
+#[derive(Debug, Clone, Copy)]
+pub struct SectionRef<'a> {
+    ini: &'a Ini,
+    name: &'a str,
+    map: &'a Map<String, Option<String>>,
+}
+
+impl<'a> SectionRef<'a> {
+    ///Returns the (cased) name of the section.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    ///Returns the stored value of the key without cloning it.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.load("tests/test.ini");
+    ///let topsecret = config.section("topsecret").unwrap();
+    ///assert_eq!(topsecret.get("KFC"), Some("the secret herb is orega-"));
+    ///assert_eq!(topsecret.get("None string"), None);
+    ///```
+    ///Returns `Some(value)` of type `&str` if value is found or else returns `None`.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.ini
+            .get_cased(self.name, &self.ini.key_name(self.name, key))
+    }
+
+    ///Parses the stored value of the key to a `bool` like `Ini::getbool()`.
+    pub fn getbool(&self, key: &str) -> Result<Option<bool>, String> {
+        self.parse(key, parse_bool)
+    }
+
+    ///Parses the stored value of the key to a `bool` like `Ini::getboolcoerce()`.
+    pub fn getboolcoerce(&self, key: &str) -> Result<Option<bool>, String> {
+        self.ini
+            .getboolcoerce_cased(self.name, &self.ini.key_name(self.name, key))
+    }
+
+    ///Parses the stored value of the key to an `i64` like `Ini::getint()`.
+    pub fn getint(&self, key: &str) -> Result<Option<i64>, String> {
+        self.parse(key, parse_value::<i64>)
+    }
+
+    ///Parses the stored value of the key to a `u64` like `Ini::getuint()`.
+    pub fn getuint(&self, key: &str) -> Result<Option<u64>, String> {
+        self.parse(key, parse_value::<u64>)
+    }
+
+    ///Parses the stored value of the key to a `f64` like `Ini::getfloat()`.
+    pub fn getfloat(&self, key: &str) -> Result<Option<f64>, String> {
+        self.parse(key, parse_value::<f64>)
+    }
+
+    ///Private function that parses the stored value of the key like the getters of `Ini`.
+    fn parse<T>(
+        &self,
+        key: &str,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        self.ini
+            .parse_cased(self.name, &self.ini.key_name(self.name, key), parse)
+    }
+
+    ///Returns `true` if the key is stored in the section (with or without a value), without cascading.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(&self.ini.key_name(self.name, key))
+    }
+
+    ///Iterates over the keys stored in the section.
+    pub fn keys(&self) -> impl Iterator<Item = &'a str> + use<'a> {
+        self.map.keys().map(String::as_str)
+    }
+
+    ///Iterates over the key-value pairs stored in the section.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, Option<&'a str>)> + use<'a> {
+        self.map
+            .iter()
+            .map(|(key, val)| (key.as_str(), val.as_deref()))
+    }
+
+    ///Returns the number of keys stored in the section.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    ///Returns `true` if the section has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+///The `SectionMut` struct is a mutable view of a single section of an `Ini`, returned by `Ini::section_mut()`.
+///Keys are cased the same way as in `Ini::set()`.
+#[derive(Debug)]
+pub struct SectionMut<'a> {
+    ini: &'a mut Ini,
+    name: String,
+}
+
+impl SectionMut<'_> {
+    ///Private function that returns the section map, which `section_mut()` guarantees to exist unless the
+    ///section was removed through the `Ini` in the meantime (which the borrow checker prevents).
+    fn map(&self) -> &Map<String, Option<String>> {
+        &self.ini.map[&self.name]
+    }
+
+    ///Private function that returns the section map mutably.
+    fn map_mut(&mut self) -> &mut Map<String, Option<String>> {
+        self.ini.map.get_mut(&self.name).unwrap()
+    }
+
+    ///Returns a read-only view of the section.
+    pub fn as_section_ref(&self) -> SectionRef<'_> {
+        SectionRef {
+            ini: self.ini,
+            name: &self.name,
+            map: self.map(),
+        }
+    }
+
+    ///Returns the (cased) name of the section.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    ///Returns the stored value of the key without cloning it, like `SectionRef::get()`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.ini
+            .get_cased(&self.name, &self.ini.key_name(&self.name, key))
+    }
+
+    ///Parses the stored value of the key to a `bool` like `Ini::getbool()`.
+    pub fn getbool(&self, key: &str) -> Result<Option<bool>, String> {
+        self.as_section_ref().getbool(key)
+    }
+
+    ///Parses the stored value of the key to a `bool` like `Ini::getboolcoerce()`.
+    pub fn getboolcoerce(&self, key: &str) -> Result<Option<bool>, String> {
+        self.as_section_ref().getboolcoerce(key)
+    }
+
+    ///Parses the stored value of the key to an `i64` like `Ini::getint()`.
+    pub fn getint(&self, key: &str) -> Result<Option<i64>, String> {
+        self.as_section_ref().getint(key)
+    }
+
+    ///Parses the stored value of the key to a `u64` like `Ini::getuint()`.
+    pub fn getuint(&self, key: &str) -> Result<Option<u64>, String> {
+        self.as_section_ref().getuint(key)
+    }
+
+    ///Parses the stored value of the key to a `f64` like `Ini::getfloat()`.
+    pub fn getfloat(&self, key: &str) -> Result<Option<f64>, String> {
+        self.as_section_ref().getfloat(key)
+    }
+
+    ///Sets an `Option<String>` for the key like `Ini::set()`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///let mut section = config.section_mut("section");
+    ///assert_eq!(section.set("key", Some(String::from("value"))), None);
+    ///assert_eq!(section.set("KEY", None), Some(Some(String::from("value"))));
+    ///```
+    ///Returns `None` if there is no existing value, else returns `Some(Option<String>)`, with the existing value being the wrapped `Option<String>`.
+    pub fn set(&mut self, key: &str, value: Option<String>) -> Option<Option<String>> {
+        let key = self.ini.key_name(&self.name, key);
+        if let Some(names) = self.ini.names_mut() {
+            names.add_key(&self.name, &key);
+        }
+        self.map_mut().insert(key, value)
+    }
+
+    ///Sets an `Option<&str>` for the key like `Ini::setstr()`.
+    ///Returns `None` if there is no existing value, else returns `Some(Option<String>)`, with the existing value being the wrapped `Option<String>`.
+    pub fn setstr(&mut self, key: &str, value: Option<&str>) -> Option<Option<String>> {
+        self.set(key, value.map(String::from))
+    }
+
+    ///Removes the key from the section like `Ini::remove_key()`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new();
+    ///config.setstr("section", "updog", Some("whatsupdog"));
+    ///let removed = config.section_mut("section").remove("UPDOG");
+    ///assert_eq!(removed, Some(Some(String::from("whatsupdog"))));
+    ///```
+    ///Returns `Some(Option<String>)` if the value exists or else, `None`.
+    pub fn remove(&mut self, key: &str) -> Option<Option<String>> {
+        let key = self.ini.key_name(&self.name, key);
+        if let Some(names) = self.ini.names.as_mut() {
+            names.remove_key(&self.name, &key);
+        }
+        #[cfg(not(feature = "indexmap"))]
+        {
+            self.map_mut().remove(&key)
+        }
+        #[cfg(feature = "indexmap")]
+        {
+            self.map_mut().swap_remove(&key)
+        }
+    }
+
+    ///Returns an `Entry` for the key in the section, like `Ini::entry()`.
+    pub fn entry(&mut self, key: &str) -> Entry<'_> {
+        let key = self.ini.key_name(&self.name, key);
+        self.ini.names = None;
+        Entry {
+            map: &mut self.ini.map,
+            section: self.name.clone(),
+            key,
+        }
+    }
+
+    ///Returns `true` if the key is stored in the section (with or without a value), without cascading.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.as_section_ref().contains_key(key)
+    }
+
+    ///Iterates over the keys stored in the section.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.map().keys().map(String::as_str)
+    }
+
+    ///Iterates over the key-value pairs stored in the section.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.map()
+            .iter()
+            .map(|(key, val)| (key.as_str(), val.as_deref()))
+    }
+
+    ///Returns the number of keys stored in the section.
+    pub fn len(&self) -> usize {
+        self.map().len()
+    }
+
+    ///Returns `true` if the section has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.map().is_empty()
+    }
+}
+
+///The `Entry` struct is a view into a single key of an `Ini`, returned by `Ini::entry()` and
+///`SectionMut::entry()`. Its section and key have already been cased.
+#[derive(Debug)]
+pub struct Entry<'a> {
+    map: &'a mut Map<String, Map<String, Option<String>>>,
+    section: String,
+    key: String,
+}
+
+impl<'a> Entry<'a> {
+    ///Returns the (cased) section name of the entry.
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    ///Returns the (cased) key of the entry.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    ///Inserts the default value if the key is not stored yet, creating the section if needed.
+    ///Returns a mutable reference to the stored value.
+    pub fn or_insert(self, default: Option<String>) -> &'a mut Option<String> {
+        self.or_insert_with(|| default)
+    }
+
+    ///Inserts the result of the function if the key is not stored yet, creating the section if needed.
+    ///Returns a mutable reference to the stored value.
+    pub fn or_insert_with<F: FnOnce() -> Option<String>>(
+        self,
+        default: F,
+    ) -> &'a mut Option<String> {
+        self.map
+            .entry(self.section)
+            .or_default()
+            .entry(self.key)
+            .or_insert_with(default)
+    }
+
+    ///Calls the function with the stored value if the key is stored, before any potential inserts.
+    pub fn and_modify<F: FnOnce(&mut Option<String>)>(self, f: F) -> Self {
+        if let Some(val) = self
+            .map
+            .get_mut(&self.section)
+            .and_then(|secmap| secmap.get_mut(&self.key))
+        {
+            f(val);
+        }
+        self
+    }
+}
+
+///The nested map type stored by `IniRef`, which borrows from the parsed input.
+pub type CowMap<'a> = Map<Cow<'a, str>, Map<Cow<'a, str>, Option<Cow<'a, str>>>>;
+
+///The `IniRef` struct is a borrowed, read-only view over an ini-syntax `&str`. Section names, keys and values are
+///stored as slices of the input wherever possible and are only allocated when they have to be lowercased or
+///stitched together from multiple lines, which makes it much cheaper than `Ini` for parsing large inputs.
+///## Example
+///```rust
+///use configparser::ini::IniRef;
+///
+///let input = String::from("[section]\nkey=value");
+///let config = IniRef::parse(&input).unwrap();
+///assert_eq!(config.get("section", "key"), Some("value"));
+///```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct IniRef<'a> {
+    map: CowMap<'a>,
+    defaults: IniDefault,
+    names: Option<NameIndex>,
+}
+
+impl<'a> IniRef<'a> {
+    ///Parses the input with the default options of `Ini::new()` and borrows from it.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::IniRef;
+    ///
+    ///let config = IniRef::parse("[Section]\nKey=Value").unwrap();
+    ///assert_eq!(config.get("SECTION", "key"), Some("Value"));
+    ///```
+    ///Returns `Ok(config)` if no errors are thrown or else `Err(error_string)`.
+    pub fn parse(input: &'a str) -> Result<IniRef<'a>, String> {
+        IniRef::parse_with_defaults(input, IniDefault::default())
+    }
+
+    ///Parses the input with the given defaults and borrows from it.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{IniDefault, IniRef};
+    ///
+    ///let mut default = IniDefault::default();
+    ///default.case_sensitive = true;
+    ///let config = IniRef::parse_with_defaults("[Section]\nKey=Value", default).unwrap();
+    ///assert_eq!(config.get("Section", "Key"), Some("Value"));
+    ///assert_eq!(config.get("section", "key"), None);
+    ///```
+    ///Returns `Ok(config)` if no errors are thrown or else `Err(error_string)`.
+    pub fn parse_with_defaults(input: &'a str, defaults: IniDefault) -> Result<IniRef<'a>, String> {
+        let (map, names) = build_map(
+            StrEvents::new(input, Lexer::from_defaults(&defaults)),
+            &defaults.default_section,
+            Casing::from_defaults(&defaults),
+            defaults.multiline,
+            defaults.duplicate_keys,
+            defaults.escapes,
+            None,
+        )?;
+        Ok(IniRef {
+            map,
+            defaults,
+            names,
+        })
+    }
+
+    ///Returns the defaults the view was parsed with.
+    pub fn defaults(&self) -> &IniDefault {
+        &self.defaults
+    }
+
+    ///Gets all the sections of the view in a vector.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::IniRef;
+    ///
+    ///let config = IniRef::parse("[section]\nkey=value").unwrap();
+    ///assert_eq!(config.sections(), vec!["section"]);
+    ///```
+    ///Returns `Vec<&str>`.
+    pub fn sections(&self) -> Vec<&str> {
+        self.map.keys().map(|section| section.as_ref()).collect()
+    }
+
+    ///Returns an immutable reference to the borrowed `Map`.
+    pub fn get_map_ref(&self) -> &CowMap<'a> {
+        &self.map
+    }
+
+    ///Returns the stored value from the key stored in the defined section, looked up the same way as
+    ///`Ini::get()` does (including `cascade_defaults`), without cloning it.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::IniRef;
+    ///
+    ///let config = IniRef::parse("[topsecret]\nKFC = the secret herb is orega-").unwrap();
+    ///assert_eq!(config.get("topsecret", "KFC"), Some("the secret herb is orega-"));
+    ///```
+    ///Returns `Some(value)` of type `&str` if value is found or else returns `None`.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        let casing = Casing::from_defaults(&self.defaults);
+        let sections = self.names.as_ref().map(|names| &names.sections);
+        let section = resolve_name(
+            Some(&self.map),
+            section,
+            casing.section,
+            casing.folding,
+            sections,
+        );
+        let val = self.get_in(&section, key, casing);
+
+        if val.is_none() && self.defaults.cascade_defaults {
+            // The default section name is used as-is unless section names are case-preserving.
+            let default_section = match casing.section {
+                CasePolicy::Preserve => resolve_name(
+                    Some(&self.map),
+                    &self.defaults.default_section,
+                    casing.section,
+                    casing.folding,
+                    sections,
+                ),
+                _ => Cow::Borrowed(self.defaults.default_section.as_str()),
+            };
+            return self.get_in(&default_section, key, casing);
+        }
+
+        val
+    }
+
+    ///Private function that looks up a key in an already-cased section without cascading.
+    fn get_in(&self, section: &str, key: &str, casing: Casing) -> Option<&str> {
+        let secmap = self.map.get(section)?;
+        let keys = self.names.as_ref().and_then(|names| names.keys(section));
+        let key = resolve_name(Some(secmap), key, casing.key, casing.folding, keys);
+        secmap.get(key.as_ref())?.as_deref()
+    }
+
+    ///Parses the stored value to a `bool` like `Ini::getbool()`.
+    ///Returns `Ok(Some(value))` of type `bool` if value is found or else returns `Ok(None)`.
+    ///If the parsing fails, it returns an `Err(string)`.
+    pub fn getbool(&self, section: &str, key: &str) -> Result<Option<bool>, String> {
+        self.get(section, key).map(parse_bool).transpose()
+    }
+
+    ///Parses the stored value to a `bool` like `Ini::getboolcoerce()`, using the boolean values of the defaults.
+    ///Returns `Ok(Some(value))` of type `bool` if value is found or else returns `Ok(None)`.
+    ///If the parsing fails, it returns an `Err(string)`.
+    pub fn getboolcoerce(&self, section: &str, key: &str) -> Result<Option<bool>, String> {
+        self.get(section, key)
+            .map(|inner| coerce_bool(&self.defaults.boolean_values, inner, section, key))
+            .transpose()
+    }
+
+    ///Parses the stored value to an `i64` like `Ini::getint()`.
+    ///Returns `Ok(Some(value))` of type `i64` if value is found or else returns `Ok(None)`.
+    ///If the parsing fails, it returns an `Err(string)`.
+    pub fn getint(&self, section: &str, key: &str) -> Result<Option<i64>, String> {
+        self.get(section, key).map(parse_value::<i64>).transpose()
+    }
+
+    ///Parses the stored value to a `u64` like `Ini::getuint()`.
+    ///Returns `Ok(Some(value))` of type `u64` if value is found or else returns `Ok(None)`.
+    ///If the parsing fails, it returns an `Err(string)`.
+    pub fn getuint(&self, section: &str, key: &str) -> Result<Option<u64>, String> {
+        self.get(section, key).map(parse_value::<u64>).transpose()
+    }
+
+    ///Parses the stored value to a `f64` like `Ini::getfloat()`.
+    ///Returns `Ok(Some(value))` of type `f64` if value is found or else returns `Ok(None)`.
+    ///If the parsing fails, it returns an `Err(string)`.
+    pub fn getfloat(&self, section: &str, key: &str) -> Result<Option<f64>, String> {
+        self.get(section, key).map(parse_value::<f64>).transpose()
+    }
+
+    ///Copies the view into an owned `Ini` with the same defaults.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::IniRef;
+    ///
+    ///let config = IniRef::parse("[section]\nkey=value").unwrap().to_ini();
+    ///assert_eq!(config.get("section", "key"), Some(String::from("value")));
+    ///```
+    pub fn to_ini(&self) -> Ini {
+        let mut ini = Ini::new_from_defaults(self.defaults.clone());
+        ini.map = self
+            .map
+            .iter()
+            .map(|(section, secmap)| {
+                (
+                    section.clone().into_owned(),
+                    secmap
+                        .iter()
+                        .map(|(key, val)| {
+                            (
+                                key.clone().into_owned(),
+                                val.as_ref().map(|val| val.clone().into_owned()),
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+        ini.names = self.names.clone();
+        ini
+    }
+}
@@ -46,7 +46,7 @@ let my_int = my_string.parse::<i32>().unwrap();
 
 
 ## 📝 Supported `ini` file structure
-A configuration file can consist of sections, each led by a `[section-name]` header, followed by key-value entries separated by a delimiter (`=` and `:`). By default, section names and key names are case-insensitive. Case-sensitivity can be enabled using the `Ini::new_cs()` constructor. All leading and trailing whitespace is removed from stored keys, values and section names.
+A configuration file can consist of sections, each led by a `[section-name]` header, followed by key-value entries separated by a delimiter (`=` and `:`). By default, section names and key names are case-insensitive. Case-sensitivity can be enabled using the `Ini::new_cs()` constructor, while `Ini::new_case_preserving()` keeps lookups case-insensitive but writes names back with their original spelling. All leading and trailing whitespace is removed from stored keys, values and section names.
 Key values can be omitted, in which case the key-value delimiter
 may also be left out (but this is different from putting a delimiter, we'll
 explain it later). You can use comment symbols (`;` and `#` to denote comments). This can be configured with the `set_comment_symbols()` method in the
@@ -113,6 +113,7 @@ fn main() -> Result<(), Box<dyn Error>> {
   let map = config.load("tests/test.ini")?;
   println!("{:?}", map);
   // You can also safely not store the reference and access it later with get_map_ref() or get a clone with get_map()
+  // If you never need the clone, load_ref() and friends skip it entirely and return a reference to the Ini instead.
 
   // If you want to access the value, then you can simply do:
   let val = config.get("TOPSECRET", "KFC").unwrap();
@@ -156,4 +157,19 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 ```
 */
+pub mod desktop;
+pub mod dialect;
+pub mod diff;
+pub mod dotenv;
+pub mod edit;
 pub mod ini;
+pub mod lint;
+pub mod merge;
+pub mod parser;
+pub mod properties;
+pub mod registry;
+pub mod report;
+pub mod schema;
+pub mod systemd;
+pub mod typed;
+pub mod watch;
@@ -0,0 +1,484 @@
+//!The lint module finds likely mistakes in ini-syntax text that parse without errors, such as duplicate keys, empty
+//!values or inline comments that cut a value short. Every finding comes from a `Rule` with its own ID and
+//!`Severity`, which a `Linter` can change or turn off, and comments in the linted text can suppress rules for a
+//!single line or the whole file.
+//!See the [`Linter`] documentation for more details.
+use crate::ini::{IniDefault, KeyStrategy};
+use crate::parser::{EventKind, Lexer, StrEvents, error_position};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+///The checks a `Linter` runs, each identified by a short ID such as `duplicate-key`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+    ///A line couldn't be parsed. Defaults to `Severity::Error`.
+    ParseError,
+    ///A key is set more than once in the same section, so only its last value is used (or its first one with
+    ///`KeyStrategy::KeepFirst`). Not reported when repeated keys are collected with `KeyStrategy::Append`.
+    DuplicateKey,
+    ///A key of a section is also set in the default section, and overrides it when defaults cascade.
+    ShadowedKey,
+    ///A key has a delimiter but no value.
+    EmptyValue,
+    ///A line ends with spaces or tabs.
+    TrailingWhitespace,
+    ///Keys are separated from their values with different delimiters, such as `=` and `:`, in one text.
+    MixedDelimiters,
+    ///The same key is spelled with different cases, such as `Port` and `port`.
+    KeyCase,
+    ///An inline comment starts right after a value (or is the whole value), so it probably swallowed part of it,
+    ///as in `color = #fff` or `url = http://host/#anchor`.
+    InlineComment,
+    ///A key has neither a delimiter nor a value.
+    ValuelessKey,
+    ///A suppression comment is malformed or names an unknown rule.
+    InvalidSuppression,
+}
+
+impl Rule {
+    ///Every rule, in the order they are documented.
+    pub const ALL: [Rule; 10] = [
+        Rule::ParseError,
+        Rule::DuplicateKey,
+        Rule::ShadowedKey,
+        Rule::EmptyValue,
+        Rule::TrailingWhitespace,
+        Rule::MixedDelimiters,
+        Rule::KeyCase,
+        Rule::InlineComment,
+        Rule::ValuelessKey,
+        Rule::InvalidSuppression,
+    ];
+
+    ///Returns the ID of the rule, used in suppression comments and diagnostics.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::ParseError => "parse-error",
+            Rule::DuplicateKey => "duplicate-key",
+            Rule::ShadowedKey => "shadowed-key",
+            Rule::EmptyValue => "empty-value",
+            Rule::TrailingWhitespace => "trailing-whitespace",
+            Rule::MixedDelimiters => "mixed-delimiters",
+            Rule::KeyCase => "key-case",
+            Rule::InlineComment => "inline-comment",
+            Rule::ValuelessKey => "valueless-key",
+            Rule::InvalidSuppression => "invalid-suppression",
+        }
+    }
+
+    ///Returns the severity of the rule unless a `Linter` changes it.
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Rule::ParseError => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    ///Parses a rule from its ID.
+    fn from_str(id: &str) -> Result<Rule, String> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.id() == id)
+            .ok_or_else(|| format!("unknown lint rule {:?}", id))
+    }
+}
+
+///How serious a `Diagnostic` is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+///A single finding of `Linter::lint()`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    ///The 1-based line of the finding.
+    pub line: usize,
+    ///The 1-based column (in characters) of the finding.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    ///Renders the diagnostic on a single line, e.g. `line 3:1: warning[duplicate-key]: server:port is already set
+    ///on line 2`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}:{}: {}[{}]: {}",
+            self.line, self.column, self.severity, self.rule, self.message
+        )
+    }
+}
+
+///The prefix of suppression comments.
+const SUPPRESSION: &str = "configparser-lint:";
+
+///The `Linter` struct checks ini-syntax text for the problems described by `Rule`, parsing it with the comment
+///symbols, delimiters and case policies of its `IniDefault`.
+///
+///A comment containing `configparser-lint: allow(rule, ...)` suppresses the listed rules on its own line if it is
+///an inline comment, or else on the next line with a section header or key. A comment containing
+///`configparser-lint: allow-file(rule, ...)` suppresses them in the whole text.
+///## Example
+///```rust
+///use configparser::lint::{Linter, Rule, Severity};
+///
+///let text = "[server]\nport = 80\nport = 8080\nname =\n; configparser-lint: allow(empty-value)\nhost =\n";
+///let linter = Linter::new().severity(Rule::DuplicateKey, Severity::Error);
+///let diagnostics = linter.lint(text);
+///assert_eq!(diagnostics.len(), 2);
+///assert_eq!(diagnostics[0].to_string(), "line 3:1: error[duplicate-key]: server:port is already set on line 2");
+///assert_eq!(diagnostics[1].rule, Rule::EmptyValue);
+///
+///assert_eq!(Linter::new().allow(Rule::DuplicateKey).lint(text).len(), 1);
+///```
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Linter {
+    defaults: IniDefault,
+    ///Severities that differ from the default severity of their rule, `None` for rules that are turned off.
+    severities: HashMap<Rule, Option<Severity>>,
+}
+
+///Private struct holding the keys seen in a section.
+struct SectionKeys {
+    name: String,
+    ///The keys with their line and column.
+    keys: Vec<(String, usize, usize)>,
+}
+
+impl Linter {
+    ///Creates a new `Linter` with the default parsing options of `Ini::new()` and every rule at its default
+    ///severity.
+    pub fn new() -> Linter {
+        Linter::default()
+    }
+
+    ///Creates a new `Linter` that parses text with the given defaults.
+    pub fn new_from_defaults(defaults: IniDefault) -> Linter {
+        Linter {
+            defaults,
+            severities: HashMap::new(),
+        }
+    }
+
+    ///Sets the severity of a rule.
+    pub fn severity(mut self, rule: Rule, severity: Severity) -> Linter {
+        self.severities.insert(rule, Some(severity));
+        self
+    }
+
+    ///Turns a rule off.
+    pub fn allow(mut self, rule: Rule) -> Linter {
+        self.severities.insert(rule, None);
+        self
+    }
+
+    ///Returns the severity of a rule, or `None` if it is turned off.
+    pub fn severity_of(&self, rule: Rule) -> Option<Severity> {
+        match self.severities.get(&rule) {
+            Some(severity) => *severity,
+            None => Some(rule.default_severity()),
+        }
+    }
+
+    ///Checks ini-syntax text.
+    ///Returns the diagnostics of every rule that isn't turned off or suppressed, ordered by position.
+    pub fn lint(&self, text: &str) -> Vec<Diagnostic> {
+        let mut found: Vec<(Rule, usize, usize, String)> = Vec::new();
+        let mut suppressed: HashSet<(Rule, usize)> = HashSet::new();
+        let mut suppressed_file: HashSet<Rule> = HashSet::new();
+        // Suppressions of full-line comments wait for the next section header or key.
+        let mut pending: Vec<Rule> = Vec::new();
+
+        let mut sections = vec![SectionKeys {
+            name: self.defaults.default_section.clone(),
+            keys: Vec::new(),
+        }];
+        let mut current = 0;
+        let mut spellings: HashMap<String, String> = HashMap::new();
+        let mut first_delimiter: Option<char> = None;
+        let mut last_key: Option<(usize, bool)> = None;
+        let mut empty: Vec<(usize, usize, String)> = Vec::new();
+        let mut continued: HashSet<usize> = HashSet::new();
+        let mut swallowed: HashSet<usize> = HashSet::new();
+
+        let lines: Vec<&str> = text.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            let trimmed = line.trim_end_matches([' ', '\t']);
+            if trimmed.len() != line.len() {
+                found.push((
+                    Rule::TrailingWhitespace,
+                    index + 1,
+                    trimmed.chars().count() + 1,
+                    String::from("trailing whitespace"),
+                ));
+            }
+        }
+
+        let mut line = 0;
+        for event in StrEvents::new(text, Lexer::from_defaults(&self.defaults)) {
+            let event = match event {
+                Ok(event) => event,
+                Err(why) => {
+                    // Every line yields at least one event unless it has an error, so the error is on the next line.
+                    line += 1;
+                    match error_position(&why) {
+                        Some((at, column, message)) => {
+                            line = at;
+                            let column = column.unwrap_or(1);
+                            found.push((Rule::ParseError, line, column, message.to_owned()));
+                        }
+                        None => found.push((Rule::ParseError, line, 1, why)),
+                    }
+                    continue;
+                }
+            };
+            line = event.line;
+            match event.kind {
+                EventKind::SectionStart { name } => {
+                    suppressed.extend(pending.drain(..).map(|rule| (rule, line)));
+                    current = match sections
+                        .iter()
+                        .position(|section| self.defaults.same_section(&section.name, &name))
+                    {
+                        Some(position) => position,
+                        None => {
+                            sections.push(SectionKeys {
+                                name: name.into_owned(),
+                                keys: Vec::new(),
+                            });
+                            sections.len() - 1
+                        }
+                    };
+                    last_key = None;
+                }
+                EventKind::KeyValue {
+                    key,
+                    delimiter,
+                    value,
+                } => {
+                    suppressed.extend(pending.drain(..).map(|rule| (rule, line)));
+                    let section = &mut sections[current];
+                    let duplicate = section
+                        .keys
+                        .iter()
+                        .find(|(seen, _, _)| self.defaults.same_key(seen, &key));
+                    if let Some((_, first, _)) = duplicate {
+                        let message = match self.defaults.duplicate_keys {
+                            KeyStrategy::Append => None,
+                            KeyStrategy::KeepFirst => Some(format!(
+                                "{}:{} is already set on line {}, so this value is ignored",
+                                section.name, key, first
+                            )),
+                            _ => Some(format!(
+                                "{}:{} is already set on line {}",
+                                section.name, key, first
+                            )),
+                        };
+                        if let Some(message) = message {
+                            found.push((Rule::DuplicateKey, line, event.column, message));
+                        }
+                    }
+                    section
+                        .keys
+                        .push((key.clone().into_owned(), line, event.column));
+
+                    let spelling = spellings
+                        .entry(key.to_lowercase())
+                        .or_insert_with(|| key.clone().into_owned());
+                    if *spelling != key {
+                        found.push((
+                            Rule::KeyCase,
+                            line,
+                            event.column,
+                            format!("{} is spelled {} elsewhere", key, spelling),
+                        ));
+                    }
+
+                    match (delimiter, value.as_deref()) {
+                        (None, _) => found.push((
+                            Rule::ValuelessKey,
+                            line,
+                            event.column,
+                            format!("{} has no delimiter and no value", key),
+                        )),
+                        (Some(_), Some("")) => {
+                            empty.push((line, event.column, format!("{} has an empty value", key)))
+                        }
+                        _ => {}
+                    }
+                    if let Some(delimiter) = delimiter {
+                        match first_delimiter {
+                            None => first_delimiter = Some(delimiter),
+                            Some(first) if first != delimiter => {
+                                let column = lines[line - 1]
+                                    .chars()
+                                    .skip(event.column - 1)
+                                    .position(|c| c == delimiter)
+                                    .map_or(event.column, |offset| event.column + offset);
+                                found.push((
+                                    Rule::MixedDelimiters,
+                                    line,
+                                    column,
+                                    format!(
+                                        "{} uses {:?}, but earlier keys use {:?}",
+                                        key, delimiter, first
+                                    ),
+                                ));
+                            }
+                            _ => {}
+                        }
+                    }
+                    last_key = Some((line, delimiter.is_some()));
+                }
+                EventKind::Comment { text: comment } => {
+                    let inline = lines[line - 1].trim().len() != comment.len();
+                    if let Some((key_line, true)) = last_key
+                        && key_line == line
+                    {
+                        let before = lines[line - 1].chars().nth(event.column - 2);
+                        let value_empty = empty
+                            .last()
+                            .is_some_and(|(empty_line, _, _)| *empty_line == line);
+                        if value_empty || before.is_some_and(|c| !c.is_whitespace()) {
+                            swallowed.insert(line);
+                            found.push((
+                                Rule::InlineComment,
+                                line,
+                                event.column,
+                                format!(
+                                    "the inline comment {:?} may have cut the value short",
+                                    comment
+                                ),
+                            ));
+                        }
+                    }
+                    if let Some((_, directive)) = comment.split_once(SUPPRESSION) {
+                        match parse_suppression(directive) {
+                            Ok((rules, true)) => suppressed_file.extend(rules),
+                            Ok((rules, false)) if inline => {
+                                suppressed.extend(rules.into_iter().map(|rule| (rule, line)))
+                            }
+                            Ok((rules, false)) => pending.extend(rules),
+                            Err(why) => {
+                                found.push((Rule::InvalidSuppression, line, event.column, why))
+                            }
+                        }
+                    }
+                }
+                EventKind::Continuation { .. } => {
+                    if let Some((key_line, _)) = last_key {
+                        continued.insert(key_line);
+                    }
+                }
+                EventKind::Blank => {}
+            }
+        }
+
+        // Keys that continue on the next lines aren't empty, and values swallowed by a comment are already reported.
+        for (line, column, message) in empty {
+            if !continued.contains(&line) && !swallowed.contains(&line) {
+                found.push((Rule::EmptyValue, line, column, message));
+            }
+        }
+
+        // Headers of the default section continue the keys before the first header, which always come first.
+        let (defaults, named) = sections
+            .split_first()
+            .expect("the default section is always first");
+        for section in named.iter() {
+            for (key, line, column) in section.keys.iter() {
+                if let Some((_, default_line, _)) = defaults
+                    .keys
+                    .iter()
+                    .find(|(default_key, _, _)| self.defaults.same_key(default_key, key))
+                {
+                    found.push((
+                        Rule::ShadowedKey,
+                        *line,
+                        *column,
+                        format!(
+                            "{}:{} overrides the key of the default section on line {}",
+                            section.name, key, default_line
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let mut diagnostics: Vec<Diagnostic> = found
+            .into_iter()
+            .filter(|(rule, line, _, _)| {
+                // Parse errors and broken suppressions can't be suppressed themselves.
+                matches!(rule, Rule::ParseError | Rule::InvalidSuppression)
+                    || !(suppressed_file.contains(rule) || suppressed.contains(&(*rule, *line)))
+            })
+            .filter_map(|(rule, line, column, message)| {
+                Some(Diagnostic {
+                    rule,
+                    severity: self.severity_of(rule)?,
+                    line,
+                    column,
+                    message,
+                })
+            })
+            .collect();
+        diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+        diagnostics
+    }
+}
+
+///Private function that parses the directive after `configparser-lint:` in a comment.
+///Returns the listed rules and whether they are suppressed in the whole text.
+fn parse_suppression(directive: &str) -> Result<(Vec<Rule>, bool), String> {
+    let directive = directive.trim();
+    let (file, list) = if let Some(list) = directive.strip_prefix("allow-file(") {
+        (true, list)
+    } else if let Some(list) = directive.strip_prefix("allow(") {
+        (false, list)
+    } else {
+        return Err(format!(
+            "expected allow(...) or allow-file(...) after {}, found {:?}",
+            SUPPRESSION, directive
+        ));
+    };
+    let Some((list, _)) = list.split_once(')') else {
+        return Err(format!("missing closing parenthesis in {:?}", directive));
+    };
+    let rules = list
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(Rule::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((rules, file))
+}
@@ -0,0 +1,357 @@
+//!The merge module provides three-way merges of configurations, for example to combine the new default
+//!configuration of a package with the local edits of an operator to the previous default configuration.
+//!See the [`Merge`] documentation for more details.
+use crate::ini::{Annotation, Ini, LINE_ENDING, WriteOptions};
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap as Map;
+#[cfg(not(feature = "indexmap"))]
+use std::collections::HashMap as Map;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+///A key that both sides changed in different ways. Each version is `None` if the key (or its whole section) is
+///missing in that configuration, `Some(None)` if the key has no value and `Some(Some(value))` otherwise.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Conflict {
+    pub section: String,
+    pub key: String,
+    pub base: Option<Option<String>>,
+    pub ours: Option<Option<String>>,
+    pub theirs: Option<Option<String>>,
+}
+
+///Private function that renders one version of a conflicting key for humans.
+fn describe(version: &Option<Option<String>>) -> String {
+    match version {
+        Some(Some(value)) => format!("{:?}", value),
+        Some(None) => String::from("no value"),
+        None => String::from("missing"),
+    }
+}
+
+impl fmt::Display for Conflict {
+    ///Renders the conflict on a single line, e.g. `server:port: base "80", ours "8080", theirs "443"`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: base {}, ours {}, theirs {}",
+            self.section,
+            self.key,
+            describe(&self.base),
+            describe(&self.ours),
+            describe(&self.theirs)
+        )
+    }
+}
+
+///The `Merge` struct holds the result of a three-way merge between a common `base` configuration and two
+///configurations derived from it, `ours` and `theirs`.
+///
+///Every key that only one side changed (including adding or removing it) takes the changed version; keys both
+///sides changed the same way are taken as well. Keys that both sides changed differently are conflicts: `merged`
+///keeps our version of them and `conflicts` lists all three versions. Sections are matched and merged the same
+///way, using the case handling and defaults of `ours`.
+///## Example
+///```rust
+///use configparser::ini::Ini;
+///use configparser::merge::Merge;
+///
+///let mut base = Ini::new();
+///base.read(String::from("[server]\nport = 80\nworkers = 4\n[log]\nlevel = info")).unwrap();
+///let mut ours = base.clone();   // the operator's local edits
+///ours.setstr("server", "workers", Some("16"));
+///ours.setstr("log", "level", Some("debug"));
+///let mut theirs = base.clone(); // the new vendor defaults
+///theirs.setstr("server", "timeout", Some("30"));
+///theirs.setstr("log", "level", Some("warn"));
+///
+///let merge = Merge::three_way(&base, &ours, &theirs);
+///assert_eq!(merge.merged.get("server", "workers").unwrap(), "16");
+///assert_eq!(merge.merged.get("server", "timeout").unwrap(), "30");
+///assert_eq!(merge.conflicts.len(), 1);
+///assert_eq!(merge.conflicts[0].to_string(), r#"log:level: base "info", ours "debug", theirs "warn""#);
+///```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Merge {
+    ///The merged configuration, with our version of every conflicting key.
+    pub merged: Ini,
+    ///The keys that both sides changed differently.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    ///Merges the changes from `base` to `ours` and from `base` to `theirs`.
+    ///Returns a `Merge`, which is clean if there are no conflicts.
+    pub fn three_way(base: &Ini, ours: &Ini, theirs: &Ini) -> Merge {
+        let mut merged = ours.clone();
+        let mut conflicts = Vec::new();
+
+        // Collect all section names in the spelling `ours` stores (or would store) them under.
+        let mut sections = Vec::new();
+        let mut seen = HashSet::new();
+        for config in [ours, theirs, base] {
+            for section in config.get_map_ref().keys() {
+                let name = ours.section_name(section);
+                if seen.insert(name.clone()) {
+                    sections.push(name);
+                }
+            }
+        }
+
+        for section in sections.iter() {
+            let mut keys = Vec::new();
+            let mut seen = HashSet::new();
+            for config in [ours, theirs, base] {
+                for key in find_section(config, section)
+                    .into_iter()
+                    .flat_map(|s| s.keys())
+                {
+                    let name = ours.key_name(section, key);
+                    if seen.insert(name.clone()) {
+                        keys.push(name);
+                    }
+                }
+            }
+
+            for key in keys.iter() {
+                let base_val = find_key(base, section, key);
+                let ours_val = find_key(ours, section, key);
+                let theirs_val = find_key(theirs, section, key);
+
+                let resolved = if ours_val == theirs_val || theirs_val == base_val {
+                    &ours_val
+                } else if ours_val == base_val {
+                    &theirs_val
+                } else {
+                    conflicts.push(Conflict {
+                        section: section.clone(),
+                        key: key.clone(),
+                        base: base_val.clone(),
+                        ours: ours_val.clone(),
+                        theirs: theirs_val.clone(),
+                    });
+                    &ours_val
+                };
+                match resolved {
+                    Some(value) => {
+                        merged
+                            .get_mut_map()
+                            .entry(section.clone())
+                            .or_default()
+                            .insert(key.clone(), value.clone());
+                    }
+                    None => {
+                        merged.remove_key(section, key);
+                    }
+                }
+            }
+
+            // A section (even an empty one) is kept unless one side removed it and the other left it unchanged.
+            let (in_base, in_ours, in_theirs) = (
+                find_section(base, section).is_some(),
+                find_section(ours, section).is_some(),
+                find_section(theirs, section).is_some(),
+            );
+            let keep = if in_ours == in_theirs || in_theirs == in_base {
+                in_ours
+            } else {
+                in_theirs
+            };
+            let is_empty = merged
+                .get_map_ref()
+                .get(section)
+                .is_none_or(|secmap| secmap.is_empty());
+            if keep {
+                merged.get_mut_map().entry(section.clone()).or_default();
+            } else if is_empty {
+                merged.remove_section(section);
+            }
+        }
+
+        #[cfg(not(feature = "indexmap"))]
+        conflicts.sort_by(|a, b| (&a.section, &a.key).cmp(&(&b.section, &b.key)));
+        Merge { merged, conflicts }
+    }
+
+    ///Returns `true` if the merge has no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    ///Writes the merged configuration to a string like `Ini::writes()`, with every conflicting key replaced by a
+    ///block of conflict markers holding all three versions:
+    ///```text
+    ///<<<<<<< ours
+    ///level=debug
+    ///||||||| base
+    ///level=info
+    ///=======
+    ///level=warn
+    ///>>>>>>> theirs
+    ///```
+    ///A version in which the key is missing is an empty part of the block. The output of a merge with conflicts
+    ///can't be read back until the markers have been resolved.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::merge::Merge;
+    ///
+    ///let mut base = Ini::new();
+    ///base.read(String::from("[log]\nlevel = info")).unwrap();
+    ///let mut ours = base.clone();
+    ///ours.setstr("log", "level", Some("debug"));
+    ///let mut theirs = base.clone();
+    ///theirs.remove_key("log", "level");
+    ///
+    ///let merge = Merge::three_way(&base, &ours, &theirs);
+    ///assert_eq!(
+    ///    merge.writes_marked(),
+    ///    "[log]\n<<<<<<< ours\nlevel=debug\n||||||| base\nlevel=info\n=======\n>>>>>>> theirs\n"
+    ///        .replace('\n', if cfg!(windows) { "\r\n" } else { "\n" })
+    ///);
+    ///```
+    ///Returns a `String` type containing the merged ini-syntax file.
+    pub fn writes_marked(&self) -> String {
+        self.pretty_writes_marked(&WriteOptions::default())
+    }
+
+    ///Writes the merged configuration with conflict markers like `writes_marked()`, using the given formatting
+    ///options like `Ini::pretty_writes()`. Conflicting versions are aligned with the other keys of their section.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{Ini, WriteOptions};
+    ///use configparser::merge::Merge;
+    ///
+    ///let mut base = Ini::new();
+    ///base.read(String::from("[log]\nlevel = info")).unwrap();
+    ///let mut ours = base.clone();
+    ///ours.setstr("log", "level", Some("debug"));
+    ///let mut theirs = base.clone();
+    ///theirs.setstr("log", "level", Some("warn"));
+    ///
+    ///let mut write_options = WriteOptions::default();
+    ///write_options.space_around_delimiters = true;
+    ///let merge = Merge::three_way(&base, &ours, &theirs);
+    ///let marked = merge.pretty_writes_marked(&write_options).replace("\r\n", "\n");
+    ///assert!(marked.contains("<<<<<<< ours\nlevel = debug\n"));
+    ///```
+    ///Returns a `String` type containing the merged ini-syntax file.
+    pub fn pretty_writes_marked(&self, write_options: &WriteOptions) -> String {
+        let map = self.merged.get_map_ref();
+        let default_section = self.merged.default_section_name();
+
+        let mut sections: Vec<&String> = Vec::new();
+        if map.contains_key(&default_section)
+            || self.conflicts.iter().any(|c| c.section == default_section)
+        {
+            sections.push(&default_section);
+        }
+        sections.extend(map.keys().filter(|section| **section != default_section));
+        for conflict in self.conflicts.iter() {
+            if !sections.contains(&&conflict.section) {
+                sections.push(&conflict.section);
+            }
+        }
+
+        let line_endings = LINE_ENDING.repeat(write_options.blank_lines_between_sections);
+        let mut out = String::new();
+        for section in sections {
+            if *section != default_section || write_options.write_default_section_header {
+                if !out.is_empty() {
+                    out.push_str(&line_endings);
+                }
+                out.push('[');
+                out.push_str(section);
+                out.push(']');
+                out.push_str(LINE_ENDING);
+            }
+            let conflicts: Vec<&Conflict> = self
+                .conflicts
+                .iter()
+                .filter(|c| c.section == *section)
+                .collect();
+            let entries: Vec<(&String, &Option<String>)> =
+                map.get(section).into_iter().flatten().collect();
+
+            let mut versions = entries.clone();
+            for conflict in conflicts.iter() {
+                for version in [&conflict.base, &conflict.ours, &conflict.theirs] {
+                    versions.extend(version.iter().map(|value| (&conflict.key, value)));
+                }
+            }
+            let key_width = Ini::key_width(&versions, write_options);
+            let write = |out: &mut String, entries: &[(&String, &Option<String>)]| {
+                self.merged.unparse_key_values(
+                    out,
+                    section,
+                    entries,
+                    key_width,
+                    write_options,
+                    &|_, _| Annotation::default(),
+                )
+            };
+
+            for entry in entries.iter() {
+                match conflicts.iter().find(|c| c.key == *entry.0) {
+                    Some(conflict) => write_markers(&mut out, conflict, &write),
+                    None => write(&mut out, &[*entry]),
+                }
+            }
+            // Conflicts where our side removed the key aren't in the merged map.
+            for conflict in conflicts {
+                if !entries.iter().any(|(key, _)| **key == conflict.key) {
+                    write_markers(&mut out, conflict, &write);
+                }
+            }
+        }
+        out
+    }
+
+    ///Writes the merged configuration with conflict markers to a file, see `writes_marked()`.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not.
+    pub fn write_marked<T: AsRef<Path>>(&self, path: T) -> std::io::Result<()> {
+        fs::write(path.as_ref(), self.writes_marked())
+    }
+}
+
+///Private function that finds a section in any of the merged configurations by the name `ours` uses for it.
+fn find_section<'a>(config: &'a Ini, section: &str) -> Option<&'a Map<String, Option<String>>> {
+    config.get_map_ref().get(&config.section_name(section))
+}
+
+///Private function that finds a key in any of the merged configurations by the names `ours` uses for it.
+fn find_key(config: &Ini, section: &str, key: &str) -> Option<Option<String>> {
+    let section = config.section_name(section);
+    config
+        .get_map_ref()
+        .get(&section)?
+        .get(&config.key_name(&section, key))
+        .cloned()
+}
+
+///Private function that writes the block of conflict markers for a key, writing each version with `write`.
+fn write_markers(
+    out: &mut String,
+    conflict: &Conflict,
+    write: &impl Fn(&mut String, &[(&String, &Option<String>)]),
+) {
+    let versions = [
+        ("<<<<<<< ours", &conflict.ours),
+        ("||||||| base", &conflict.base),
+        ("=======", &conflict.theirs),
+    ];
+    for (marker, version) in versions {
+        out.push_str(marker);
+        out.push_str(LINE_ENDING);
+        if let Some(value) = version {
+            write(out, &[(&conflict.key, value)]);
+        }
+    }
+    out.push_str(">>>>>>> theirs");
+    out.push_str(LINE_ENDING);
+}
@@ -0,0 +1,516 @@
+//!The parser module provides a streaming, event-based view of ini-syntax input. Instead of building the nested map
+//!that `Ini` stores, the `Events` iterator reads its input line by line from any `std::io::BufRead` and yields
+//!what it finds, so huge files can be filtered or transformed in bounded memory.
+//!See the [`Events`] documentation for more details.
+use crate::ini::{Escapes, IniDefault};
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::ops::Range;
+
+///A single syntactic element of an ini-syntax input, together with the 1-based line and column (counted in
+///characters) at which it starts.
+///
+///The strings stored in an event are exactly as they were written (only surrounding whitespace is trimmed), so
+///section names and keys are **not** lowercased here even for case-insensitive defaults.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Event<S = String> {
+    ///What was found on the line.
+    pub kind: EventKind<S>,
+    ///The 1-based line number of the event.
+    pub line: usize,
+    ///The 1-based column (in characters) at which the event starts.
+    pub column: usize,
+    ///Where the event was found in the input: the whole header of a `SectionStart`, the key of a `KeyValue`, the
+    ///text of a `Comment` or `Continuation` and the (empty) end of a `Blank` line.
+    pub span: Span,
+    ///Where the value of a `KeyValue` was found, if it has one.
+    pub value_span: Option<Span>,
+}
+
+///A region of the input, given both as byte offsets from the start of the input and as 1-based lines and columns
+///(counted in characters). The end is exclusive, so an empty span has the same start and end.
+///## Example
+///```rust
+///use configparser::parser::Events;
+///
+///let mut events = Events::new("[section]\nkey = välue".as_bytes());
+///let header = events.next().unwrap().unwrap().span;
+///assert_eq!((header.start, header.end), (0, 9));
+///let value = events.next().unwrap().unwrap().value_span.unwrap();
+///assert_eq!((value.start, value.end), (16, 22));
+///assert_eq!((value.line, value.column, value.end_line, value.end_column), (2, 7, 2, 12));
+///```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub struct Span {
+    ///The byte offset at which the span starts.
+    pub start: usize,
+    ///The byte offset just past the end of the span.
+    pub end: usize,
+    ///The 1-based line on which the span starts.
+    pub line: usize,
+    ///The 1-based column (in characters) at which the span starts.
+    pub column: usize,
+    ///The 1-based line on which the span ends.
+    pub end_line: usize,
+    ///The 1-based column (in characters) just past the end of the span.
+    pub end_column: usize,
+}
+
+impl Span {
+    ///Returns a span reaching from the start of `self` to the end of `other`.
+    ///## Example
+    ///```rust
+    ///use configparser::parser::Events;
+    ///
+    ///let spans: Vec<_> = Events::new("a=1\nb=2".as_bytes()).map(|event| event.unwrap().span).collect();
+    ///let both = spans[0].to(spans[1]);
+    ///assert_eq!((both.start, both.end, both.line, both.end_line), (0, 5, 1, 2));
+    ///```
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            end_line: other.end_line,
+            end_column: other.end_column,
+            ..self
+        }
+    }
+}
+
+///The kinds of events yielded by `Events`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum EventKind<S = String> {
+    ///A `[section]` header, with the name trimmed of surrounding whitespace.
+    SectionStart { name: S },
+    ///A key, followed by the delimiter that separated it from its value (if any). Keys without a delimiter have
+    ///no value.
+    KeyValue {
+        key: S,
+        delimiter: Option<char>,
+        value: Option<S>,
+    },
+    ///A comment, including its comment symbol. Inline comments are yielded right after the event of the line
+    ///they were found on.
+    Comment { text: S },
+    ///An empty (or whitespace-only) line.
+    Blank,
+    ///An indented line continuing the value of the last key, only yielded when multiline support is enabled.
+    Continuation { text: S },
+}
+
+impl<S> Event<S> {
+    ///Private function that converts the strings stored in the event.
+    fn map<T>(self, mut f: impl FnMut(S) -> T) -> Event<T> {
+        let kind = match self.kind {
+            EventKind::SectionStart { name } => EventKind::SectionStart { name: f(name) },
+            EventKind::KeyValue {
+                key,
+                delimiter,
+                value,
+            } => EventKind::KeyValue {
+                key: f(key),
+                delimiter,
+                value: value.map(&mut f),
+            },
+            EventKind::Comment { text } => EventKind::Comment { text: f(text) },
+            EventKind::Blank => EventKind::Blank,
+            EventKind::Continuation { text } => EventKind::Continuation { text: f(text) },
+        };
+        Event {
+            kind,
+            line: self.line,
+            column: self.column,
+            span: self.span,
+            value_span: self.value_span,
+        }
+    }
+}
+
+///Private function that splits a parser error like `line 3: message` or `line 3:5: message` into its line, column
+///and message.
+pub(crate) fn error_position(why: &str) -> Option<(usize, Option<usize>, &str)> {
+    let (position, message) = why.strip_prefix("line ")?.split_once(": ")?;
+    match position.split_once(':') {
+        Some((line, column)) => Some((line.parse().ok()?, Some(column.parse().ok()?), message)),
+        None => Some((position.parse().ok()?, None, message)),
+    }
+}
+
+///Private struct holding the line-level state machine shared by all event iterators.
+#[derive(Debug, Clone)]
+pub(crate) struct Lexer {
+    comment_symbols: Vec<char>,
+    inline_comment_symbols: Vec<char>,
+    delimiters: Vec<char>,
+    multiline: bool,
+    enable_inline_comments: bool,
+    escapes: Escapes,
+    line: usize,
+    has_entry: bool,
+}
+
+impl Lexer {
+    pub(crate) fn new(
+        comment_symbols: &[char],
+        inline_comment_symbols: Option<&[char]>,
+        delimiters: &[char],
+        multiline: bool,
+        enable_inline_comments: bool,
+        escapes: Escapes,
+    ) -> Lexer {
+        Lexer {
+            comment_symbols: comment_symbols.to_vec(),
+            inline_comment_symbols: inline_comment_symbols.unwrap_or(comment_symbols).to_vec(),
+            delimiters: delimiters.to_vec(),
+            multiline,
+            enable_inline_comments,
+            escapes,
+            line: 0,
+            has_entry: false,
+        }
+    }
+
+    pub(crate) fn from_defaults(defaults: &IniDefault) -> Lexer {
+        Lexer::new(
+            &defaults.comment_symbols,
+            defaults.inline_comment_symbols.as_deref(),
+            &defaults.delimiters,
+            defaults.multiline,
+            defaults.enable_inline_comments,
+            defaults.escapes,
+        )
+    }
+
+    ///Private function that returns the byte index of the inline comment in `line`, if any. With escapes, escaped
+    ///comment symbols (and quoted ones for `Escapes::Quoted`) don't start a comment.
+    fn find_inline_comment(&self, line: &str) -> Option<usize> {
+        let is_comment = |c: char| self.inline_comment_symbols.contains(&c);
+        if self.escapes == Escapes::None {
+            return line.find(is_comment);
+        }
+        let (mut escaped, mut quoted) = (false, false);
+        for (idx, c) in line.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' if self.escapes == Escapes::Quoted => quoted = !quoted,
+                c if !quoted && is_comment(c) => return Some(idx),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    ///Lexes the next line of input, which starts at byte offset `base` of the whole input, pushing its events to
+    ///`out`. `make` turns a byte range of `raw` into the string type stored in the events.
+    fn lex<S>(
+        &mut self,
+        raw: &str,
+        base: usize,
+        make: impl Fn(Range<usize>) -> S,
+        out: &mut VecDeque<Event<S>>,
+    ) -> Result<(), String> {
+        self.line += 1;
+        let line_number = self.line;
+        let offset = |sub: &str| sub.as_ptr() as usize - raw.as_ptr() as usize;
+        let column = |idx: usize| raw[..idx].chars().count() + 1;
+        let span = |range: Range<usize>| {
+            let start = column(range.start);
+            Span {
+                start: base + range.start,
+                end: base + range.end,
+                line: line_number,
+                column: start,
+                end_line: line_number,
+                end_column: start + raw[range].chars().count(),
+            }
+        };
+        let event = |kind: EventKind<S>, range: Range<usize>| Event {
+            kind,
+            line: line_number,
+            column: column(range.start),
+            span: span(range),
+            value_span: None,
+        };
+        let line = raw.trim();
+        let start = offset(line);
+
+        // If the line is _just_ a comment, skip it entirely. Only the first
+        // character can make this a full-line comment, so there's no need to
+        // scan the whole line.
+        if line.starts_with(|c: char| self.comment_symbols.contains(&c)) {
+            let range = start..start + line.len();
+            out.push_back(event(
+                EventKind::Comment {
+                    text: make(range.clone()),
+                },
+                range,
+            ));
+            return Ok(());
+        }
+
+        if line.is_empty() {
+            out.push_back(event(EventKind::Blank, raw.len()..raw.len()));
+            return Ok(());
+        }
+
+        // Split off a trailing inline comment if enabled. `line` is already
+        // trimmed on both ends, so only the comment-stripped slice needs to
+        // be re-trimmed.
+        let (trimmed, comment) = if self.enable_inline_comments {
+            match self.find_inline_comment(line) {
+                Some(idx) => (line[..idx].trim(), Some(start + idx..start + line.len())),
+                None => (line, None),
+            }
+        } else {
+            (line, None)
+        };
+        let start = offset(trimmed);
+
+        if trimmed.starts_with('[') {
+            match trimmed.rfind(']') {
+                Some(end) => {
+                    let name = trimmed[1..end].trim();
+                    let name = make(offset(name)..offset(name) + name.len());
+                    out.push_back(event(
+                        EventKind::SectionStart { name },
+                        start..start + end + 1,
+                    ));
+                }
+                None => {
+                    return Err(format!(
+                        "line {}:{}: Found opening bracket for section name but no closing bracket",
+                        line_number,
+                        column(start)
+                    ));
+                }
+            }
+        } else if raw.starts_with(char::is_whitespace) && self.multiline {
+            if !self.has_entry {
+                return Err(format!(
+                    "line {}:{}: Started with indentation but there is no current entry",
+                    line_number,
+                    column(start)
+                ));
+            }
+            let range = start..start + trimmed.len();
+            out.push_back(event(
+                EventKind::Continuation {
+                    text: make(range.clone()),
+                },
+                range,
+            ));
+        } else {
+            match trimmed.find(&self.delimiters[..]) {
+                Some(delimiter) => {
+                    let key = trimmed[..delimiter].trim();
+                    if key.is_empty() {
+                        return Err(format!(
+                            "line {}:{}: Key cannot be empty",
+                            line_number,
+                            column(start + delimiter)
+                        ));
+                    }
+                    let value = trimmed[delimiter + 1..].trim();
+                    let key = offset(key)..offset(key) + key.len();
+                    let value = offset(value)..offset(value) + value.len();
+                    let kind = EventKind::KeyValue {
+                        key: make(key.clone()),
+                        delimiter: trimmed[delimiter..].chars().next(),
+                        value: Some(make(value.clone())),
+                    };
+                    out.push_back(Event {
+                        value_span: Some(span(value)),
+                        ..event(kind, key)
+                    });
+                }
+                None => {
+                    let key = start..start + trimmed.len();
+                    let kind = EventKind::KeyValue {
+                        key: make(key.clone()),
+                        delimiter: None,
+                        value: None,
+                    };
+                    out.push_back(event(kind, key));
+                }
+            }
+            self.has_entry = true;
+        }
+
+        if let Some(range) = comment {
+            out.push_back(event(
+                EventKind::Comment {
+                    text: make(range.clone()),
+                },
+                range,
+            ));
+        }
+        Ok(())
+    }
+}
+
+///The `Events` struct is a pull parser over any `std::io::BufRead`. It reads one line at a time and yields an
+///`Event` for every section header, key, comment, blank line and multiline continuation it finds, honouring the
+///comment symbols, delimiters and multiline settings of the given defaults.
+///
+///Errors are yielded in place of the offending line, after which parsing continues with the next line.
+///## Example
+///```rust
+///use configparser::parser::{EventKind, Events};
+///
+///let input = "[section]\nkey = value ; comment\n";
+///let events: Vec<_> = Events::new(input.as_bytes())
+///    .map(|event| event.unwrap().kind)
+///    .collect();
+///assert_eq!(
+///    events,
+///    vec![
+///        EventKind::SectionStart { name: "section".to_owned() },
+///        EventKind::KeyValue {
+///            key: "key".to_owned(),
+///            delimiter: Some('='),
+///            value: Some("value".to_owned()),
+///        },
+///        EventKind::Comment { text: "; comment".to_owned() },
+///    ]
+///);
+///```
+#[derive(Debug)]
+pub struct Events<R> {
+    reader: R,
+    lexer: Lexer,
+    buf: String,
+    consumed: usize,
+    pending: VecDeque<Event>,
+}
+
+impl<R: BufRead> Events<R> {
+    ///Creates a new event iterator over the reader using the default options of `Ini::new()`.
+    ///## Example
+    ///```rust
+    ///use configparser::parser::Events;
+    ///
+    ///let events = Events::new("[section]\nkey=value".as_bytes());
+    ///assert_eq!(events.count(), 2);
+    ///```
+    pub fn new(reader: R) -> Events<R> {
+        Events::with_defaults(reader, &IniDefault::default())
+    }
+
+    ///Creates a new event iterator over the reader using the given defaults.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::IniDefault;
+    ///use configparser::parser::{EventKind, Events};
+    ///
+    ///let mut default = IniDefault::default();
+    ///default.multiline = true;
+    ///let mut events = Events::with_defaults("key=first\n  second".as_bytes(), &default);
+    ///let last = events.nth(1).unwrap().unwrap();
+    ///assert_eq!(last.kind, EventKind::Continuation { text: "second".to_owned() });
+    ///assert_eq!((last.line, last.column), (2, 3));
+    ///```
+    pub fn with_defaults(reader: R, defaults: &IniDefault) -> Events<R> {
+        Events::from_lexer(reader, Lexer::from_defaults(defaults))
+    }
+
+    pub(crate) fn from_lexer(reader: R, lexer: Lexer) -> Events<R> {
+        Events {
+            reader,
+            lexer,
+            buf: String::new(),
+            consumed: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Events<R> {
+    type Item = Result<Event, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            // Read raw bytes so that a line which isn't valid UTF-8 still counts towards the byte offsets.
+            let mut bytes = std::mem::take(&mut self.buf).into_bytes();
+            bytes.clear();
+            let base = self.consumed;
+            match self.reader.read_until(b'\n', &mut bytes) {
+                Ok(0) => return None,
+                Ok(read) => self.consumed += read,
+                Err(why) => return Some(Err(format!("line {}:1: {}", self.lexer.line + 1, why))),
+            }
+            match String::from_utf8(bytes) {
+                Ok(line) => self.buf = line,
+                Err(why) => {
+                    self.lexer.line += 1;
+                    let valid = &why.as_bytes()[..why.utf8_error().valid_up_to()];
+                    let column = String::from_utf8_lossy(valid).chars().count() + 1;
+                    return Some(Err(format!(
+                        "line {}:{}: stream did not contain valid UTF-8",
+                        self.lexer.line, column
+                    )));
+                }
+            }
+            // Strip the line ending the same way `str::lines()` does.
+            if self.buf.ends_with('\n') {
+                self.buf.pop();
+                if self.buf.ends_with('\r') {
+                    self.buf.pop();
+                }
+            }
+            let raw = self.buf.as_str();
+            if let Err(why) =
+                self.lexer
+                    .lex(raw, base, |range| raw[range].to_owned(), &mut self.pending)
+            {
+                return Some(Err(why));
+            }
+        }
+    }
+}
+
+///Private event iterator over an in-memory string, which borrows from it instead of allocating.
+pub(crate) struct StrEvents<'a> {
+    input: &'a str,
+    lines: std::str::Lines<'a>,
+    lexer: Lexer,
+    pending: VecDeque<Event<&'a str>>,
+}
+
+impl<'a> StrEvents<'a> {
+    pub(crate) fn new(input: &'a str, lexer: Lexer) -> StrEvents<'a> {
+        StrEvents {
+            input,
+            lines: input.lines(),
+            lexer,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for StrEvents<'a> {
+    type Item = Result<Event<Cow<'a, str>>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event.map(Cow::Borrowed)));
+            }
+            let raw = self.lines.next()?;
+            let base = raw.as_ptr() as usize - self.input.as_ptr() as usize;
+            if let Err(why) = self
+                .lexer
+                .lex(raw, base, |range| &raw[range], &mut self.pending)
+            {
+                return Some(Err(why));
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//!The properties module reads and writes Java `.properties` files into and from an `Ini`, so that they can be used
+//!with the typed getters and the rest of the crate like any other configuration.
+//!See [`Ini::read_properties()`] for more details.
+use crate::ini::{Ini, LINE_ENDING};
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap as Map;
+#[cfg(not(feature = "indexmap"))]
+use std::collections::HashMap as Map;
+
+use std::fs;
+use std::path::Path;
+
+///How the keys of a `.properties` file are mapped to sections.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub enum KeyLayout {
+    ///Every key is stored as-is in the default section.
+    #[default]
+    Flat,
+    ///Keys are split at their last dot, so `server.http.port` is the key `port` of the section `server.http`.
+    ///Keys without a dot are stored in the default section.
+    Dotted,
+}
+
+///The whitespace that separates keys from values and is skipped at the start of lines.
+const WHITESPACE: [char; 3] = [' ', '\t', '\x0c'];
+
+impl Ini {
+    ///Reads the text of a `.properties` file, replacing the stored configuration like `read()`. The syntax is the one
+    ///of `java.util.Properties`: keys are separated from their values by `=`, `:` or whitespace, lines starting with
+    ///`#` or `!` are comments, a line ending in a backslash continues on the next line (without its leading
+    ///whitespace), and `\t`, `\n`, `\r`, `\f` and `\uXXXX` are escapes, any other escaped character standing for
+    ///itself. The last of repeated keys wins. Keys are stored with the case handling of the `Ini`.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::properties::KeyLayout;
+    ///
+    ///let mut config = Ini::new_cs();
+    ///config.read_properties(String::from(
+    ///    "# service settings\nserver.port = 8080\nserver.name:caf\\u00e9\ngreeting Hello, \\\n    world\n",
+    ///), KeyLayout::Dotted).unwrap();
+    ///assert_eq!(config.getuint("server", "port").unwrap(), Some(8080));
+    ///assert_eq!(config.get("server", "name").unwrap(), "café");
+    ///assert_eq!(config.get("default", "greeting").unwrap(), "Hello, world");
+    ///```
+    ///Returns `Ok(map)` with a clone of the stored `Map` or an `Err(error_string)` for a malformed `\uXXXX` escape.
+    pub fn read_properties(
+        &mut self,
+        input: String,
+        layout: KeyLayout,
+    ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        let entries = parse_properties(&input)?;
+        self.clear();
+        let default_section = self.default_section_name();
+        for (key, value) in entries {
+            let (section, key) = match key.rsplit_once('.') {
+                Some((section, key)) if layout == KeyLayout::Dotted => (section, key),
+                _ => (default_section.as_str(), key.as_str()),
+            };
+            self.setstr(section, key, Some(&value));
+        }
+        Ok(self.get_map_ref().clone())
+    }
+
+    ///Loads a `.properties` file, see `read_properties()`. The file is expected to be UTF-8, which includes files
+    ///with only ASCII characters and `\uXXXX` escapes as written by `java.util.Properties`.
+    ///Returns `Ok(map)` with a clone of the stored `Map` or an `Err(error_string)` if the file can't be read or parsed.
+    pub fn load_properties<T: AsRef<Path>>(
+        &mut self,
+        path: T,
+        layout: KeyLayout,
+    ) -> Result<Map<String, Map<String, Option<String>>>, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+        self.read_properties(text, layout)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))
+    }
+
+    ///Writes the configuration in `.properties` syntax. Keys of the default section are written as they are and
+    ///keys of other sections as `section.key`, so both layouts read the file back. Separators, comment symbols,
+    ///backslashes, control characters, leading spaces of values and all non-ASCII characters are escaped, and keys
+    ///without a value get an empty one.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///
+    ///let mut config = Ini::new_cs();
+    ///config.set("db", "url", Some(String::from("jdbc:h2:mem")));
+    ///assert_eq!(config.writes_properties(), "db.url=jdbc\\:h2\\:mem\n");
+    ///```
+    ///Returns a `String` with the file text.
+    pub fn writes_properties(&self) -> String {
+        let default_section = self.default_section_name();
+        let map = self.get_map_ref();
+        let first = map.get_key_value(&default_section);
+        let rest = map
+            .iter()
+            .filter(|(section, _)| **section != default_section);
+        let mut out = String::new();
+        for (section, keys) in first.into_iter().chain(rest) {
+            for (key, value) in keys {
+                if *section != default_section {
+                    escape(&mut out, section, true);
+                    out.push('.');
+                }
+                escape(&mut out, key, true);
+                out.push('=');
+                escape(&mut out, value.as_deref().unwrap_or_default(), false);
+                out.push_str(LINE_ENDING);
+            }
+        }
+        out
+    }
+
+    ///Writes the configuration to a `.properties` file, see `writes_properties()`.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not.
+    pub fn write_properties<T: AsRef<Path>>(&self, path: T) -> std::io::Result<()> {
+        fs::write(path.as_ref(), self.writes_properties())
+    }
+}
+
+///Private function that splits `.properties` text into its unescaped keys and values.
+fn parse_properties(input: &str) -> Result<Vec<(String, String)>, String> {
+    let mut entries = Vec::new();
+    let mut lines = input.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let indent = line.len() - line.trim_start_matches(WHITESPACE).len();
+        let line = &line[indent..];
+        if line.is_empty() || line.starts_with(['#', '!']) {
+            continue;
+        }
+        let mut logical = line.to_owned();
+        // Where each physical line starts in `logical`, with its 1-based line and column.
+        let mut segments = vec![(0, index + 1, indent + 1)];
+        while continues(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some((index, next)) => {
+                    let trimmed = next.trim_start_matches(WHITESPACE);
+                    segments.push((logical.len(), index + 1, next.len() - trimmed.len() + 1));
+                    logical.push_str(trimmed);
+                }
+                None => break,
+            }
+        }
+        let (key, value) = split_entry(&logical);
+        let locate = |part: &str, (offset, message): (usize, &str)| {
+            let at = part.as_ptr() as usize - logical.as_ptr() as usize + offset;
+            let (start, line, column) = segments[segments.partition_point(|s| s.0 <= at) - 1];
+            let column = column + logical[start..at].chars().count();
+            format!("line {}:{}: {}", line, column, message)
+        };
+        entries.push((
+            unescape(key).map_err(|why| locate(key, why))?,
+            unescape(value).map_err(|why| locate(value, why))?,
+        ));
+    }
+    Ok(entries)
+}
+
+///Private function that checks if a line ends in an odd number of backslashes.
+fn continues(line: &str) -> bool {
+    (line.len() - line.trim_end_matches('\\').len()) % 2 == 1
+}
+
+///Private function that splits a logical line at the first unescaped separator: `=`, `:` or whitespace, optionally
+///followed by `=` or `:`, with the whitespace around them.
+fn split_entry(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '=' | ':' => {
+                return (
+                    &line[..index],
+                    line[index + 1..].trim_start_matches(WHITESPACE),
+                );
+            }
+            c if WHITESPACE.contains(&c) => {
+                let rest = line[index..].trim_start_matches(WHITESPACE);
+                let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+                return (&line[..index], rest.trim_start_matches(WHITESPACE));
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+///Private function that resolves the escapes of a key or value.
+///Returns the text or an `Err` with the byte offset of the malformed escape in `raw` and the error message.
+fn unescape(raw: &str) -> Result<String, (usize, &'static str)> {
+    let mut units: Vec<u16> = Vec::with_capacity(raw.len());
+    let mut chars = raw.char_indices();
+    let mut buf = [0; 2];
+    while let Some((offset, c)) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('f') => '\x0c',
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                    let unit = (hex.len() == 4)
+                        .then(|| u16::from_str_radix(&hex, 16).ok())
+                        .flatten()
+                        .ok_or((offset, "Malformed \\uXXXX escape"))?;
+                    units.push(unit);
+                    continue;
+                }
+                Some(other) => other,
+                None => break,
+            },
+            c => c,
+        };
+        units.extend_from_slice(c.encode_utf16(&mut buf));
+    }
+    String::from_utf16(&units).map_err(|_| (0, "Unpaired surrogate in \\uXXXX escapes"))
+}
+
+///Private function that writes a key or value with the escapes `java.util.Properties` writes.
+fn escape(out: &mut String, text: &str, key: bool) {
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            ' ' if key || index == 0 => out.push_str("\\ "),
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\x0c' => out.push_str("\\f"),
+            '=' | ':' | '#' | '!' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    out.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+}
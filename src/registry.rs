@@ -0,0 +1,528 @@
+//!The registry module reads and writes Windows registry export files (`.reg` files) as written by `regedit`, in both
+//!the `REGEDIT4` and the `Windows Registry Editor Version 5.00` format, with their quoted value names, typed data and
+//!encodings.
+//!See the [`RegFile`] documentation for more details.
+use crate::dialect::Dialect;
+use crate::ini::Ini;
+use crate::parser::{EventKind, Lexer, StrEvents};
+
+use std::fs;
+use std::path::Path;
+
+///The line ending of registry files, which are always written with Windows line endings.
+const CRLF: &str = "\r\n";
+
+///The width `regedit` wraps long hex lists at.
+const WRAP_WIDTH: usize = 80;
+
+///The format of a registry file, given by its header line.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum RegVersion {
+    ///`REGEDIT4`, whose files and strings are encoded in the ANSI code page. Only Latin-1 is supported, other
+    ///characters are written as `?`.
+    Regedit4,
+    ///`Windows Registry Editor Version 5.00`, whose files and strings are encoded in UTF-16LE.
+    Version5,
+}
+
+impl RegVersion {
+    ///Returns the header line of the format.
+    pub fn header(self) -> &'static str {
+        match self {
+            RegVersion::Regedit4 => "REGEDIT4",
+            RegVersion::Version5 => "Windows Registry Editor Version 5.00",
+        }
+    }
+}
+
+///The typed data of a registry value.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum RegValue {
+    ///A string (`REG_SZ`), written as `"text"`.
+    String(String),
+    ///A 32-bit number (`REG_DWORD`), written as `dword:0000002a`.
+    Dword(u32),
+    ///A 64-bit number (`REG_QWORD`), written as `hex(b):` and its little-endian bytes.
+    Qword(u64),
+    ///A string with environment variables like `%SystemRoot%` (`REG_EXPAND_SZ`), written as `hex(2):` and its
+    ///encoded bytes.
+    ExpandString(String),
+    ///A list of strings (`REG_MULTI_SZ`), written as `hex(7):` and their encoded bytes.
+    MultiString(Vec<String>),
+    ///Binary data (`REG_BINARY`), written as `hex:` and the bytes.
+    Binary(Vec<u8>),
+    ///Data of another type, like `REG_NONE` (0), written as `hex(type):` and the bytes.
+    Raw { kind: u32, data: Vec<u8> },
+    ///The deletion of the value when the file is imported, written as `-`.
+    Delete,
+}
+
+///The `RegFile` struct holds a registry file. Its registry keys are the sections of an `Ini` and their values the keys,
+///with `@` standing for the default value of a registry key. Like in the registry, names are case-insensitive and keep
+///their spelling. Each value is stored in the `Ini` as the data it is written with, in one line, and typed by `get()`.
+///
+///A registry key written as `[-HKEY_CURRENT_USER\Software\App]` is deleted when the file is imported. It's stored like
+///any other registry key, including the leading `-`. Registry keys are written in the order they were read or added,
+///since importing applies them in that order.
+///## Example
+///```rust
+///use configparser::registry::{RegFile, RegValue};
+///
+///let mut file = RegFile::read(
+///    "Windows Registry Editor Version 5.00\r\n\r\n[HKEY_CURRENT_USER\\Software\\App]\r\n\
+///     @=\"App\"\r\n\"Install \\\"Dir\\\"\"=\"C:\\\\App\"\r\n\"Retries\"=dword:00000003\r\n\
+///     \"Path\"=hex(2):25,00,41,00,25,00,00,00\r\n\"Blob\"=hex:01,02,\\\r\n  03\r\n",
+///).unwrap();
+///let key = "hkey_current_user\\software\\app";
+///assert_eq!(file.get(key, "@"), Some(RegValue::String(String::from("App"))));
+///assert_eq!(file.get(key, "install \"dir\""), Some(RegValue::String(String::from("C:\\App"))));
+///assert_eq!(file.get(key, "Retries"), Some(RegValue::Dword(3)));
+///assert_eq!(file.get(key, "Path"), Some(RegValue::ExpandString(String::from("%A%"))));
+///assert_eq!(file.get(key, "Blob"), Some(RegValue::Binary(vec![1, 2, 3])));
+///assert_eq!(file.as_ini().get(key, "blob").unwrap(), "hex:01,02,03");
+///
+///file.set(key, "Retries", &RegValue::Dword(5));
+///assert!(file.writes().contains("\"Retries\"=dword:00000005\r\n"));
+///```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RegFile {
+    version: RegVersion,
+    config: Ini,
+    ///The stored names of the registry keys in file order, which matters when importing: `[-key]` followed by
+    ///`[key]` deletes the key and then recreates it.
+    order: Vec<String>,
+}
+
+impl RegFile {
+    ///Creates an empty registry file of the given format.
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new(version: RegVersion) -> RegFile {
+        RegFile {
+            version,
+            config: Ini::new_from_defaults(Dialect::Windows.defaults()),
+            order: Vec::new(),
+        }
+    }
+
+    ///Parses the text of a registry file. The first line must be the header of one of the formats, lines starting
+    ///with `;` are comments and a line ending in a backslash continues on the next line, which `regedit` uses to wrap
+    ///hex lists.
+    ///Returns `Ok(file)` or an `Err(error_string)` if the text isn't a valid registry file.
+    pub fn read(input: &str) -> Result<RegFile, String> {
+        let joined = join_continuations(input);
+        let header = joined.lines().next().unwrap_or_default();
+        let version = match header.trim_start_matches('\u{feff}').trim() {
+            "REGEDIT4" => RegVersion::Regedit4,
+            "Windows Registry Editor Version 5.00" => RegVersion::Version5,
+            _ => {
+                return Err(String::from(
+                    "line 1:1: Expected a REGEDIT4 or Windows Registry Editor Version 5.00 header",
+                ));
+            }
+        };
+        let mut file = RegFile::new(version);
+        // Lines are lexed without delimiters, since `=` may be part of a quoted value name.
+        let mut defaults = file.config.defaults();
+        defaults.delimiters = Vec::new();
+        let mut section: Option<String> = None;
+        for event in StrEvents::new(&joined, Lexer::from_defaults(&defaults)).skip(1) {
+            let event = event?;
+            let (line, column) = (event.line, event.column);
+            match event.kind {
+                EventKind::SectionStart { name } => {
+                    file.add_key(&name);
+                    section = Some(name.into_owned());
+                }
+                EventKind::KeyValue { key, .. } => {
+                    let section = section.as_deref().ok_or_else(|| {
+                        format!("line {}:{}: Value outside of a registry key", line, column)
+                    })?;
+                    let (name, data) = split_value(&key).ok_or_else(|| {
+                        format!("line {}:{}: Expected \"name\"=data or @=data", line, column)
+                    })?;
+                    let value = parse_data(data, version)
+                        .map_err(|why| format!("line {}:{}: {}", line, column, why))?;
+                    file.set(section, &name, &value);
+                }
+                _ => {}
+            }
+        }
+        Ok(file)
+    }
+
+    ///Decodes and parses a registry file, see `read()`. Files starting with a UTF-16LE byte order mark, as written
+    ///for `Windows Registry Editor Version 5.00`, are decoded as UTF-16LE, other files as UTF-8 if they are valid
+    ///UTF-8 and as Latin-1 otherwise.
+    ///Returns `Ok(file)` or an `Err(error_string)` if the bytes can't be decoded or parsed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RegFile, String> {
+        let text = match bytes {
+            [0xff, 0xfe, rest @ ..] => {
+                if rest.len() % 2 != 0 {
+                    return Err(String::from("Invalid UTF-16LE: odd number of bytes"));
+                }
+                let units: Vec<u16> = rest
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16(&units).map_err(|why| format!("Invalid UTF-16LE: {}", why))?
+            }
+            [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+            _ => match std::str::from_utf8(bytes) {
+                Ok(text) => text.to_owned(),
+                Err(_) => bytes.iter().map(|&byte| char::from(byte)).collect(),
+            },
+        };
+        RegFile::read(&text)
+    }
+
+    ///Loads a registry file, see `from_bytes()`.
+    ///Returns `Ok(file)` or an `Err(error_string)` if the file can't be read, decoded or parsed.
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<RegFile, String> {
+        let path = path.as_ref();
+        let bytes =
+            fs::read(path).map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+        RegFile::from_bytes(&bytes)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))
+    }
+
+    ///Returns the format of the file.
+    pub fn version(&self) -> RegVersion {
+        self.version
+    }
+
+    ///Returns the paths of all registry keys in file order, like `HKEY_CURRENT_USER\Software\App`.
+    pub fn keys(&self) -> Vec<String> {
+        self.order.clone()
+    }
+
+    ///Returns the typed data of a value, or `None` if the registry key or value is missing. The default value of a
+    ///registry key is named `@`.
+    pub fn get(&self, key: &str, name: &str) -> Option<RegValue> {
+        let data = self.config.get(key, name)?;
+        parse_data(&data, self.version).ok()
+    }
+
+    ///Returns the names and typed data of all values of a registry key, or `None` if the registry key is missing.
+    pub fn values(&self, key: &str) -> Option<Vec<(String, RegValue)>> {
+        let section = self.config.section(key)?;
+        let values = section
+            .iter()
+            .filter_map(|(name, data)| {
+                let value = parse_data(data?, self.version).ok()?;
+                Some((name.to_owned(), value))
+            })
+            .collect();
+        Some(values)
+    }
+
+    ///Sets a value of a registry key, adding the registry key if it's missing.
+    pub fn set(&mut self, key: &str, name: &str, value: &RegValue) {
+        let data = format_data(value, self.version);
+        self.add_key(key);
+        self.config.setstr(key, name, Some(&data));
+    }
+
+    ///Removes a value of a registry key.
+    ///Returns `true` if the value was set.
+    pub fn remove(&mut self, key: &str, name: &str) -> bool {
+        self.config.remove_key(key, name).is_some()
+    }
+
+    ///Replaces a registry key with its deletion, written as `[-key]`, so that importing the file deletes it with all
+    ///its values and subkeys. Setting values of the registry key afterwards adds it again after its deletion.
+    pub fn delete_key(&mut self, key: &str) {
+        let name = self.config.section_name(key);
+        if self.config.remove_section(key).is_some() {
+            self.order.retain(|stored| *stored != name);
+        }
+        self.add_key(&format!("-{}", key));
+    }
+
+    ///Private function that adds a registry key at the end of the file if it's missing.
+    fn add_key(&mut self, key: &str) {
+        if self.config.section(key).is_none() {
+            self.config.section_mut(key);
+            self.order.push(self.config.section_name(key));
+        }
+    }
+
+    ///Returns the underlying `Ini`, which stores the data of each value in one line, like `dword:0000002a`.
+    pub fn as_ini(&self) -> &Ini {
+        &self.config
+    }
+
+    ///Writes the file in its format, with Windows line endings and hex lists wrapped like `regedit` does.
+    ///Returns a `String` with the file text.
+    pub fn writes(&self) -> String {
+        let mut out = String::from(self.version.header());
+        out.push_str(CRLF);
+        for key in self.order.iter() {
+            let Some(values) = self.config.section(key) else {
+                continue;
+            };
+            out.push_str(CRLF);
+            out.push('[');
+            out.push_str(key);
+            out.push(']');
+            out.push_str(CRLF);
+            for (name, data) in values.iter() {
+                let start = out.len();
+                if name == "@" {
+                    out.push('@');
+                } else {
+                    out.push('"');
+                    out.push_str(&escape(name));
+                    out.push('"');
+                }
+                out.push('=');
+                let data = data.unwrap_or_default();
+                match data
+                    .split_once(':')
+                    .filter(|(kind, _)| kind.starts_with("hex"))
+                {
+                    Some((kind, bytes)) => {
+                        out.push_str(kind);
+                        out.push(':');
+                        let mut width = out.len() - start;
+                        let mut bytes = bytes.split(',').peekable();
+                        while let Some(byte) = bytes.next() {
+                            let last = bytes.peek().is_none();
+                            // The comma and a backslash need to fit in before wrapping.
+                            if width + byte.len() + 2 > WRAP_WIDTH {
+                                out.push('\\');
+                                out.push_str(CRLF);
+                                out.push_str("  ");
+                                width = 2;
+                            }
+                            out.push_str(byte);
+                            width += byte.len();
+                            if !last {
+                                out.push(',');
+                                width += 1;
+                            }
+                        }
+                    }
+                    None => out.push_str(data),
+                }
+                out.push_str(CRLF);
+            }
+        }
+        out.push_str(CRLF);
+        out
+    }
+
+    ///Encodes the file in its format: UTF-16LE with a byte order mark for `Version5` and Latin-1 for `Regedit4`.
+    ///Returns the bytes of the file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let text = self.writes();
+        match self.version {
+            RegVersion::Regedit4 => text.chars().map(latin1).collect(),
+            RegVersion::Version5 => {
+                let mut bytes = vec![0xff, 0xfe];
+                bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+                bytes
+            }
+        }
+    }
+
+    ///Writes the file, see `to_bytes()`.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not.
+    pub fn write<T: AsRef<Path>>(&self, path: T) -> std::io::Result<()> {
+        fs::write(path.as_ref(), self.to_bytes())
+    }
+}
+
+///Private function that joins lines ending in a backslash, which wrap hex lists, with the next line, without its
+///leading whitespace. Joined lines are followed by empty lines, so that line numbers stay the same.
+fn join_continuations(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut joined = 0;
+    let mut continued = false;
+    for line in input.lines() {
+        let line = if continued {
+            line.trim()
+        } else {
+            line.trim_end()
+        };
+        continued = !line.starts_with(['[', ';']) && line.ends_with('\\');
+        if continued {
+            out.push_str(&line[..line.len() - 1]);
+            joined += 1;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            for _ in 0..joined {
+                out.push('\n');
+            }
+            joined = 0;
+        }
+    }
+    out
+}
+
+///Private function that splits a value line like `"name"=data` or `@=data` into the unescaped name and the data.
+fn split_value(line: &str) -> Option<(String, &str)> {
+    let (name, rest) = match line.strip_prefix('@') {
+        Some(rest) => (String::from("@"), rest),
+        None => {
+            let quoted = line.strip_prefix('"')?;
+            let end = closing_quote(quoted)?;
+            (unescape(&quoted[..end]), &quoted[end + 1..])
+        }
+    };
+    let data = rest.trim_start().strip_prefix('=')?.trim();
+    Some((name, data))
+}
+
+///Private function that finds the closing quote of a quoted name or string, skipping escaped characters.
+fn closing_quote(quoted: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in quoted.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+///Private function that resolves the `\\` and `\"` escapes of a quoted name or string.
+fn unescape(quoted: &str) -> String {
+    let mut out = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+///Private function that escapes backslashes and quotes of a name or string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+///Private function that encodes a character as Latin-1, replacing other characters with `?`.
+fn latin1(c: char) -> u8 {
+    u8::try_from(u32::from(c)).unwrap_or(b'?')
+}
+
+///Private function that parses the data of a value, like `"text"`, `dword:0000002a` or `hex(7):61,00,00,00`.
+fn parse_data(data: &str, version: RegVersion) -> Result<RegValue, String> {
+    if data == "-" {
+        return Ok(RegValue::Delete);
+    }
+    if let Some(quoted) = data.strip_prefix('"') {
+        return match closing_quote(quoted) {
+            Some(end) if end == quoted.len() - 1 => Ok(RegValue::String(unescape(&quoted[..end]))),
+            _ => Err(format!("Invalid string {}", data)),
+        };
+    }
+    if let Some(digits) = data.strip_prefix("dword:") {
+        return match u32::from_str_radix(digits, 16) {
+            Ok(number) if digits.len() <= 8 => Ok(RegValue::Dword(number)),
+            _ => Err(format!("Invalid dword {}", data)),
+        };
+    }
+    let (kind, bytes) = match data.strip_prefix("hex") {
+        Some(rest) => match rest.strip_prefix(':') {
+            Some(bytes) => (3, bytes),
+            None => rest
+                .strip_prefix('(')
+                .and_then(|rest| rest.split_once("):"))
+                .and_then(|(kind, bytes)| Some((u32::from_str_radix(kind, 16).ok()?, bytes)))
+                .ok_or_else(|| format!("Invalid hex type in {}", data))?,
+        },
+        None => return Err(format!("Unknown data type in {}", data)),
+    };
+    let bytes = bytes
+        .split(',')
+        .map(str::trim)
+        .filter(|byte| !byte.is_empty())
+        .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| format!("Invalid hex byte {}", byte)))
+        .collect::<Result<Vec<u8>, String>>()?;
+    Ok(match (kind, bytes.len()) {
+        (2, _) => {
+            RegValue::ExpandString(decode_strings(&bytes, version).next().unwrap_or_default())
+        }
+        (7, _) => RegValue::MultiString(
+            decode_strings(&bytes, version)
+                .take_while(|s| !s.is_empty())
+                .collect(),
+        ),
+        (3, _) => RegValue::Binary(bytes),
+        (0xb, 8) => RegValue::Qword(u64::from_le_bytes(bytes.try_into().unwrap_or_default())),
+        (kind, _) => RegValue::Raw { kind, data: bytes },
+    })
+}
+
+///Private function that decodes NUL-terminated strings, as UTF-16LE for `Version5` and Latin-1 for `Regedit4`.
+fn decode_strings(bytes: &[u8], version: RegVersion) -> impl Iterator<Item = String> {
+    let strings: Vec<String> = match version {
+        RegVersion::Regedit4 => bytes
+            .split(|&byte| byte == 0)
+            .map(|string| string.iter().map(|&byte| char::from(byte)).collect())
+            .collect(),
+        RegVersion::Version5 => {
+            let units: Vec<u16> = bytes
+                .chunks(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]))
+                .collect();
+            units
+                .split(|&unit| unit == 0)
+                .map(String::from_utf16_lossy)
+                .collect()
+        }
+    };
+    strings.into_iter()
+}
+
+///Private function that encodes NUL-terminated strings, followed by another NUL for lists.
+fn encode_strings(strings: &[String], list: bool, version: RegVersion) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for string in strings {
+        match version {
+            RegVersion::Regedit4 => bytes.extend(string.chars().map(latin1).chain([0])),
+            RegVersion::Version5 => {
+                bytes.extend(string.encode_utf16().chain([0]).flat_map(u16::to_le_bytes))
+            }
+        }
+    }
+    if list {
+        let nul: &[u8] = if version == RegVersion::Version5 {
+            &[0, 0]
+        } else {
+            &[0]
+        };
+        bytes.extend_from_slice(nul);
+    }
+    bytes
+}
+
+///Private function that writes the data of a value in one line.
+fn format_data(value: &RegValue, version: RegVersion) -> String {
+    let hex = |kind: &str, bytes: &[u8]| {
+        let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("{}:{}", kind, bytes.join(","))
+    };
+    match value {
+        RegValue::String(string) => format!("\"{}\"", escape(string)),
+        RegValue::Dword(number) => format!("dword:{:08x}", number),
+        RegValue::Qword(number) => hex("hex(b)", &number.to_le_bytes()),
+        RegValue::ExpandString(string) => hex(
+            "hex(2)",
+            &encode_strings(std::slice::from_ref(string), false, version),
+        ),
+        RegValue::MultiString(strings) => hex("hex(7)", &encode_strings(strings, true, version)),
+        RegValue::Binary(bytes) => hex("hex", bytes),
+        RegValue::Raw { kind, data } => hex(&format!("hex({:x})", kind), data),
+        RegValue::Delete => String::from("-"),
+    }
+}
@@ -0,0 +1,411 @@
+//!The report module renders errors for people rather than programs. A `Report` describes a single problem, whether
+//!it is a parse error, a value one of the `get*` functions couldn't convert, a schema violation or a lint finding,
+//!and a `Renderer` prints it with the file name, the offending line with a caret under the column, the lines around
+//!it and a hint, in plain text or with ANSI colours.
+//!See the [`Renderer`] documentation for more details.
+use crate::ini::Ini;
+use crate::lint::{Diagnostic, Severity};
+use crate::parser::error_position;
+use crate::schema::{Violation, ViolationKind};
+
+use std::fmt::Write;
+
+///A single problem to render, with its position in the source if it is known.
+///## Example
+///```rust
+///use configparser::lint::Severity;
+///use configparser::report::Report;
+///
+///let report = Report::new(Severity::Warning, "port 80 needs root").at(2, 8).width(2).hint("use 8080");
+///assert_eq!((report.line, report.column, report.width), (Some(2), Some(8), Some(2)));
+///```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Report {
+    pub severity: Severity,
+    ///An identifier of the kind of problem, such as the ID of a lint rule, rendered after the severity.
+    pub code: Option<String>,
+    pub message: String,
+    ///The 1-based line of the problem, if known.
+    pub line: Option<usize>,
+    ///The 1-based column (in characters) of the problem, if known. Without a column, the whole line is marked.
+    pub column: Option<usize>,
+    ///How many characters are marked from the column. Without a width, the mark reaches the end of the line.
+    pub width: Option<usize>,
+    ///A suggestion of how to fix the problem.
+    pub hint: Option<String>,
+}
+
+impl Report {
+    ///Creates a new report without a position, code or hint.
+    ///## Example
+    ///```rust
+    ///use configparser::lint::Severity;
+    ///use configparser::report::Report;
+    ///
+    ///let report = Report::new(Severity::Error, "something went wrong");
+    ///assert_eq!(report.line, None);
+    ///```
+    pub fn new(severity: Severity, message: impl Into<String>) -> Report {
+        Report {
+            severity,
+            code: None,
+            message: message.into(),
+            line: None,
+            column: None,
+            width: None,
+            hint: None,
+        }
+    }
+
+    ///Sets the 1-based line and column (in characters) of the problem.
+    pub fn at(mut self, line: usize, column: usize) -> Report {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    ///Sets how many characters are marked from the column.
+    pub fn width(mut self, width: usize) -> Report {
+        self.width = Some(width);
+        self
+    }
+
+    ///Sets the code rendered after the severity.
+    pub fn code(mut self, code: impl Into<String>) -> Report {
+        self.code = Some(code.into());
+        self
+    }
+
+    ///Sets the suggestion of how to fix the problem.
+    pub fn hint(mut self, hint: impl Into<String>) -> Report {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    ///Creates a report from an error returned while loading or reading, such as `line 4:1: Found opening bracket for
+    ///section name but no closing bracket`, taking the position from the message and adding a hint for the
+    ///errors of the parser. Anything before the position, like `couldn't read app.ini: `, is kept in the message.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::report::Report;
+    ///
+    ///let error = Ini::new().read(String::from("[server\nport = 80")).unwrap_err();
+    ///let report = Report::from_parse_error(&error);
+    ///assert_eq!(report.message, "Found opening bracket for section name but no closing bracket");
+    ///assert_eq!((report.line, report.column), (Some(1), Some(1)));
+    ///assert!(report.hint.is_some());
+    ///```
+    pub fn from_parse_error(error: &str) -> Report {
+        let mut report = Report::new(Severity::Error, error);
+        // The position follows the context added by the loading functions, if any.
+        let starts = std::iter::once(0).chain(error.match_indices(": ").map(|(idx, _)| idx + 2));
+        for start in starts {
+            let Some((line, column, message)) = error_position(&error[start..]) else {
+                continue;
+            };
+            report.message = format!("{}{}", &error[..start], message);
+            report.line = Some(line);
+            report.column = column;
+            if message.ends_with("Key cannot be empty") {
+                report.width = Some(1);
+                report.hint = Some(String::from(
+                    "write the name of the key before the delimiter",
+                ));
+            } else if message.ends_with("no closing bracket") {
+                report.hint = Some(String::from("add a `]` at the end of the section header"));
+            } else if message.ends_with("there is no current entry") {
+                report.hint = Some(String::from(
+                    "remove the indentation, or add a key above the line whose value it continues",
+                ));
+            }
+            break;
+        }
+        report
+    }
+
+    ///Creates a report for an error returned by one of the `get*` functions of `config`, such as `getint()`,
+    ///pointing to the value of the key in the source it was parsed from.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::report::Report;
+    ///
+    ///let mut config = Ini::new();
+    ///config.read(String::from("[server]\nport = eighty")).unwrap();
+    ///let error = config.getuint("server", "port").unwrap_err();
+    ///let report = Report::from_value_error(&config, "server", "port", &error).hint("use a number like 80");
+    ///assert_eq!(report.message, "invalid value for server:port: invalid digit found in string");
+    ///assert_eq!((report.line, report.column, report.width), (Some(2), Some(8), Some(6)));
+    ///```
+    pub fn from_value_error(config: &Ini, section: &str, key: &str, error: &str) -> Report {
+        // The getters prefix errors with the position of the value, which the report shows on its own.
+        let error = error_position(error).map_or(error, |(_, _, message)| message);
+        let mut report = Report::new(
+            Severity::Error,
+            format!("invalid value for {}:{}: {}", section, key, error),
+        );
+        // Values missing from their section were cascaded from the default section.
+        let found = config
+            .span_of(section, key)
+            .or_else(|| config.span_of(&config.default_section_name(), key));
+        if let Some(found) = found {
+            let span = found.value.unwrap_or(found.key);
+            report.line = Some(span.line);
+            report.column = Some(span.column);
+            // Multiline values are marked to the end of their first line.
+            if span.end_line == span.line {
+                report.width = Some(span.end_column - span.column);
+            }
+        }
+        report
+    }
+
+    ///Creates a report from a schema violation, with a hint for missing and unknown sections and keys.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::report::Report;
+    ///use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+    ///
+    ///let schema = Schema::new().section(
+    ///    SectionSchema::new("server").key(KeySchema::new("port", ValueType::String).required()),
+    ///);
+    ///let mut config = Ini::new();
+    ///config.read(String::from("[server]\nhost = example.com")).unwrap();
+    ///let violations = config.validate(&schema).unwrap_err();
+    ///let report = Report::from_violation(&violations[0]);
+    ///assert_eq!(report.message, "server:port: missing required key");
+    ///assert_eq!(report.hint.as_deref(), Some("add `port = ...` to the [server] section"));
+    ///```
+    pub fn from_violation(violation: &Violation) -> Report {
+        let mut unplaced = violation.clone();
+        unplaced.line = None;
+        unplaced.column = None;
+        let mut report = Report::new(Severity::Error, unplaced.to_string());
+        report.line = violation.line;
+        report.column = violation.column;
+        let section = &violation.section;
+        report.hint = match (&violation.kind, &violation.key) {
+            (ViolationKind::MissingSection, _) => Some(format!("add a [{}] section", section)),
+            (ViolationKind::MissingKey, Some(key)) => {
+                Some(format!("add `{} = ...` to the [{}] section", key, section))
+            }
+            (ViolationKind::UnknownSection, _) => Some(String::from(
+                "remove the section or check the spelling of its name",
+            )),
+            (ViolationKind::UnknownKey, _) => Some(String::from(
+                "remove the key or check the spelling of its name",
+            )),
+            _ => None,
+        };
+        report
+    }
+
+    ///Creates a report from a finding of the `Linter`, using the ID of its rule as the code.
+    ///## Example
+    ///```rust
+    ///use configparser::lint::Linter;
+    ///use configparser::report::Report;
+    ///
+    ///let diagnostics = Linter::new().lint("[server]\nport = 80\nport = 8080");
+    ///let report = Report::from_lint(&diagnostics[0]);
+    ///assert_eq!(report.code.as_deref(), Some("duplicate-key"));
+    ///assert_eq!((report.line, report.column), (Some(3), Some(1)));
+    ///```
+    pub fn from_lint(diagnostic: &Diagnostic) -> Report {
+        Report::new(diagnostic.severity, diagnostic.message.clone())
+            .code(diagnostic.rule.id())
+            .at(diagnostic.line, diagnostic.column)
+    }
+}
+
+///The ANSI escape codes used by a colouring `Renderer`.
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+///How many columns a tab takes up in a rendered snippet.
+const TAB_WIDTH: usize = 4;
+
+///The `Renderer` struct turns a `Report` into a multi-line message in the style of compiler errors: the severity and
+///message, the file name and position, the offending line with a caret under the marked characters between a few
+///lines of context, and the hint. Tabs in the snippet are expanded so the caret lines up.
+///## Example
+///```rust
+///use configparser::ini::Ini;
+///use configparser::report::{Renderer, Report};
+///
+///let source = "[server]\nport = 80\n[log\nlevel = info";
+///let error = Ini::new().read(source.to_owned()).unwrap_err();
+///let rendered = Renderer::new().render(&Report::from_parse_error(&error), "app.ini", source);
+///assert_eq!(
+///    rendered,
+///    "error: Found opening bracket for section name but no closing bracket
+/// --> app.ini:3:1
+///  |
+///2 | port = 80
+///3 | [log
+///  | ^^^^
+///4 | level = info
+///  |
+///  = hint: add a `]` at the end of the section header
+///"
+///);
+///```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Renderer {
+    context: usize,
+    color: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer {
+            context: 1,
+            color: false,
+        }
+    }
+}
+
+impl Renderer {
+    ///Creates a new renderer that writes plain text with one line of context before and after the offending line.
+    pub fn new() -> Renderer {
+        Renderer::default()
+    }
+
+    ///Sets how many lines are shown before and after the offending line.
+    ///## Example
+    ///```rust
+    ///use configparser::lint::Severity;
+    ///use configparser::report::{Renderer, Report};
+    ///
+    ///let report = Report::new(Severity::Error, "bad value").at(2, 5);
+    ///let rendered = Renderer::new().context(0).render(&report, "app.ini", "a = 1\nb = 2\nc = 3");
+    ///assert_eq!(rendered, "error: bad value\n --> app.ini:2:5\n  |\n2 | b = 2\n  |     ^\n");
+    ///```
+    pub fn context(mut self, lines: usize) -> Renderer {
+        self.context = lines;
+        self
+    }
+
+    ///Sets whether the output is coloured with ANSI escape codes, which is meant for terminals.
+    ///## Example
+    ///```rust
+    ///use configparser::lint::Severity;
+    ///use configparser::report::{Renderer, Report};
+    ///
+    ///let report = Report::new(Severity::Warning, "unused");
+    ///let rendered = Renderer::new().color(true).render(&report, "app.ini", "");
+    ///assert!(rendered.starts_with("\x1b[1;33mwarning\x1b[0m"));
+    ///```
+    pub fn color(mut self, color: bool) -> Renderer {
+        self.color = color;
+        self
+    }
+
+    ///Renders the report for `source`, the text of the file called `name`. The snippet is left out if the report
+    ///has no line or the line isn't in `source`.
+    ///
+    ///Returns the rendered report, ending with a line break.
+    pub fn render(&self, report: &Report, name: &str, source: &str) -> String {
+        let paint = |style: &str, text: &str| {
+            if self.color {
+                format!("{}{}{}", style, text, RESET)
+            } else {
+                text.to_owned()
+            }
+        };
+        let level = match report.severity {
+            Severity::Warning => YELLOW,
+            _ => RED,
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let snippet = report.line.filter(|&line| line >= 1 && line <= lines.len());
+        let last = snippet.map_or(0, |line| (line + self.context).min(lines.len()));
+        let gutter = " ".repeat(last.to_string().len());
+
+        let mut out = String::new();
+        let label = match &report.code {
+            Some(code) => format!("{}[{}]", report.severity, code),
+            None => report.severity.to_string(),
+        };
+        writeln!(
+            out,
+            "{}{}",
+            paint(level, &label),
+            paint(BOLD, &format!(": {}", report.message))
+        )
+        .unwrap();
+        let position = match (report.line, report.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", name, line, column),
+            (Some(line), None) => format!("{}:{}", name, line),
+            _ => name.to_owned(),
+        };
+        writeln!(out, "{}{} {}", gutter, paint(BLUE, "-->"), position).unwrap();
+
+        if let Some(line) = snippet {
+            let bar = paint(BLUE, "|");
+            writeln!(out, "{} {}", gutter, bar).unwrap();
+            for number in line.saturating_sub(self.context).max(1)..=last {
+                let text = lines[number - 1];
+                let number_text = format!("{:>width$}", number, width = gutter.len());
+                let expanded = text.replace('\t', &" ".repeat(TAB_WIDTH));
+                writeln!(out, "{} {} {}", paint(BLUE, &number_text), bar, expanded).unwrap();
+                if number == line {
+                    let (start, width) = mark(text, report.column, report.width);
+                    let carets = "^".repeat(width);
+                    writeln!(
+                        out,
+                        "{} {} {}{}",
+                        gutter,
+                        bar,
+                        " ".repeat(start),
+                        paint(level, &carets)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        if let Some(hint) = &report.hint {
+            if snippet.is_some() {
+                writeln!(out, "{} {}", gutter, paint(BLUE, "|")).unwrap();
+            }
+            writeln!(
+                out,
+                "{} {} {}: {}",
+                gutter,
+                paint(BLUE, "="),
+                paint(CYAN, "hint"),
+                hint
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+///Private function that returns the display column (counting tabs as `TAB_WIDTH`) and width of the characters of
+///`text` marked by a report. Without a column, the whole text without its indentation is marked, and without a
+///width, everything from the column to the end of the text. At least one character is always marked.
+fn mark(text: &str, column: Option<usize>, width: Option<usize>) -> (usize, usize) {
+    let display = |c: char| if c == '\t' { TAB_WIDTH } else { 1 };
+    let trimmed = text.trim_end();
+    let skip = match column {
+        Some(column) => column.saturating_sub(1),
+        None => trimmed.chars().take_while(|c| c.is_whitespace()).count(),
+    };
+    let start = text.chars().take(skip).map(display).sum::<usize>()
+        + skip.saturating_sub(text.chars().count());
+    let width: usize = match width {
+        Some(width) => text.chars().skip(skip).take(width).map(display).sum(),
+        None => trimmed.chars().skip(skip).map(display).sum(),
+    };
+    (start, width.max(1))
+}
@@ -0,0 +1,745 @@
+//!The schema module provides declarative validation of configurations. A `Schema` describes the sections and keys a
+//!program expects, together with the types and ranges of their values, and `Ini::validate()` checks a loaded
+//!configuration against it, reporting every violation at once.
+//!See the [`Schema`] documentation for more details.
+use crate::ini::{Annotation, Ini, WriteOptions};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+///The type of the value of a key, checked by `Ini::validate()`.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ValueType {
+    ///Any value.
+    String,
+    ///A boolean, accepting the same values as `Ini::getboolcoerce()`.
+    Bool,
+    ///A signed integer within the optional inclusive bounds.
+    Int { min: Option<i64>, max: Option<i64> },
+    ///An unsigned integer within the optional inclusive bounds.
+    Uint { min: Option<u64>, max: Option<u64> },
+    ///A floating point number within the optional inclusive bounds.
+    Float { min: Option<f64>, max: Option<f64> },
+    ///One of the given values, compared case-sensitively.
+    Enum(Vec<String>),
+    ///A value matching a simple pattern, where `*` matches any sequence of characters, `?` matches any single
+    ///character, `#` matches a single ASCII digit and every other character matches itself.
+    Pattern(String),
+    ///A list of values separated by `separator`, where every item (trimmed of surrounding whitespace) must be of
+    ///the `item` type. Empty items are ignored.
+    List {
+        item: Box<ValueType>,
+        separator: char,
+    },
+    ///A duration in the format accepted by `parse_duration()`, such as `90s` or `1h30m`.
+    Duration,
+}
+
+impl ValueType {
+    ///Returns a `ValueType::Int` with the given inclusive bounds.
+    pub fn int_range(min: i64, max: i64) -> ValueType {
+        ValueType::Int {
+            min: Some(min),
+            max: Some(max),
+        }
+    }
+
+    ///Returns a `ValueType::Uint` with the given inclusive bounds.
+    pub fn uint_range(min: u64, max: u64) -> ValueType {
+        ValueType::Uint {
+            min: Some(min),
+            max: Some(max),
+        }
+    }
+
+    ///Returns a `ValueType::Enum` of the given values.
+    pub fn one_of<S: AsRef<str>>(values: impl IntoIterator<Item = S>) -> ValueType {
+        ValueType::Enum(values.into_iter().map(|v| v.as_ref().to_owned()).collect())
+    }
+
+    ///Returns a `ValueType::List` of items of the given type, separated by `separator`.
+    pub fn list_of(item: ValueType, separator: char) -> ValueType {
+        ValueType::List {
+            item: Box::new(item),
+            separator,
+        }
+    }
+
+    ///Private function that checks a value, returning why it doesn't match.
+    fn check(
+        &self,
+        value: &str,
+        boolean_values: &HashMap<bool, Vec<String>>,
+    ) -> Result<(), String> {
+        fn bounds<T: PartialOrd + fmt::Display>(
+            value: T,
+            min: Option<T>,
+            max: Option<T>,
+        ) -> Result<(), String> {
+            match (min, max) {
+                (Some(min), _) if value < min => {
+                    Err(format!("{} is below the minimum {}", value, min))
+                }
+                (_, Some(max)) if value > max => {
+                    Err(format!("{} is above the maximum {}", value, max))
+                }
+                _ => Ok(()),
+            }
+        }
+
+        match self {
+            ValueType::String => Ok(()),
+            ValueType::Bool => {
+                let lowercase = value.to_lowercase();
+                if boolean_values
+                    .values()
+                    .any(|values| values.contains(&lowercase))
+                {
+                    Ok(())
+                } else {
+                    Err(format!("expected a boolean, found {:?}", value))
+                }
+            }
+            ValueType::Int { min, max } => match value.parse::<i64>() {
+                Ok(value) => bounds(value, *min, *max),
+                Err(_) => Err(format!("expected an integer, found {:?}", value)),
+            },
+            ValueType::Uint { min, max } => match value.parse::<u64>() {
+                Ok(value) => bounds(value, *min, *max),
+                Err(_) => Err(format!("expected an unsigned integer, found {:?}", value)),
+            },
+            ValueType::Float { min, max } => match value.parse::<f64>() {
+                Ok(value) => bounds(value, *min, *max),
+                Err(_) => Err(format!("expected a number, found {:?}", value)),
+            },
+            ValueType::Enum(values) => {
+                if values.iter().any(|v| v == value) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "expected one of {}, found {:?}",
+                        values.join(", "),
+                        value
+                    ))
+                }
+            }
+            ValueType::Pattern(pattern) => {
+                let pattern: Vec<char> = pattern.chars().collect();
+                let value: Vec<char> = value.chars().collect();
+                if matches(&pattern, &value) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "expected a value like {:?}, found {:?}",
+                        pattern.iter().collect::<String>(),
+                        value.iter().collect::<String>()
+                    ))
+                }
+            }
+            ValueType::List { item, separator } => value
+                .split(*separator)
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .try_for_each(|value| item.check(value, boolean_values)),
+            ValueType::Duration => parse_duration(value).map(|_| ()),
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    ///Renders the type for humans, e.g. `integer (1..=65535)` or `list of string separated by ','`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn range<T: fmt::Display>(
+            f: &mut fmt::Formatter<'_>,
+            name: &str,
+            min: &Option<T>,
+            max: &Option<T>,
+        ) -> fmt::Result {
+            match (min, max) {
+                (None, None) => write!(f, "{}", name),
+                (Some(min), None) => write!(f, "{} (>= {})", name, min),
+                (None, Some(max)) => write!(f, "{} (<= {})", name, max),
+                (Some(min), Some(max)) => write!(f, "{} ({}..={})", name, min, max),
+            }
+        }
+
+        match self {
+            ValueType::String => write!(f, "string"),
+            ValueType::Bool => write!(f, "boolean"),
+            ValueType::Int { min, max } => range(f, "integer", min, max),
+            ValueType::Uint { min, max } => range(f, "unsigned integer", min, max),
+            ValueType::Float { min, max } => range(f, "number", min, max),
+            ValueType::Enum(values) => write!(f, "one of {}", values.join(", ")),
+            ValueType::Pattern(pattern) => write!(f, "pattern {}", pattern),
+            ValueType::List { item, separator } => {
+                write!(f, "list of {} separated by {:?}", item, separator)
+            }
+            ValueType::Duration => write!(f, "duration"),
+        }
+    }
+}
+
+///Private function that matches a value against a `ValueType::Pattern`. Every `*` is matched greedily and, on a
+///mismatch, only the most recent `*` takes one more character, which never needs more than quadratic time.
+fn matches(pattern: &[char], value: &[char]) -> bool {
+    let matches_char = |p: char, v: char| match p {
+        '?' => true,
+        '#' => v.is_ascii_digit(),
+        p => p == v,
+    };
+    let (mut p, mut v) = (0, 0);
+    // The pattern position after the most recent `*` and the value position it was matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                star = Some((p, v));
+            }
+            Some(&c) if matches_char(c, value[v]) => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                Some((after, matched)) => {
+                    p = after;
+                    v = matched + 1;
+                    star = Some((after, v));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+///Parses a duration such as `90s`, `1h30m` or `250ms`: one or more numbers, each followed by a unit out of `ms`,
+///`s`, `m`, `h` and `d`. A single number without a unit is a number of seconds.
+///## Example
+///```rust
+///use configparser::schema::parse_duration;
+///use std::time::Duration;
+///
+///assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+///assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+///assert_eq!(parse_duration("15"), Ok(Duration::from_secs(15)));
+///assert!(parse_duration("soon").is_err());
+///```
+///Returns `Ok(duration)` if the value is a valid duration or else `Err(error_string)`.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected a duration, found {:?}", value);
+    let trimmed = value.trim();
+    if let Ok(seconds) = trimmed.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let number: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let seconds = |factor: u64| number.checked_mul(factor).map(Duration::from_secs);
+        let part = match &rest[..unit] {
+            "ms" => Some(Duration::from_millis(number)),
+            "s" => seconds(1),
+            "m" => seconds(60),
+            "h" => seconds(60 * 60),
+            "d" => seconds(60 * 60 * 24),
+            _ => return Err(invalid()),
+        };
+        // Durations that don't fit are invalid rather than wrapping around.
+        total = part
+            .and_then(|part| total.checked_add(part))
+            .ok_or_else(invalid)?;
+        rest = &rest[unit..];
+    }
+    if trimmed.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+///The description of a single key in a `SectionSchema`.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct KeySchema {
+    pub name: String,
+    pub value_type: ValueType,
+    ///Whether a missing key is a violation. Keys with a default are never required.
+    pub required: bool,
+    ///The value a missing key falls back to, see `Schema::apply_defaults()`.
+    pub default: Option<String>,
+    ///A human-readable description of the key.
+    pub description: Option<String>,
+}
+
+impl KeySchema {
+    ///Creates a new optional key of the given type.
+    pub fn new(name: &str, value_type: ValueType) -> KeySchema {
+        KeySchema {
+            name: name.to_owned(),
+            value_type,
+            required: false,
+            default: None,
+            description: None,
+        }
+    }
+
+    ///Marks the key as required.
+    pub fn required(mut self) -> KeySchema {
+        self.required = true;
+        self
+    }
+
+    ///Sets the default value of the key.
+    pub fn default_value(mut self, default: &str) -> KeySchema {
+        self.default = Some(default.to_owned());
+        self
+    }
+
+    ///Sets the description of the key.
+    pub fn description(mut self, description: &str) -> KeySchema {
+        self.description = Some(description.to_owned());
+        self
+    }
+}
+
+///The description of a single section in a `Schema`.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SectionSchema {
+    pub name: String,
+    pub keys: Vec<KeySchema>,
+    ///Whether a missing section is a violation. Missing optional sections are not checked any further.
+    pub required: bool,
+    ///Whether keys that the schema doesn't describe are allowed. Default is `false`.
+    pub allow_unknown_keys: bool,
+    ///A human-readable description of the section.
+    pub description: Option<String>,
+}
+
+impl SectionSchema {
+    ///Creates a new optional section without keys.
+    pub fn new(name: &str) -> SectionSchema {
+        SectionSchema {
+            name: name.to_owned(),
+            keys: Vec::new(),
+            required: false,
+            allow_unknown_keys: false,
+            description: None,
+        }
+    }
+
+    ///Adds a key to the section.
+    pub fn key(mut self, key: KeySchema) -> SectionSchema {
+        self.keys.push(key);
+        self
+    }
+
+    ///Marks the section as required.
+    pub fn required(mut self) -> SectionSchema {
+        self.required = true;
+        self
+    }
+
+    ///Allows keys that the schema doesn't describe.
+    pub fn allow_unknown_keys(mut self) -> SectionSchema {
+        self.allow_unknown_keys = true;
+        self
+    }
+
+    ///Sets the description of the section.
+    pub fn description(mut self, description: &str) -> SectionSchema {
+        self.description = Some(description.to_owned());
+        self
+    }
+}
+
+///The `Schema` struct describes the sections and keys of a configuration, see `Ini::validate()`.
+///Section and key names are matched with the case handling of the validated `Ini`.
+///## Example
+///```rust
+///use configparser::ini::Ini;
+///use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+///
+///let schema = Schema::new()
+///    .section(
+///        SectionSchema::new("server")
+///            .required()
+///            .key(KeySchema::new("port", ValueType::uint_range(1, 65535)).required())
+///            .key(KeySchema::new("mode", ValueType::one_of(["dev", "prod"])).default_value("prod")),
+///    );
+///
+///let mut config = Ini::new();
+///config.read(String::from("[server]\nport = 8080\nmode = dev")).unwrap();
+///assert_eq!(config.validate(&schema), Ok(()));
+///
+///config.read(String::from("[server]\nport = 0\nmode = staging")).unwrap();
+///let violations = config.validate(&schema).unwrap_err();
+///assert_eq!(violations.len(), 2);
+///assert_eq!(violations[0].to_string(), "line 2:8: server:port: 0 is below the minimum 1");
+///```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct Schema {
+    pub sections: Vec<SectionSchema>,
+    ///Whether sections that the schema doesn't describe are allowed. Default is `false`.
+    pub allow_unknown_sections: bool,
+}
+
+impl Schema {
+    ///Creates a new empty schema.
+    pub fn new() -> Schema {
+        Schema::default()
+    }
+
+    ///Adds a section to the schema.
+    pub fn section(mut self, section: SectionSchema) -> Schema {
+        self.sections.push(section);
+        self
+    }
+
+    ///Allows sections that the schema doesn't describe.
+    pub fn allow_unknown_sections(mut self) -> Schema {
+        self.allow_unknown_sections = true;
+        self
+    }
+
+    ///Sets every missing key that has a default value to its default, creating sections as needed.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+    ///
+    ///let schema = Schema::new().section(
+    ///    SectionSchema::new("server").key(KeySchema::new("port", ValueType::Uint { min: None, max: None }).default_value("80")),
+    ///);
+    ///let mut config = Ini::new();
+    ///schema.apply_defaults(&mut config);
+    ///assert_eq!(config.getuint("server", "port").unwrap(), Some(80));
+    ///```
+    pub fn apply_defaults(&self, config: &mut Ini) {
+        for section in self.sections.iter() {
+            for key in section.keys.iter() {
+                if let Some(default) = key.default.as_deref() {
+                    config
+                        .entry(&section.name, &key.name)
+                        .or_insert(Some(default.to_owned()));
+                }
+            }
+        }
+    }
+
+    ///Generates a documented sample configuration that lists every section and key of the schema, written with
+    ///`write_options` like `Ini::pretty_writes()`. Every section is preceded by its description and every key by
+    ///its description, its type and whether it is required. Keys with a default are set to it, unless
+    ///`comment_out_defaults` is `true`; keys without a default are always commented out with an empty value.
+    ///Sections and keys appear in schema order.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::{Ini, WriteOptions};
+    ///use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+    ///
+    ///let schema = Schema::new().section(
+    ///    SectionSchema::new("server")
+    ///        .description("The HTTP listener.")
+    ///        .key(KeySchema::new("port", ValueType::uint_range(1, 65535)).default_value("80").description("Port to listen on.")),
+    ///);
+    ///let sample = schema.sample(&WriteOptions::new_with_params(true, 4, 1), false);
+    ///assert_eq!(
+    ///    sample,
+    ///    "; The HTTP listener.\n[server]\n; Port to listen on.\n; Type: unsigned integer (1..=65535)\nport = 80\n"
+    ///        .replace('\n', if cfg!(windows) { "\r\n" } else { "\n" })
+    ///);
+    ///
+    ///let mut config = Ini::new();
+    ///config.read(sample).unwrap();
+    ///assert_eq!(config.validate(&schema), Ok(()));
+    ///```
+    ///Returns a `String` type containing the sample ini-syntax file.
+    pub fn sample(&self, write_options: &WriteOptions, comment_out_defaults: bool) -> String {
+        let mut config = Ini::new_cs();
+        for section in self.sections.iter() {
+            config
+                .get_mut_map()
+                .entry(section.name.clone())
+                .or_default();
+            for key in section.keys.iter() {
+                let value = key.default.clone().unwrap_or_default();
+                config.set(&section.name, &key.name, Some(value));
+            }
+        }
+        let order: Vec<(String, Vec<String>)> = self
+            .sections
+            .iter()
+            .map(|section| {
+                let keys = section.keys.iter().map(|key| key.name.clone()).collect();
+                (section.name.clone(), keys)
+            })
+            .collect();
+        config.unparse_annotated(write_options, Some(&order), &|section, key| {
+            let Some(section) = self.sections.iter().find(|s| s.name == section) else {
+                return Annotation::default();
+            };
+            let Some(key) = key else {
+                return Annotation {
+                    comments: comment_lines(section.description.iter().cloned()),
+                    ..Annotation::default()
+                };
+            };
+            let Some(key) = section.keys.iter().find(|k| k.name == key) else {
+                return Annotation::default();
+            };
+            let mut comments: Vec<String> = key.description.iter().cloned().collect();
+            if key.required && key.default.is_none() {
+                comments.push(format!("Type: {} (required)", key.value_type));
+            } else {
+                comments.push(format!("Type: {}", key.value_type));
+            }
+            Annotation {
+                comments: comment_lines(comments),
+                inline_comment: None,
+                commented_out: key.default.is_none() || comment_out_defaults,
+            }
+        })
+    }
+
+    ///Writes the sample configuration generated by `sample()` to a file.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not.
+    pub fn write_sample<T: AsRef<Path>>(
+        &self,
+        path: T,
+        write_options: &WriteOptions,
+        comment_out_defaults: bool,
+    ) -> std::io::Result<()> {
+        fs::write(
+            path.as_ref(),
+            self.sample(write_options, comment_out_defaults),
+        )
+    }
+}
+
+///Private function that turns descriptions into `;` comment lines, one per line of text.
+fn comment_lines(texts: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for text in texts {
+        for line in text.lines() {
+            if line.is_empty() {
+                lines.push(String::from(";"));
+            } else {
+                lines.push(format!("; {}", line));
+            }
+        }
+    }
+    lines
+}
+
+///What is wrong in a `Violation`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ViolationKind {
+    MissingSection,
+    MissingKey,
+    UnknownSection,
+    UnknownKey,
+    ///The value doesn't match the type of the key, with the reason why.
+    InvalidValue(String),
+}
+
+///A single violation of a schema found by `Ini::validate()`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Violation {
+    pub section: String,
+    pub key: Option<String>,
+    pub kind: ViolationKind,
+    ///The 1-based line of the offending value, key or section header, if known.
+    pub line: Option<usize>,
+    ///The 1-based column (in characters) of the offending value, key or section header, if known.
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for Violation {
+    ///Renders the violation on a single line, e.g. `line 3:8: server:port: 0 is below the minimum 1`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "line {}:{}: ", line, column)?,
+            (Some(line), None) => write!(f, "line {}: ", line)?,
+            _ => {}
+        }
+        match &self.key {
+            Some(key) => write!(f, "{}:{}: ", self.section, key)?,
+            None => write!(f, "{}: ", self.section)?,
+        }
+        match &self.kind {
+            ViolationKind::MissingSection => write!(f, "missing required section"),
+            ViolationKind::MissingKey => write!(f, "missing required key"),
+            ViolationKind::UnknownSection => write!(f, "unknown section"),
+            ViolationKind::UnknownKey => write!(f, "unknown key"),
+            ViolationKind::InvalidValue(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl Ini {
+    ///Checks the stored configuration against a schema. Keys are looked up like `get()`, so with
+    ///`cascade_defaults` enabled a key in the default section satisfies every section. A key without a value
+    ///counts as missing.
+    ///
+    ///Violations are listed in the order of the schema, followed by unknown sections and keys. Their positions are
+    ///taken from the spans recorded when the configuration was parsed (see `span_of()`): invalid values point to
+    ///the value, unknown keys to the key and missing keys and unknown sections to the section header. Violations
+    ///without a parsed source, such as values added with `set()`, have no position.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType, ViolationKind};
+    ///
+    ///let schema = Schema::new().section(
+    ///    SectionSchema::new("log").key(KeySchema::new("level", ValueType::String).required()),
+    ///);
+    ///let mut config = Ini::new();
+    ///config.read(String::from("[log]\nfile = app.log\n[extra]")).unwrap();
+    ///let violations = config.validate(&schema).unwrap_err();
+    ///assert_eq!(violations[0].kind, ViolationKind::MissingKey);
+    ///assert!(violations.iter().any(|v| v.kind == ViolationKind::UnknownKey));
+    ///assert!(violations.iter().any(|v| v.kind == ViolationKind::UnknownSection));
+    ///```
+    ///Returns `Ok(())` if the configuration matches the schema or else `Err(violations)` with every violation.
+    pub fn validate(&self, schema: &Schema) -> Result<(), Vec<Violation>> {
+        let boolean_values = self.defaults().boolean_values;
+        let mut violations = Vec::new();
+        let mut violation = |section: &str, key: Option<&str>, kind| {
+            violations.push(Violation {
+                section: section.to_owned(),
+                key: key.map(str::to_owned),
+                kind,
+                line: None,
+                column: None,
+            })
+        };
+        let map = self.get_map_ref();
+        let default_section = self.default_section_name();
+
+        for section in schema.sections.iter() {
+            let name = self.section_name(&section.name);
+            if !map.contains_key(&name) && name != default_section {
+                if section.required {
+                    violation(&section.name, None, ViolationKind::MissingSection);
+                }
+                continue;
+            }
+            for key in section.keys.iter() {
+                match self.get(&section.name, &key.name) {
+                    None if key.required && key.default.is_none() => {
+                        violation(&section.name, Some(&key.name), ViolationKind::MissingKey);
+                    }
+                    None => {}
+                    Some(value) => {
+                        if let Err(reason) = key.value_type.check(&value, &boolean_values) {
+                            violation(
+                                &section.name,
+                                Some(&key.name),
+                                ViolationKind::InvalidValue(reason),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        for (name, secmap) in map.iter() {
+            let section = schema
+                .sections
+                .iter()
+                .find(|section| self.section_name(&section.name) == *name);
+            match section {
+                None if !schema.allow_unknown_sections => {
+                    violation(name, None, ViolationKind::UnknownSection);
+                }
+                Some(section) if !section.allow_unknown_keys => {
+                    for key in secmap.keys() {
+                        if !section
+                            .keys
+                            .iter()
+                            .any(|k| self.key_name(name, &k.name) == *key)
+                        {
+                            violation(name, Some(key), ViolationKind::UnknownKey);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            for violation in violations.iter_mut() {
+                self.locate(violation);
+            }
+            Err(violations)
+        }
+    }
+
+    ///Private function that fills in the position of a violation from the recorded spans.
+    pub(crate) fn locate(&self, violation: &mut Violation) {
+        let section = &violation.section;
+        let span = match (&violation.kind, &violation.key) {
+            // Values missing from their section were cascaded from the default section.
+            (ViolationKind::InvalidValue(_), Some(key)) => self
+                .span_of(section, key)
+                .or_else(|| self.span_of(&self.default_section_name(), key))
+                .map(|found| found.value.unwrap_or(found.key)),
+            (ViolationKind::UnknownKey, Some(key)) => {
+                self.span_of(section, key).map(|found| found.key)
+            }
+            _ => self.section_span(section),
+        };
+        violation.line = span.map(|span| span.line);
+        violation.column = span.map(|span| span.column);
+    }
+
+    ///Checks the stored configuration against a schema like `validate()`, taking the positions of the violations
+    ///from `source`, the text the configuration was read from, instead of the recorded spans. This is useful when
+    ///the configuration was changed or merged after parsing. Violations of keys that appear several times point to
+    ///the last appearance, since that is the value that was stored. If `source` can't be parsed, the violations
+    ///have no position.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+    ///
+    ///let schema = Schema::new().section(
+    ///    SectionSchema::new("server").key(KeySchema::new("port", ValueType::Uint { min: None, max: None })),
+    ///);
+    ///let source = "[server]\nport = eighty";
+    ///let mut config = Ini::new();
+    ///config.read(source.to_owned()).unwrap();
+    ///let violations = config.validate_source(&schema, source).unwrap_err();
+    ///assert_eq!(violations[0].to_string(), r#"line 2:8: server:port: expected an unsigned integer, found "eighty""#);
+    ///```
+    ///Returns `Ok(())` if the configuration matches the schema or else `Err(violations)` with every violation.
+    pub fn validate_source(&self, schema: &Schema, source: &str) -> Result<(), Vec<Violation>> {
+        self.validate(schema).map_err(|mut violations| {
+            let mut parsed = Ini::new_from_defaults(self.defaults());
+            let parsed = parsed.read_ref(source.to_owned()).ok();
+            for violation in violations.iter_mut() {
+                match parsed {
+                    Some(parsed) => parsed.locate(violation),
+                    None => (violation.line, violation.column) = (None, None),
+                }
+            }
+            violations
+        })
+    }
+}
@@ -0,0 +1,514 @@
+//!The systemd module provides reading and writing of systemd unit files and their drop-ins with the semantics of
+//!systemd itself: case-sensitive names, repeated keys that build lists, empty assignments that reset them, backslash
+//!line continuations and drop-in directories merged in a defined order.
+//!See the [`Unit`] documentation for more details.
+use crate::dialect::Dialect;
+use crate::ini::{Ini, LINE_ENDING};
+use crate::parser::{EventKind, Lexer, StrEvents};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+///The `Unit` struct holds a systemd unit file together with the drop-ins applied to it.
+///
+///Every assignment of a key is kept: the values of a key are stored in its `Ini` value, one per line, and an empty
+///assignment like `ExecStart=` drops the values before it, which is how drop-ins clear the lists of the unit they
+///extend. A reset is remembered as a leading empty line, so writing the unit back reproduces it. Values are kept as
+///written, including specifiers like `%i` and backslash escapes; `expand_specifiers()` resolves the specifiers on
+///demand.
+///
+///A line ending in a backslash continues on the next line, the backslash being replaced by a space, and comments
+///inside such a continuation are skipped. Keys must be inside a section and must have a `=`.
+///## Example
+///```rust
+///use configparser::systemd::Unit;
+///
+///let mut unit = Unit::new();
+///unit.read(String::from(
+///    "[Service]\nExecStart=/usr/bin/app \\\n    --config %h/app.ini\nEnvironment=A=1\nEnvironment=B=2\n",
+///)).unwrap();
+///// A drop-in replacing the command and extending the environment.
+///unit.read(String::from("[Service]\nExecStart=\nExecStart=/usr/bin/app --debug\nEnvironment=C=3\n")).unwrap();
+///assert_eq!(unit.get_all("Service", "ExecStart"), ["/usr/bin/app --debug"]);
+///assert_eq!(unit.get_all("Service", "Environment"), ["A=1", "B=2", "C=3"]);
+///assert_eq!(unit.get("Service", "execstart"), None);
+///```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Unit {
+    config: Ini,
+}
+
+impl Default for Unit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Unit {
+    ///Creates an empty unit.
+    ///Returns the struct and stores it in the calling variable.
+    pub fn new() -> Unit {
+        Unit {
+            config: Ini::with_dialect(Dialect::Systemd),
+        }
+    }
+
+    ///Loads a unit file and its drop-ins. Drop-ins are the `*.conf` files of the directory named after the unit file
+    ///with `.d` appended, of the `foo@.service.d` directory of the template of an instance like `foo@bar.service`
+    ///and of the directories of its dash-separated prefixes like `foo-.service.d` and its type like `service.d`. If
+    ///several of these directories contain a file with the same name, the one of the most specific directory is
+    ///used. Drop-ins are applied in the order of their file names.
+    ///## Example
+    ///```rust
+    ///use configparser::systemd::Unit;
+    ///use std::fs;
+    ///
+    ///let dir = std::env::temp_dir().join("configparser-systemd-doctest");
+    ///fs::create_dir_all(dir.join("app.service.d")).unwrap();
+    ///fs::write(dir.join("app.service"), "[Service]\nUser=app\nNice=0\n").unwrap();
+    ///fs::write(dir.join("app.service.d/20-nice.conf"), "[Service]\nNice=10\n").unwrap();
+    ///fs::write(dir.join("app.service.d/10-nice.conf"), "[Service]\nNice=5\n").unwrap();
+    ///let unit = Unit::load(dir.join("app.service")).unwrap();
+    ///assert_eq!(unit.get("Service", "Nice"), Some("10"));
+    ///assert_eq!(unit.get("Service", "User"), Some("app"));
+    ///```
+    ///Returns `Ok(unit)` or an `Err(error_string)` if the unit file or a drop-in can't be read or parsed.
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Unit, String> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("couldn't read {}: not a unit file name", path.display()))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut unit = Unit::new();
+        unit.load_file(path)?;
+        unit.load_dropins(&[dir], name)?;
+        Ok(unit)
+    }
+
+    ///Loads a unit by name from a search path like `/etc/systemd/system`, `/run/systemd/system` and
+    ///`/usr/lib/systemd/system`, in order of decreasing priority. The unit file is the one of the first directory
+    ///containing it, falling back to the template `foo@.service` for an instance like `foo@bar.service`. Drop-ins are
+    ///collected from all directories as described for `load()`, a drop-in of a directory earlier in the search path
+    ///overriding one with the same name of a later directory; a drop-in linked to `/dev/null` masks them.
+    ///## Example
+    ///```rust
+    ///use configparser::systemd::Unit;
+    ///use std::fs;
+    ///
+    ///let root = std::env::temp_dir().join("configparser-systemd-dirs-doctest");
+    ///let (etc, lib) = (root.join("etc"), root.join("lib"));
+    ///fs::create_dir_all(etc.join("getty@tty1.service.d")).unwrap();
+    ///fs::create_dir_all(lib.join("getty@.service.d")).unwrap();
+    ///fs::write(lib.join("getty@.service"), "[Service]\nExecStart=/sbin/agetty %I\nTTYPath=/dev/%I\n").unwrap();
+    ///fs::write(lib.join("getty@.service.d/10-vc.conf"), "[Service]\nTTYVTDisallocate=yes\n").unwrap();
+    ///fs::write(etc.join("getty@tty1.service.d/10-vc.conf"), "[Service]\nTTYVTDisallocate=no\n").unwrap();
+    ///let unit = Unit::load_from_dirs("getty@tty1.service", &[etc, lib]).unwrap();
+    ///assert_eq!(unit.get("Service", "TTYVTDisallocate"), Some("no"));
+    ///assert_eq!(unit.get("Service", "TTYPath"), Some("/dev/%I"));
+    ///```
+    ///Returns `Ok(unit)` or an `Err(error_string)` if the unit can't be found or a file can't be read or parsed.
+    pub fn load_from_dirs<P: AsRef<Path>>(name: &str, dirs: &[P]) -> Result<Unit, String> {
+        let dirs: Vec<&Path> = dirs.iter().map(|dir| dir.as_ref()).collect();
+        let names = UnitName::parse(name);
+        let candidates = std::iter::once(name.to_owned()).chain(names.template());
+        let path = candidates
+            .flat_map(|candidate| dirs.iter().map(move |dir| dir.join(&candidate)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| format!("couldn't find unit {}", name))?;
+        let mut unit = Unit::new();
+        unit.load_file(&path)?;
+        unit.load_dropins(&dirs, name)?;
+        Ok(unit)
+    }
+
+    ///Private function that reads one file into the unit.
+    fn load_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = fs::read_to_string(path)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+        self.read(text)
+            .map_err(|why| format!("couldn't read {}: {}", path.display(), why))
+    }
+
+    ///Private function that applies the drop-ins of a unit found in the given directories.
+    fn load_dropins(&mut self, dirs: &[&Path], name: &str) -> Result<(), String> {
+        let dropin_dirs = UnitName::parse(name).dropin_dirs();
+        let mut dropins: HashMap<String, PathBuf> = HashMap::new();
+        for dir in dirs {
+            for dropin_dir in &dropin_dirs {
+                let dropin_dir = dir.join(dropin_dir);
+                let entries = match fs::read_dir(&dropin_dir) {
+                    Ok(entries) => entries,
+                    Err(why) if why.kind() == ErrorKind::NotFound => continue,
+                    Err(why) => {
+                        return Err(format!("couldn't read {}: {}", dropin_dir.display(), why));
+                    }
+                };
+                for entry in entries {
+                    let path = entry
+                        .map_err(|why| format!("couldn't read {}: {}", dropin_dir.display(), why))?
+                        .path();
+                    let file_name = path.file_name().and_then(|name| name.to_str());
+                    if let Some(file_name) = file_name.filter(|name| name.ends_with(".conf")) {
+                        dropins.entry(file_name.to_owned()).or_insert(path);
+                    }
+                }
+            }
+        }
+        let mut dropins: Vec<(String, PathBuf)> = dropins.into_iter().collect();
+        dropins.sort();
+        for (_, path) in dropins {
+            // Masked drop-ins are links to /dev/null, which reads as an empty file.
+            self.load_file(&path)?;
+        }
+        Ok(())
+    }
+
+    ///Applies unit file text to the unit, as systemd does for the unit file and then for each of its drop-ins.
+    ///Returns `Ok(())` or an `Err(error_string)` if the text can't be parsed, in which case the unit is unchanged.
+    pub fn read(&mut self, input: String) -> Result<(), String> {
+        let joined = join_continuations(&input);
+        let lexer = Lexer::from_defaults(&self.config.defaults());
+        let mut config = self.config.clone();
+        let mut section: Option<String> = None;
+        for event in StrEvents::new(&joined, lexer) {
+            let event = event?;
+            match event.kind {
+                EventKind::SectionStart { name } => section = Some(name.into_owned()),
+                EventKind::KeyValue { key, value, .. } => {
+                    let section = section.as_deref().ok_or_else(|| {
+                        format!(
+                            "line {}:{}: Assignment outside of section",
+                            event.line, event.column
+                        )
+                    })?;
+                    let value = value.ok_or_else(|| {
+                        format!(
+                            "line {}:{}: Missing '=' after key {}",
+                            event.line, event.column, key
+                        )
+                    })?;
+                    let values = config
+                        .get_mut_map()
+                        .entry(section.to_owned())
+                        .or_default()
+                        .entry(key.into_owned())
+                        .or_insert(None);
+                    push_value(values, &value);
+                }
+                _ => {}
+            }
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    ///Returns the last value of a key, which is the one that counts for settings that aren't lists, or `None` if the
+    ///key is missing or was reset.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.get_all(section, key).pop()
+    }
+
+    ///Returns all values of a key in the order of their assignments, leaving out the values before the last reset.
+    ///## Example
+    ///```rust
+    ///use configparser::systemd::Unit;
+    ///
+    ///let mut unit = Unit::new();
+    ///unit.read(String::from("[Unit]\nAfter=a.target\nAfter=\nAfter=b.target c.target")).unwrap();
+    ///assert_eq!(unit.get_all("Unit", "After"), ["b.target c.target"]);
+    ///assert!(unit.get_all("Unit", "Wants").is_empty());
+    ///```
+    pub fn get_all(&self, section: &str, key: &str) -> Vec<&str> {
+        self.config
+            .get_map_ref()
+            .get(section)
+            .and_then(|keys| keys.get(key))
+            .and_then(|values| values.as_deref())
+            .map(|values| values.lines().filter(|value| !value.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    ///Sets a key to a single value, replacing all its assignments.
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        self.values(section, key).take();
+        self.add(section, key, value);
+    }
+
+    ///Adds an assignment of a key after the existing ones, which adds a value to list settings.
+    ///An empty value resets the key, see `reset()`.
+    pub fn add(&mut self, section: &str, key: &str, value: &str) {
+        push_value(self.values(section, key), value);
+    }
+
+    ///Resets a key with an empty assignment like `ExecStart=`, dropping its values. Unlike `remove()`, the reset is
+    ///written back, which is how a drop-in clears a list set by the unit it extends.
+    ///## Example
+    ///```rust
+    ///use configparser::systemd::Unit;
+    ///
+    ///let mut dropin = Unit::new();
+    ///dropin.reset("Service", "ExecStart");
+    ///dropin.add("Service", "ExecStart", "/usr/bin/app --debug");
+    ///assert_eq!(dropin.writes(), "[Service]\nExecStart=\nExecStart=/usr/bin/app --debug\n");
+    ///```
+    pub fn reset(&mut self, section: &str, key: &str) {
+        push_value(self.values(section, key), "");
+    }
+
+    ///Removes a key with all its assignments.
+    ///Returns `true` if the key was set.
+    pub fn remove(&mut self, section: &str, key: &str) -> bool {
+        self.config.remove_key(section, key).is_some()
+    }
+
+    ///Private function that returns the stored values of a key, adding the key if it's missing.
+    fn values(&mut self, section: &str, key: &str) -> &mut Option<String> {
+        self.config
+            .get_mut_map()
+            .entry(section.to_owned())
+            .or_default()
+            .entry(key.to_owned())
+            .or_insert(None)
+    }
+
+    ///Returns the names of all sections.
+    pub fn sections(&self) -> Vec<String> {
+        self.config.sections()
+    }
+
+    ///Returns the underlying `Ini`, which stores the values of each key one per line.
+    pub fn as_ini(&self) -> &Ini {
+        &self.config
+    }
+
+    ///Consumes the unit and returns the underlying `Ini`, see `as_ini()`.
+    pub fn into_ini(self) -> Ini {
+        self.config
+    }
+
+    ///Writes the unit in unit file syntax, with one assignment per value and an empty assignment for a reset.
+    ///Values are written as stored, so specifiers and escapes are kept.
+    ///Returns a `String` with the unit file text.
+    pub fn writes(&self) -> String {
+        let mut out = String::new();
+        for (section, keys) in self.config.get_map_ref() {
+            if !out.is_empty() {
+                out.push_str(LINE_ENDING);
+            }
+            out.push('[');
+            out.push_str(section);
+            out.push(']');
+            out.push_str(LINE_ENDING);
+            for (key, values) in keys {
+                for value in values.iter().flat_map(|values| values.split(LINE_ENDING)) {
+                    out.push_str(key);
+                    out.push('=');
+                    out.push_str(value);
+                    out.push_str(LINE_ENDING);
+                }
+            }
+        }
+        out
+    }
+
+    ///Writes the unit to a file, see `writes()`.
+    ///Returns a `std::io::Result<()>` type dependent on whether the write was successful or not.
+    pub fn write<T: AsRef<Path>>(&self, path: T) -> std::io::Result<()> {
+        fs::write(path.as_ref(), self.writes())
+    }
+}
+
+///Private function that adds an assignment to the stored values of a key. An empty value resets the key, which is
+///stored as a leading empty line.
+fn push_value(values: &mut Option<String>, value: &str) {
+    match values {
+        Some(values) if !value.is_empty() => {
+            values.push_str(LINE_ENDING);
+            values.push_str(value);
+        }
+        _ if value.is_empty() => *values = Some(String::new()),
+        _ => *values = Some(value.to_owned()),
+    }
+}
+
+///Private function that joins lines ending in an odd number of backslashes with the next line, replacing the
+///backslash with a space and skipping comment lines inside the continuation. Joined lines are followed by empty
+///lines, so that line numbers stay the same.
+fn join_continuations(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut joined = 0;
+    let mut continued = false;
+    for line in input.lines() {
+        let trimmed = line.trim_start();
+        if continued && (trimmed.starts_with('#') || trimmed.starts_with(';')) {
+            joined += 1;
+            continue;
+        }
+        let line = line.trim_end();
+        let backslashes = line.len() - line.trim_end_matches('\\').len();
+        continued = backslashes % 2 == 1;
+        if continued {
+            out.push_str(&line[..line.len() - 1]);
+            out.push(' ');
+            joined += 1;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            for _ in 0..joined {
+                out.push('\n');
+            }
+            joined = 0;
+        }
+    }
+    out
+}
+
+///Private struct holding the parts of a unit name like `foo-bar@baz.service`.
+struct UnitName<'a> {
+    ///The name without the type, like `foo-bar@baz`.
+    stem: &'a str,
+    ///The type, like `service`.
+    kind: &'a str,
+    ///The prefix, like `foo-bar`.
+    prefix: &'a str,
+    ///The instance, like `baz`, if the unit is an instance of a template.
+    instance: Option<&'a str>,
+}
+
+impl<'a> UnitName<'a> {
+    fn parse(name: &'a str) -> UnitName<'a> {
+        let (stem, kind) = name.rsplit_once('.').unwrap_or((name, ""));
+        let (prefix, instance) = match stem.split_once('@') {
+            Some((prefix, instance)) => (prefix, Some(instance)),
+            None => (stem, None),
+        };
+        UnitName {
+            stem,
+            kind,
+            prefix,
+            instance,
+        }
+    }
+
+    ///Returns the name of the template of an instance, like `foo-bar@.service`.
+    fn template(&self) -> Option<String> {
+        self.instance
+            .filter(|instance| !instance.is_empty())
+            .map(|_| format!("{}@.{}", self.prefix, self.kind))
+    }
+
+    ///Returns the drop-in directories of the unit from the most to the least specific.
+    fn dropin_dirs(&self) -> Vec<String> {
+        let mut dirs = vec![format!("{}.{}.d", self.stem, self.kind)];
+        dirs.extend(self.template().map(|template| format!("{}.d", template)));
+        let dashes = self
+            .prefix
+            .match_indices('-')
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        for &index in dashes.iter().rev() {
+            dirs.push(format!("{}-.{}.d", &self.prefix[..index], self.kind));
+        }
+        dirs.push(format!("{}.d", self.kind));
+        dirs
+    }
+}
+
+///Private function that undoes the escaping of unit names: `-` stands for `/` and `\xNN` for a byte.
+fn unescape_name(name: &str) -> String {
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .strip_prefix(b"x")
+            .filter(|_| byte == b'\\')
+            .and_then(|hex| hex.get(..2))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(escaped) => {
+                bytes.push(escaped);
+                rest = &tail[3..];
+            }
+            None => {
+                bytes.push(if byte == b'-' { b'/' } else { byte });
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+///Returns the specifiers that systemd derives from a unit name: `%n` (the full name), `%N` (the name without its
+///type), `%p` (the prefix), `%i` (the instance), `%j` (the last dash-separated part of the prefix), their unescaped
+///forms `%P`, `%I` and `%J`, and `%f` (the unescaped instance, or prefix, as a path). Other specifiers, like `%h` or
+///`%H`, depend on the system and can be added to the map by the caller.
+///## Example
+///```rust
+///use configparser::systemd::name_specifiers;
+///
+///let specifiers = name_specifiers("serial-getty@ttyS0.service");
+///assert_eq!(specifiers[&'p'], "serial-getty");
+///assert_eq!(specifiers[&'i'], "ttyS0");
+///assert_eq!(specifiers[&'j'], "getty");
+///assert_eq!(specifiers[&'f'], "/ttyS0");
+///```
+pub fn name_specifiers(name: &str) -> HashMap<char, String> {
+    let parts = UnitName::parse(name);
+    let instance = parts.instance.unwrap_or_default();
+    let last = parts.prefix.rsplit('-').next().unwrap_or_default();
+    let path = unescape_name(if instance.is_empty() {
+        parts.prefix
+    } else {
+        instance
+    });
+    HashMap::from([
+        ('n', name.to_owned()),
+        ('N', parts.stem.to_owned()),
+        ('p', parts.prefix.to_owned()),
+        ('P', unescape_name(parts.prefix)),
+        ('i', instance.to_owned()),
+        ('I', unescape_name(instance)),
+        ('j', last.to_owned()),
+        ('J', unescape_name(last)),
+        ('f', format!("/{}", path.trim_start_matches('/'))),
+    ])
+}
+
+///Resolves the specifiers of a value, like `%i`, with the given map, and `%%` to a single `%`. Values read into a
+///`Unit` keep their specifiers, so this is done on demand.
+///## Example
+///```rust
+///use configparser::systemd::{expand_specifiers, name_specifiers};
+///
+///let specifiers = name_specifiers("getty@tty1.service");
+///assert_eq!(expand_specifiers("/dev/%I (100%%)", &specifiers).unwrap(), "/dev/tty1 (100%)");
+///assert!(expand_specifiers("%h/.config", &specifiers).is_err());
+///```
+///Returns `Ok(value)` or an `Err(error_string)` for a specifier missing from the map or a trailing `%`.
+pub fn expand_specifiers(
+    value: &str,
+    specifiers: &HashMap<char, String>,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some(specifier) => match specifiers.get(&specifier) {
+                Some(expanded) => out.push_str(expanded),
+                None => return Err(format!("Unknown specifier %{} in {:?}", specifier, value)),
+            },
+            None => return Err(format!("Trailing % in {:?}", value)),
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,276 @@
+//!The typed module provides the `FromIni` trait, which builds a typed struct from a configuration and reports every
+//!missing or invalid key at once. With the `derive` feature, `#[derive(FromIni)]` generates the implementation from
+//!the struct's fields without depending on serde.
+//!See the [`FromIni`] documentation for more details.
+use crate::ini::Ini;
+use crate::parser::error_position;
+use crate::schema::{Violation, ViolationKind, parse_duration};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(feature = "derive")]
+pub use configparser_derive::FromIni;
+
+///A type that can be built from the keys of a configuration.
+///
+///With the `derive` feature, `#[derive(FromIni)]` implements this trait for structs with named fields. Every field
+///is read from a key named after the field (or its `rename`), in the section passed to `from_ini_section()`
+///(or the struct's `section`). The field type decides how the value is parsed, see [`FromIniValue`]. Fields of
+///type `Option<T>` may be missing, other fields are required unless they have a default.
+///
+///The `#[ini(...)]` attribute takes these options on the struct:
+///- `section = "name"`: the section `from_ini()` reads, instead of the default section.
+///- `cascade` or `cascade = "name"`: every field falls back to the default section (or the named one) when its key
+///  is missing.
+///
+///And these options on fields:
+///- `rename = "key"`: the key to read, instead of the field name.
+///- `section = "name"`: the section to read the key from, instead of the struct's section.
+///- `default` or `default = "path"`: a missing key is set to `Default::default()` (or the return value of the
+///  function at `path`) instead of being a violation.
+///- `cascade` or `cascade = "name"`: the key falls back to the default section (or the named one) when missing.
+///- `with = "path"`: parses the value with the function at `path`, of type `fn(&str) -> Result<T, String>`.
+///- `flatten`: the field is itself a `FromIni` type, read from the same section (or the field's `section`). Its
+///  violations are reported together with the ones of the outer struct.
+///- `skip`: the field isn't read and is set to `Default::default()`.
+///## Example
+///```rust
+///# #[cfg(feature = "derive")] {
+///use configparser::ini::Ini;
+///use configparser::typed::FromIni;
+///use std::time::Duration;
+///
+///#[derive(FromIni)]
+///#[ini(section = "server")]
+///struct Server {
+///    port: u16,
+///    #[ini(rename = "bind-address", default)]
+///    address: String,
+///    timeout: Option<Duration>,
+///}
+///
+///let mut config = Ini::new();
+///config.read(String::from("[server]\nport = 8080\ntimeout = 30s")).unwrap();
+///let server = Server::from_ini(&config).unwrap();
+///assert_eq!(server.port, 8080);
+///assert_eq!(server.address, "");
+///assert_eq!(server.timeout, Some(Duration::from_secs(30)));
+///
+///config.read(String::from("[server]\ntimeout = soon")).unwrap();
+///let violations = Server::from_ini(&config).err().unwrap();
+///assert_eq!(violations[0].to_string(), "line 1:1: server:port: missing required key");
+///assert_eq!(violations[1].to_string(), "line 2:11: server:timeout: invalid value \"soon\": expected a duration");
+///# }
+///```
+pub trait FromIni: Sized {
+    ///Builds the type from the keys of `section` in the configuration.
+    ///Returns a `Result<Self, Vec<Violation>>` with every missing or invalid key if it fails.
+    fn from_ini_section(config: &Ini, section: &str) -> Result<Self, Vec<Violation>>;
+
+    ///Builds the type from the keys of the default section in the configuration, or the section the type
+    ///itself names.
+    ///Returns a `Result<Self, Vec<Violation>>` with every missing or invalid key if it fails.
+    fn from_ini(config: &Ini) -> Result<Self, Vec<Violation>> {
+        Self::from_ini_section(config, &config.defaults().default_section)
+    }
+}
+
+///A type that can be read from the value of a single key, using the typed getters of `Ini`.
+///
+///Implemented for `String`, `bool` (like `getboolcoerce()`), all integer types (like `getint()` and `getuint()`,
+///checking the range of smaller types), `f32`, `f64`, `char`, `PathBuf` and `Duration` (like `parse_duration()`).
+pub trait FromIniValue: Sized {
+    ///Reads the value of `key` in `section`.
+    ///Returns `Ok(None)` if the key is missing or has no value, or an `Err(string)` if the value is invalid.
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String>;
+}
+
+impl FromIniValue for String {
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+        Ok(config.get(section, key))
+    }
+}
+
+impl FromIniValue for bool {
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+        config.getboolcoerce(section, key)
+    }
+}
+
+impl FromIniValue for f64 {
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+        config.getfloat(section, key)
+    }
+}
+
+impl FromIniValue for f32 {
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+        config
+            .getfloat(section, key)?
+            .map(|value| match value as f32 {
+                narrowed if narrowed.is_finite() || !value.is_finite() => Ok(narrowed),
+                _ => Err(String::from("out of range for f32")),
+            })
+            .transpose()
+    }
+}
+
+macro_rules! impl_from_ini_value_int {
+    ($getter:ident: $($int:ty),*) => {$(
+        impl FromIniValue for $int {
+            fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+                config
+                    .$getter(section, key)?
+                    .map(|value| {
+                        <$int>::try_from(value)
+                            .map_err(|_| format!("out of range for {}", stringify!($int)))
+                    })
+                    .transpose()
+            }
+        }
+    )*};
+}
+
+impl_from_ini_value_int!(getint: i8, i16, i32, i64, i128, isize);
+impl_from_ini_value_int!(getuint: u8, u16, u32, u64, u128, usize);
+
+impl FromIniValue for char {
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+        config
+            .get(section, key)
+            .map(|value| {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(c),
+                    _ => Err(String::from("expected a single character")),
+                }
+            })
+            .transpose()
+    }
+}
+
+impl FromIniValue for PathBuf {
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+        Ok(config.get(section, key).map(PathBuf::from))
+    }
+}
+
+impl FromIniValue for Duration {
+    fn from_ini_value(config: &Ini, section: &str, key: &str) -> Result<Option<Self>, String> {
+        config
+            .get(section, key)
+            .map(|value| parse_duration(&value).map_err(|_| String::from("expected a duration")))
+            .transpose()
+    }
+}
+
+///Collects the fields read by the code `#[derive(FromIni)]` generates, together with their violations.
+///Not part of the public API.
+#[doc(hidden)]
+pub struct Fields<'a> {
+    config: &'a Ini,
+    violations: Vec<Violation>,
+}
+
+#[doc(hidden)]
+impl<'a> Fields<'a> {
+    pub fn new(config: &'a Ini) -> Fields<'a> {
+        Fields {
+            config,
+            violations: Vec::new(),
+        }
+    }
+
+    ///Reads a key, falling back to `fallback` if it's missing in `section`.
+    ///Returns `None` if the value is invalid (recording the violation) or else `Some(value)`, which is
+    ///`Some(None)` for missing keys.
+    pub fn get<T: FromIniValue>(
+        &mut self,
+        section: &str,
+        key: &str,
+        fallback: Option<&str>,
+    ) -> Option<Option<T>> {
+        self.read(section, key, fallback, |config, section| {
+            T::from_ini_value(config, section, key)
+        })
+    }
+
+    ///Reads a key like `get()`, parsing its value with `parse`.
+    pub fn get_with<T>(
+        &mut self,
+        section: &str,
+        key: &str,
+        fallback: Option<&str>,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Option<Option<T>> {
+        self.read(section, key, fallback, |config, section| {
+            config
+                .get(section, key)
+                .map(|value| parse(&value))
+                .transpose()
+        })
+    }
+
+    fn read<T>(
+        &mut self,
+        section: &str,
+        key: &str,
+        fallback: Option<&str>,
+        read: impl Fn(&Ini, &str) -> Result<Option<T>, String>,
+    ) -> Option<Option<T>> {
+        let mut found = section;
+        let mut result = read(self.config, section);
+        if let (Ok(None), Some(fallback)) = (&result, fallback) {
+            found = fallback;
+            result = read(self.config, fallback);
+        }
+        match result {
+            Ok(value) => Some(value),
+            Err(why) => {
+                // The getters prefix errors with the position, which the violation has on its own.
+                let why = error_position(&why).map_or(why.as_str(), |(_, _, message)| message);
+                let reason = match self.config.get(found, key) {
+                    Some(value) => format!("invalid value {:?}: {}", value, why),
+                    None => why.to_owned(),
+                };
+                self.violation(found, key, ViolationKind::InvalidValue(reason));
+                None
+            }
+        }
+    }
+
+    ///Records a missing required key.
+    pub fn missing<T>(&mut self, section: &str, key: &str) -> Option<T> {
+        self.violation(section, key, ViolationKind::MissingKey);
+        None
+    }
+
+    ///Reads a nested `FromIni` type from `section`, recording its violations.
+    pub fn flatten<T: FromIni>(&mut self, section: &str) -> Option<T> {
+        match T::from_ini_section(self.config, section) {
+            Ok(value) => Some(value),
+            Err(violations) => {
+                self.violations.extend(violations);
+                None
+            }
+        }
+    }
+
+    ///Returns the recorded violations.
+    pub fn finish(self) -> Vec<Violation> {
+        self.violations
+    }
+
+    fn violation(&mut self, section: &str, key: &str, kind: ViolationKind) {
+        let mut violation = Violation {
+            section: section.to_owned(),
+            key: Some(key.to_owned()),
+            kind,
+            line: None,
+            column: None,
+        };
+        self.config.locate(&mut violation);
+        self.violations.push(violation);
+    }
+}
@@ -0,0 +1,595 @@
+//!The watch module provides hot reloading for long-running programs. A `Watcher` keeps an `Ini` loaded from one or
+//!more files up to date: it notices when the files change, validates the new content before swapping it in and
+//!notifies subscribers of what changed.
+//!See the [`Watcher`] documentation for more details.
+use crate::diff::{IniDiff, KeyChange, SectionChange};
+use crate::ini::Ini;
+
+#[cfg(feature = "notify")]
+use std::collections::HashSet;
+#[cfg(feature = "notify")]
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "notify")]
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+///What happened to a section or key during a reload.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+///A single change found during a reload. Changes to whole sections have no key.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Change {
+    pub section: String,
+    pub key: Option<String>,
+    pub kind: ChangeKind,
+}
+
+///The message subscribers receive for every reload attempt: either the changes that were applied or the reason
+///the new content was rejected (in which case the previous configuration stays in place).
+pub type ReloadResult = Result<Vec<Change>, String>;
+
+///Private alias for the validator callback.
+type Validator = Box<dyn Fn(&Ini) -> Result<(), String> + Send>;
+
+///The mechanism a `Watcher` uses to notice file changes.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum WatchBackend {
+    ///Compares the modification time, size and (on Unix) inode of every file on each check. This works everywhere
+    ///and needs no extra resources, but a rewrite that keeps the size of a file within the timestamp granularity
+    ///of the filesystem can go unnoticed. This is the default.
+    Polling,
+    ///Sleeps on the native filesystem events (inotify on Linux) for the directories of the watched files, using
+    ///the [notify](https://crates.io/crates/notify) crate, and reloads as soon as one of the files is written,
+    ///created, renamed or deleted. Only `Watcher::wait()` and `Watcher::spawn()` use the events, `poll()` still
+    ///compares the files. Requires the `notify` feature.
+    #[cfg(feature = "notify")]
+    Notify,
+}
+
+///Private struct identifying the state of a file on disk.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    #[cfg(unix)]
+    inode: (u64, u64),
+}
+
+impl FileStamp {
+    fn from_metadata(metadata: &fs::Metadata) -> FileStamp {
+        #[cfg(unix)]
+        use std::os::unix::fs::MetadataExt;
+
+        FileStamp {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            #[cfg(unix)]
+            inode: (metadata.dev(), metadata.ino()),
+        }
+    }
+}
+
+///The `Watcher` struct reloads a set of files into an `Ini` whenever they change. The files are read in order,
+///with later files applied on top of earlier ones like `load_and_append()`, using the defaults of the `Ini` the
+///watcher was created with.
+///
+///A reload only replaces the configuration if every file can be read and parsed and the optional validator accepts
+///the result. Subscribers receive the list of changed sections and keys, or the reason a reload was rejected. The
+///full `IniDiff` of the last reload, including the old and new values, is available from `last_diff()`.
+///## Example
+///```rust
+///use configparser::ini::Ini;
+///use configparser::watch::Watcher;
+///
+///let path = std::env::temp_dir().join("configparser_watch_doc.ini");
+///std::fs::write(&path, "[server]\nport = 80\n").unwrap();
+///
+///let mut watcher = Watcher::new(Ini::new(), [&path]).unwrap();
+///watcher.set_validator(|config| match config.getuint("server", "port")? {
+///    Some(_) => Ok(()),
+///    None => Err(String::from("server.port is required")),
+///});
+///let updates = watcher.subscribe();
+///
+///std::fs::write(&path, "[server]\nport = 8080\n").unwrap();
+///let changes = watcher.poll().unwrap().unwrap();
+///assert_eq!(changes.len(), 1);
+///assert_eq!(watcher.config().get("server", "port").unwrap(), "8080");
+///assert_eq!(updates.try_recv().unwrap().unwrap(), changes);
+///let diff = watcher.last_diff().unwrap();
+///assert_eq!(diff.to_string(), "  [server]\n- port = 80\n+ port = 8080\n");
+///# std::fs::remove_file(&path).unwrap();
+///```
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    stamps: Vec<Option<FileStamp>>,
+    config: Arc<RwLock<Ini>>,
+    validator: Option<Validator>,
+    subscribers: Vec<Sender<ReloadResult>>,
+    last_diff: Option<IniDiff>,
+    backend: WatchBackend,
+    #[cfg(feature = "notify")]
+    events: Option<Events>,
+}
+
+impl std::fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watcher")
+            .field("paths", &self.paths)
+            .field("config", &self.config)
+            .field("backend", &self.backend)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Watcher {
+    ///Creates a new `Watcher` for the given files and loads them right away. The map of `template` is ignored,
+    ///only its defaults are used for parsing.
+    ///Returns `Ok(watcher)` if all files could be loaded or else `Err(error_string)`.
+    pub fn new<P: AsRef<Path>>(
+        template: Ini,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Watcher, String> {
+        let paths: Vec<PathBuf> = paths.into_iter().map(|p| p.as_ref().to_owned()).collect();
+        if paths.is_empty() {
+            return Err(String::from("no files to watch"));
+        }
+        let stamps = paths.iter().map(|path| stamp(path)).collect();
+        let contents = paths
+            .iter()
+            .map(|path| read(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let config = build(&template, &paths, contents)?;
+
+        Ok(Watcher {
+            paths,
+            stamps,
+            config: Arc::new(RwLock::new(config)),
+            validator: None,
+            subscribers: Vec::new(),
+            last_diff: None,
+            backend: WatchBackend::Polling,
+            #[cfg(feature = "notify")]
+            events: None,
+        })
+    }
+
+    ///Sets a validator that every reloaded configuration must pass before it replaces the current one. It is not
+    ///applied to the configuration loaded by `new()`.
+    pub fn set_validator<F>(&mut self, validator: F)
+    where
+        F: Fn(&Ini) -> Result<(), String> + Send + 'static,
+    {
+        self.validator = Some(Box::new(validator));
+    }
+
+    ///Switches the mechanism used to notice file changes.
+    ///Returns `Err(error_string)` if the backend could not be set up, in which case the backend is unchanged.
+    pub fn set_backend(&mut self, backend: WatchBackend) -> Result<(), String> {
+        #[cfg(feature = "notify")]
+        {
+            self.events = match backend {
+                WatchBackend::Notify => Some(
+                    Events::new(&self.paths)
+                        .map_err(|why| format!("couldn't watch the files: {}", why))?,
+                ),
+                WatchBackend::Polling => None,
+            };
+        }
+        self.backend = backend;
+        Ok(())
+    }
+
+    ///Returns a new receiver that gets the result of every reload attempt from now on.
+    pub fn subscribe(&mut self) -> Receiver<ReloadResult> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    ///Returns a read guard for the current configuration.
+    pub fn config(&self) -> RwLockReadGuard<'_, Ini> {
+        self.config
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    ///Returns the diff from the previous to the current configuration of the last reload that replaced it, or
+    ///`None` if no reload has happened yet.
+    pub fn last_diff(&self) -> Option<&IniDiff> {
+        self.last_diff.as_ref()
+    }
+
+    ///Returns the shared handle of the current configuration, which stays up to date after the watcher has been
+    ///moved to another thread with `spawn()`.
+    pub fn shared(&self) -> Arc<RwLock<Ini>> {
+        Arc::clone(&self.config)
+    }
+
+    ///Checks the files once and reloads them if any of them changed.
+    ///Returns `Ok(Some(changes))` if the configuration was reloaded (the list may be empty if only formatting
+    ///changed), `Ok(None)` if no file changed or else `Err(error_string)` if the new content was rejected.
+    pub fn poll(&mut self) -> Result<Option<Vec<Change>>, String> {
+        self.check(false)
+    }
+
+    ///Private function that reloads the files if their stamps changed or `force` is set.
+    fn check(&mut self, force: bool) -> Result<Option<Vec<Change>>, String> {
+        let stamps: Vec<_> = self.paths.iter().map(|path| stamp(path)).collect();
+        if !force && stamps == self.stamps {
+            return Ok(None);
+        }
+        // Remember the new state even if the reload fails, so broken content is only reported once.
+        self.stamps = stamps;
+        let contents = self.paths.iter().map(|path| read(path)).collect();
+        self.apply(contents).map(Some)
+    }
+
+    ///Blocks until the files change and the configuration was reloaded (or rejected), checking at least every
+    ///`interval`. With the polling backend, the files are compared every `interval`.
+    ///Returns the same as `poll()`, except that it only returns `Ok(None)` if `stop` was set. If the `Notify` backend
+    ///fails, the error is returned (and sent to the subscribers) and the watcher falls back to polling.
+    pub fn wait(
+        &mut self,
+        interval: Duration,
+        stop: &AtomicBool,
+    ) -> Result<Option<Vec<Change>>, String> {
+        while !stop.load(Ordering::Relaxed) {
+            #[cfg(feature = "notify")]
+            let force = match self.events.as_ref().map(|events| events.wait(interval)) {
+                Some(Ok(force)) => force,
+                Some(Err(why)) => {
+                    // Fall back to polling, so a broken watcher neither stops reloading nor fails on every call.
+                    self.events = None;
+                    self.backend = WatchBackend::Polling;
+                    let why = format!("{}, falling back to polling", why);
+                    self.subscribers
+                        .retain(|subscriber| subscriber.send(Err(why.clone())).is_ok());
+                    return Err(why);
+                }
+                None => {
+                    thread::sleep(interval);
+                    false
+                }
+            };
+            #[cfg(not(feature = "notify"))]
+            let force = {
+                thread::sleep(interval);
+                false
+            };
+
+            if let Some(changes) = self.check(force)? {
+                return Ok(Some(changes));
+            }
+        }
+        Ok(None)
+    }
+
+    ///Moves the watcher to a background thread that keeps reloading the files until the returned handle is
+    ///stopped or dropped. Use `shared()` and `subscribe()` before spawning to access the configuration and the
+    ///reload results.
+    ///## Example
+    ///```rust
+    ///use configparser::ini::Ini;
+    ///use configparser::watch::Watcher;
+    ///use std::time::Duration;
+    ///
+    ///let path = std::env::temp_dir().join("configparser_spawn_doc.ini");
+    ///std::fs::write(&path, "key = old\n").unwrap();
+    ///
+    ///let mut watcher = Watcher::new(Ini::new(), [&path]).unwrap();
+    ///let config = watcher.shared();
+    ///let updates = watcher.subscribe();
+    ///let handle = watcher.spawn(Duration::from_millis(10));
+    ///
+    ///// Replace the file atomically so the watcher never reads it half-written.
+    ///let staged = path.with_extension("new");
+    ///std::fs::write(&staged, "key = newer\n").unwrap();
+    ///std::fs::rename(&staged, &path).unwrap();
+    ///updates.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    ///assert_eq!(config.read().unwrap().get("default", "key").unwrap(), "newer");
+    ///handle.stop();
+    ///# std::fs::remove_file(&path).unwrap();
+    ///```
+    pub fn spawn(mut self, interval: Duration) -> WatchHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            // Rejected reloads and backend failures have already been sent to the subscribers, so keep watching.
+            // Neither repeats without a new change: failed reloads remember the stamps they read and a failed
+            // backend is replaced by polling, which sleeps between checks.
+            while !matches!(self.wait(interval, &thread_stop), Ok(None)) {}
+        });
+        WatchHandle {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    ///Private function that builds, validates and swaps in a new configuration from freshly read files, then
+    ///notifies the subscribers.
+    fn apply(&mut self, contents: Vec<Result<String, String>>) -> Result<Vec<Change>, String> {
+        let result = contents
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|contents| build(&self.config(), &self.paths, contents))
+            .and_then(|config| {
+                if let Some(validator) = self.validator.as_ref() {
+                    validator(&config)?;
+                }
+                Ok(config)
+            })
+            .map(|config| {
+                let mut current = self
+                    .config
+                    .write()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                let diff = current.diff(&config);
+                *current = config;
+                diff
+            })
+            .map(|diff| {
+                let changes = changes(&diff);
+                self.last_diff = Some(diff);
+                changes
+            });
+        self.subscribers
+            .retain(|subscriber| subscriber.send(result.clone()).is_ok());
+        result
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Watcher {
+    ///Checks the files once asynchronously and reloads them if any of them changed.
+    ///
+    ///Usage is similar to `poll`, but `.await` must be called after along with the usual async rules.
+    pub async fn poll_async(&mut self) -> Result<Option<Vec<Change>>, String> {
+        let mut stamps = Vec::with_capacity(self.paths.len());
+        for path in self.paths.iter() {
+            stamps.push(
+                tokio::fs::metadata(path)
+                    .await
+                    .ok()
+                    .map(|metadata| FileStamp::from_metadata(&metadata)),
+            );
+        }
+        if stamps == self.stamps {
+            return Ok(None);
+        }
+        self.stamps = stamps;
+        let mut contents = Vec::with_capacity(self.paths.len());
+        for path in self.paths.iter() {
+            contents.push(
+                tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|why| format!("couldn't read {}: {}", path.display(), why)),
+            );
+        }
+        self.apply(contents).map(Some)
+    }
+
+    ///Turns the watcher into an `AsyncWatcher` that checks the files every `interval`.
+    pub fn into_async(self, interval: Duration) -> AsyncWatcher {
+        AsyncWatcher {
+            watcher: self,
+            interval,
+        }
+    }
+}
+
+///The `AsyncWatcher` struct is the asynchronous counterpart of `Watcher::spawn()`, created with
+///`Watcher::into_async()`. Call `changed()` in a loop to receive reload results as they happen.
+///## Example
+///```rust
+///use configparser::ini::Ini;
+///use configparser::watch::Watcher;
+///use std::time::Duration;
+///
+///#[tokio::main]
+///async fn main() {
+///    let path = std::env::temp_dir().join("configparser_stream_doc.ini");
+///    std::fs::write(&path, "key = old\n").unwrap();
+///
+///    let watcher = Watcher::new(Ini::new(), [&path]).unwrap();
+///    let config = watcher.shared();
+///    let mut watcher = watcher.into_async(Duration::from_millis(10));
+///
+///    std::fs::write(&path, "key = newer\n").unwrap();
+///    watcher.changed().await.unwrap();
+///    assert_eq!(config.read().unwrap().get("default", "key").unwrap(), "newer");
+///    # std::fs::remove_file(&path).unwrap();
+///}
+///```
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct AsyncWatcher {
+    watcher: Watcher,
+    interval: Duration,
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncWatcher {
+    ///Waits until the files change and returns the result of the reload.
+    pub async fn changed(&mut self) -> ReloadResult {
+        loop {
+            tokio::time::sleep(self.interval).await;
+            if let Some(changes) = self.watcher.poll_async().await? {
+                return Ok(changes);
+            }
+        }
+    }
+
+    ///Returns the watcher back.
+    pub fn into_inner(self) -> Watcher {
+        self.watcher
+    }
+}
+
+///The `WatchHandle` struct controls a watcher running on a background thread, see `Watcher::spawn()`.
+///Dropping the handle stops the thread as well.
+#[derive(Debug)]
+pub struct WatchHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    ///Stops the background thread and waits for it to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+///Private function that lists the changes of a diff, with whole sections for added and removed sections.
+fn changes(diff: &IniDiff) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for section in diff.sections.iter() {
+        let change = |key: Option<&str>, kind| Change {
+            section: section.name.clone(),
+            key: key.map(str::to_owned),
+            kind,
+        };
+        match section.change {
+            SectionChange::Added => changes.push(change(None, ChangeKind::Added)),
+            SectionChange::Removed => changes.push(change(None, ChangeKind::Removed)),
+            SectionChange::Modified => {
+                for key in section.keys.iter() {
+                    let kind = match key.change {
+                        KeyChange::Added(_) => ChangeKind::Added,
+                        KeyChange::Removed(_) => ChangeKind::Removed,
+                        KeyChange::Changed { .. } => ChangeKind::Modified,
+                    };
+                    changes.push(change(Some(&key.key), kind));
+                }
+            }
+        }
+    }
+    changes
+}
+
+///Private function that returns the current stamp of a file, or `None` if it cannot be accessed.
+fn stamp(path: &Path) -> Option<FileStamp> {
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| FileStamp::from_metadata(&metadata))
+}
+
+///Private function that reads a file with the same error message as `Ini::load()`.
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|why| format!("couldn't read {}: {}", path.display(), why))
+}
+
+///Private function that parses the contents of all files into a new `Ini` with the defaults of `template`.
+fn build(template: &Ini, paths: &[PathBuf], contents: Vec<String>) -> Result<Ini, String> {
+    let mut config = template.clone();
+    config.clear();
+    for (index, (path, content)) in paths.iter().zip(contents).enumerate() {
+        let loaded = if index == 0 {
+            config.read_ref(content)
+        } else {
+            config.read_and_append_ref(content)
+        };
+        loaded.map_err(|why| format!("couldn't read {}: {}", path.display(), why))?;
+    }
+    Ok(config)
+}
+
+///Private struct receiving the filesystem events for the directories of the watched files. Directories are watched
+///instead of the files themselves so that editors replacing files by renaming are noticed as well.
+#[cfg(feature = "notify")]
+struct Events {
+    _watcher: notify::RecommendedWatcher,
+    receiver: Receiver<notify::Result<notify::Event>>,
+    names: HashSet<OsString>,
+}
+
+#[cfg(feature = "notify")]
+impl Events {
+    fn new(paths: &[PathBuf]) -> notify::Result<Events> {
+        use notify::Watcher as _;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let directories: HashSet<&Path> = paths
+            .iter()
+            .map(|path| match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            })
+            .collect();
+        for directory in directories {
+            watcher.watch(directory, notify::RecursiveMode::NonRecursive)?;
+        }
+        let names = paths
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_owned())
+            .collect();
+        Ok(Events {
+            _watcher: watcher,
+            receiver,
+            names,
+        })
+    }
+
+    ///Waits up to `timeout` for events and drains them. Returns whether any event concerned one of the watched
+    ///files.
+    fn wait(&self, timeout: Duration) -> Result<bool, String> {
+        let mut events = match self.receiver.recv_timeout(timeout) {
+            Ok(event) => vec![event],
+            Err(RecvTimeoutError::Timeout) => return Ok(false),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(String::from("the file watcher stopped unexpectedly"));
+            }
+        };
+        // Take the events that arrived in the meantime as well, so a burst of writes causes a single reload.
+        events.extend(self.receiver.try_iter());
+        let mut any = false;
+        for event in events {
+            let event = event.map_err(|why| format!("couldn't watch the files: {}", why))?;
+            any |= event
+                .paths
+                .iter()
+                .filter_map(|path| path.file_name())
+                .any(|name| self.names.contains(name));
+        }
+        Ok(any)
+    }
+}
+
+#[cfg(feature = "notify")]
+impl std::fmt::Debug for Events {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Events")
+            .field("names", &self.names)
+            .finish_non_exhaustive()
+    }
+}
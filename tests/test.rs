@@ -620,6 +620,79 @@ Key3 = another value
     Ok(())
 }
 
+#[test]
+#[cfg(feature = "indexmap")]
+fn pretty_writes_aligned_and_wrapped() -> Result<(), Box<dyn Error>> {
+    let mut config = Ini::new_cs();
+    config.set_multiline(true);
+    config.read(
+        "top=level
+[Section]
+Key1=Value1
+LongerKey=the quick brown fox jumps over the lazy dog
+NoValue
+Empty="
+            .to_owned(),
+    )?;
+
+    let mut write_options = WriteOptions::default();
+    write_options.space_around_delimiters = true;
+    write_options.multiline_line_indentation = 2;
+    write_options.blank_lines_between_sections = 1;
+    write_options.delimiter = ':';
+    write_options.align_delimiters = true;
+    write_options.wrap_width = Some(30);
+    write_options.write_default_section_header = true;
+    let written = config.pretty_writes(&write_options);
+    assert_eq!(
+        written,
+        "[default]
+top : level
+
+[Section]
+Key1      : Value1
+LongerKey : the quick brown
+  fox jumps over the lazy dog
+NoValue
+Empty     :
+"
+    );
+
+    // Wrapping turns the long value into a multiline value on re-read.
+    let mut reread = Ini::new_cs();
+    reread.set_multiline(true);
+    reread.read(written)?;
+    assert_eq!(reread.get("default", "top").unwrap(), "level");
+    assert_eq!(
+        reread.get("Section", "LongerKey").unwrap(),
+        "the quick brown\nfox jumps over the lazy dog"
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "indexmap")]
+fn pretty_writes_wrap_keeps_short_lines() -> Result<(), Box<dyn Error>> {
+    let mut config = Ini::new();
+    config.set_multiline(true);
+    config.read("[s]\nk = a    b  c\nlong = one  two   three four".to_owned())?;
+
+    let mut write_options = WriteOptions::default();
+    write_options.wrap_width = Some(16);
+    let written = config.pretty_writes(&write_options);
+    assert!(written.contains("k=a    b  c"));
+    assert!(written.contains("long=one  two\n    three four"));
+
+    let mut reread = Ini::new();
+    reread.set_multiline(true);
+    reread.read(written)?;
+    assert_eq!(reread.get("s", "k").unwrap(), "a    b  c");
+    assert_eq!(reread.get("s", "long").unwrap(), "one  two\nthree four");
+
+    Ok(())
+}
+
 #[test]
 #[cfg(feature = "indexmap")]
 #[cfg(feature = "tokio")]
@@ -794,6 +867,213 @@ async fn async_load_and_append() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn ref_loaders_and_iterators() -> Result<(), Box<dyn Error>> {
+    let mut cloned = Ini::new();
+    cloned.load("tests/test.ini")?;
+    cloned.load_and_append("tests/test_more.ini")?;
+
+    let mut config = Ini::new();
+    config.load_ref("tests/test.ini")?;
+    config.load_and_append_ref("tests/test_more.ini")?;
+    assert_eq!(config, cloned);
+
+    let mut streamed = Ini::new();
+    streamed.load_from_stream_ref(std::fs::File::open("tests/test.ini")?)?;
+    streamed.read_and_append_ref(std::fs::read_to_string("tests/test_more.ini")?)?;
+    assert_eq!(streamed, cloned);
+
+    let sections: HashSet<&str> = config.iter_sections().collect();
+    assert_eq!(
+        sections,
+        HashSet::from(["default", "topsecret", "spacing", "values"])
+    );
+    let topsecret: HashSet<(&str, Option<&str>)> =
+        config.iter_section("TopSecret").unwrap().collect();
+    assert!(topsecret.contains(&("kfc", Some("redacted"))));
+    assert!(topsecret.contains(&("none string", None)));
+    assert!(config.iter_section("missing").is_none());
+    assert_eq!(
+        config.iter().count(),
+        config
+            .get_map_ref()
+            .values()
+            .map(|secmap| secmap.len())
+            .sum::<usize>()
+    );
+    assert!(
+        config
+            .iter()
+            .any(|entry| entry == ("values", "bool", Some("False")))
+    );
+
+    assert!(config.read_ref("[unclosed".to_owned()).is_err());
+
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::approx_constant)]
+fn section_views_and_entries() -> Result<(), Box<dyn Error>> {
+    let mut config = Ini::new();
+    config.load("tests/test.ini")?;
+    config.set_cascade_defaults(true);
+
+    let values = config.section("VALUES").unwrap();
+    assert_eq!(values.name(), "values");
+    assert_eq!(values.len(), 5);
+    assert!(values.getbool("Bool")?.unwrap());
+    assert!(!values.getboolcoerce("boolcoerce")?.unwrap());
+    assert_eq!(values.getint("int")?, Some(-31415));
+    assert_eq!(values.getuint("UINT")?, Some(31415));
+    assert_eq!(values.getfloat("Float")?, Some(3.1415));
+    // Cascades to the default section but does not report it as contained.
+    assert_eq!(values.get("defaultvalues"), Some("defaultvalues"));
+    assert!(!values.contains_key("defaultvalues"));
+    let keys: HashSet<&str> = values.keys().collect();
+    assert_eq!(
+        keys,
+        HashSet::from(["bool", "boolcoerce", "int", "uint", "float"])
+    );
+    assert!(config.section("nonexistent").is_none());
+
+    let mut section = config.section_mut("New Section");
+    assert!(section.is_empty());
+    section.setstr("Answer", Some("42"));
+    section.set("Question", None);
+    assert_eq!(section.getint("answer")?, Some(42));
+    assert_eq!(section.len(), 2);
+    *section.entry("ANSWER").or_insert(None) = Some("43".to_owned());
+    assert_eq!(section.remove("question"), Some(None));
+    assert_eq!(
+        section.iter().collect::<Vec<_>>(),
+        vec![("answer", Some("43"))]
+    );
+    assert_eq!(config.get("new section", "answer").unwrap(), "43");
+
+    let mut cs = Ini::new_cs();
+    cs.entry("Section", "Key")
+        .or_insert(Some("first".to_owned()));
+    cs.entry("Section", "key")
+        .or_insert(Some("second".to_owned()));
+    cs.entry("Section", "Key")
+        .and_modify(|val| val.as_mut().unwrap().push('!'))
+        .or_insert(None);
+    cs.entry("Missing", "key").and_modify(|val| *val = None);
+    assert_eq!(cs.get("Section", "Key").unwrap(), "first!");
+    assert_eq!(cs.get("Section", "key").unwrap(), "second");
+    assert!(cs.section("Missing").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn case_preserving() -> Result<(), Box<dyn Error>> {
+    use configparser::ini::{CaseFolding, CasePolicy, IniDefault, IniRef};
+
+    const INPUT: &str = "Top=level
+[Server]
+MaxConnections = 10
+Host = localhost
+[SERVER]
+maxconnections = 20
+[Ünïcode]
+Straße = yes
+";
+    let mut config = Ini::new_case_preserving();
+    config.read(INPUT.to_owned())?;
+    assert_eq!(config.sections().len(), 3);
+    assert_eq!(config.getint("server", "MAXCONNECTIONS")?, Some(20));
+    assert_eq!(config.get("ÜNÏCODE", "STRASSE"), None);
+    assert_eq!(config.get("ÜNÏCODE", "straße").unwrap(), "yes");
+    assert_eq!(config.section("SeRvEr").unwrap().name(), "Server");
+
+    config.setstr("SERVER", "HOST", Some("example.com"));
+    config.setstr("server", "Port", Some("8080"));
+    assert_eq!(
+        config.remove_key("server", "port"),
+        Some(Some("8080".to_owned()))
+    );
+    config.setstr("server", "Port", Some("8080"));
+    let server: Vec<_> = config.section("server").unwrap().iter().collect();
+    assert_eq!(server.len(), 3);
+    assert!(server.contains(&("MaxConnections", Some("20"))));
+    assert!(server.contains(&("Host", Some("example.com"))));
+    assert!(server.contains(&("Port", Some("8080"))));
+
+    config.set_cascade_defaults(true);
+    assert_eq!(config.get("Server", "TOP").unwrap(), "level");
+
+    // Names added around the setters are still found case-insensitively.
+    config
+        .get_mut_map()
+        .get_mut("Server")
+        .unwrap()
+        .insert("Timeout".to_owned(), Some("5".to_owned()));
+    config
+        .entry("server", "Retries")
+        .or_insert(Some("3".to_owned()));
+    config.setstr("SERVER", "TIMEOUT", Some("6"));
+    config.setstr("SERVER", "RETRIES", Some("4"));
+    assert_eq!(config.get("server", "timeout").unwrap(), "6");
+    assert_eq!(config.get("server", "retries").unwrap(), "4");
+    assert_eq!(config.section("server").unwrap().len(), 5);
+
+    // Large sections keep every spelling apart from the first.
+    let large: String = (0..2000)
+        .map(|idx| format!("Key{idx} = {idx}\nKEY{idx} = last{idx}\n"))
+        .collect();
+    let mut config = Ini::new_case_preserving();
+    config.read(format!("[Large]\n{large}"))?;
+    assert_eq!(config.section("large").unwrap().len(), 2000);
+    assert_eq!(config.get("LARGE", "key1999").unwrap(), "last1999");
+    let view = IniRef::parse_with_defaults(&large, config.defaults())?;
+    assert_eq!(view.get("default", "kEy7"), Some("last7"));
+
+    // Separate policies and ASCII-only folding.
+    let mut default = IniDefault::default();
+    default.section_case = CasePolicy::Sensitive;
+    default.key_case = CasePolicy::Lowercase;
+    default.case_folding = CaseFolding::Ascii;
+    let mut config = Ini::new_from_defaults(default.clone());
+    config.read(INPUT.to_owned())?;
+    assert_eq!(config.sections().len(), 4);
+    assert_eq!(config.getint("Server", "MAXCONNECTIONS")?, Some(10));
+    assert_eq!(config.get("server", "maxconnections"), None);
+    assert!(config.get_map_ref()["Ünïcode"].contains_key("straße"));
+
+    let view = IniRef::parse_with_defaults(INPUT, default)?;
+    assert_eq!(view.get("SERVER", "MaxConnections"), Some("20"));
+
+    // case_sensitive takes precedence over the policies.
+    let mut default = IniDefault::default();
+    default.case_sensitive = true;
+    default.key_case = CasePolicy::Preserve;
+    let mut config = Ini::new_from_defaults(default);
+    config.read(INPUT.to_owned())?;
+    assert_eq!(config.get("Server", "maxconnections"), None);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn async_ref_loaders() -> Result<(), Box<dyn Error>> {
+    let mut sync_content = Ini::new();
+    sync_content.load_ref("tests/test.ini")?;
+    sync_content.load_and_append_ref("tests/test_more.ini")?;
+
+    let mut async_content = Ini::new();
+    async_content.load_async_ref("tests/test.ini").await?;
+    async_content
+        .load_and_append_async_ref("tests/test_more.ini")
+        .await?;
+
+    assert_eq!(sync_content, async_content);
+
+    Ok(())
+}
+
 #[test]
 #[cfg(feature = "indexmap")]
 fn multiline_off() -> Result<(), Box<dyn Error>> {
@@ -858,6 +1138,233 @@ Key4=Four
     Ok(())
 }
 
+#[test]
+#[allow(clippy::approx_constant)]
+fn borrowed_view() -> Result<(), Box<dyn Error>> {
+    use configparser::ini::{IniDefault, IniRef};
+    use std::borrow::Cow;
+
+    let input = std::fs::read_to_string("tests/test.ini")?;
+    let view = IniRef::parse(&input)?;
+    let mut config = Ini::new();
+    config.read(input.clone())?;
+
+    assert_eq!(view.to_ini(), config);
+    assert_eq!(view.sections().len(), 4);
+    assert_eq!(
+        view.get("topsecret", "KFC"),
+        Some("the secret herb is orega-")
+    );
+    assert_eq!(view.get("topsecret", "None string"), None);
+    assert!(view.getbool("values", "Bool")?.unwrap());
+    assert!(!view.getboolcoerce("values", "Boolcoerce")?.unwrap());
+    assert_eq!(view.getint("values", "Int")?, Some(-31415));
+    assert_eq!(view.getuint("values", "Uint")?, Some(31415));
+    assert_eq!(view.getfloat("values", "Float")?, Some(3.1415));
+
+    // Already-lowercase keys and all values are borrowed from the input, "KFC" had to be folded.
+    let topsecret = &view.get_map_ref()["topsecret"];
+    assert!(
+        topsecret
+            .keys()
+            .any(|key| matches!(key, Cow::Owned(k) if k == "kfc"))
+    );
+    assert!(
+        topsecret
+            .keys()
+            .any(|key| matches!(key, Cow::Borrowed("colon")))
+    );
+    assert!(matches!(topsecret["kfc"], Some(Cow::Borrowed(_))));
+
+    let mut default = IniDefault::default();
+    default.multiline = true;
+    default.case_sensitive = true;
+    let input = std::fs::read_to_string("tests/test_multiline.ini")?;
+    let view = IniRef::parse_with_defaults(&input, default)?;
+    assert_eq!(
+        view.get("Section", "Key3"),
+        Some("this is a haiku\nspread across separate lines\n\na single value")
+    );
+    assert_eq!(view.get("section", "key3"), None);
+
+    Ok(())
+}
+
+#[test]
+fn streaming_events() -> Result<(), Box<dyn Error>> {
+    use configparser::ini::IniDefault;
+    use configparser::parser::{EventKind, Events};
+    use std::fs::File;
+    use std::io::BufReader;
+
+    let mut default = IniDefault::default();
+    default.multiline = true;
+    let events = Events::with_defaults(
+        BufReader::new(File::open("tests/test_multiline.ini")?),
+        &default,
+    )
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let positions: Vec<_> = events
+        .iter()
+        .map(|event| (event.line, event.column))
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 1),
+            (5, 5),
+            (6, 5),
+            (7, 1),
+            (8, 5),
+            (9, 1)
+        ]
+    );
+    assert_eq!(
+        events[0].kind,
+        EventKind::SectionStart {
+            name: "Section".to_owned()
+        }
+    );
+    assert_eq!(
+        events[1].kind,
+        EventKind::KeyValue {
+            key: "Key1".to_owned(),
+            delimiter: Some(':'),
+            value: Some("Value1".to_owned())
+        }
+    );
+    assert_eq!(
+        events[5].kind,
+        EventKind::Comment {
+            text: "# This is a comment".to_owned()
+        }
+    );
+    assert_eq!(events[6].kind, EventKind::Blank);
+    assert_eq!(
+        events[7].kind,
+        EventKind::Continuation {
+            text: "a single value".to_owned()
+        }
+    );
+
+    // Only keep one section while streaming, without building the whole map.
+    let input = "[keep]\na=1\n[drop]\nb=2\n[keep2]\nc=3 ; note\n";
+    let mut current = String::new();
+    let mut kept = Vec::new();
+    for event in Events::new(input.as_bytes()) {
+        match event?.kind {
+            EventKind::SectionStart { name } => current = name,
+            EventKind::KeyValue { key, .. } if current.starts_with("keep") => kept.push(key),
+            _ => {}
+        }
+    }
+    assert_eq!(kept, vec!["a", "c"]);
+
+    let errors: Vec<_> = Events::new("[broken\nkey=value".as_bytes())
+        .filter_map(Result::err)
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["line 1:1: Found opening bracket for section name but no closing bracket"]
+    );
+
+    let mut events = Events::new(&b"a=1\nb=\xff\nc=3"[..]);
+    let span = events.next().unwrap()?.span;
+    assert_eq!((span.start, span.end), (0, 1));
+    assert_eq!(
+        events.next().unwrap().unwrap_err(),
+        "line 2:3: stream did not contain valid UTF-8"
+    );
+    let span = events.next().unwrap()?.span;
+    assert_eq!((span.line, span.start, span.end), (3, 8, 9));
+
+    let mut config = Ini::new();
+    let map = config.load_from_stream(BufReader::new(File::open("tests/test.ini")?))?;
+    assert_eq!(map, Ini::new().load("tests/test.ini")?);
+
+    Ok(())
+}
+
+#[test]
+fn source_spans() -> Result<(), Box<dyn Error>> {
+    let source = "top = 1\n[Section]\r\nkey = first\n  second\n\n  third\nflag\nkey2 = ünïcode";
+    let mut config = Ini::new();
+    config.set_multiline(true);
+    config.read(source.to_owned())?;
+
+    let header = config.section_span("section").unwrap();
+    assert_eq!(&source[header.start..header.end], "[Section]");
+    assert_eq!((header.line, header.column, header.end_column), (2, 1, 10));
+    assert_eq!(config.section_span("default"), None);
+
+    let top = config.span_of("default", "top").unwrap();
+    assert_eq!(&source[top.key.start..top.key.end], "top");
+    let key = config.span_of("section", "KEY").unwrap();
+    assert_eq!((key.key.line, key.key.column), (3, 1));
+    let value = key.value.unwrap();
+    assert_eq!(
+        &source[value.start..value.end],
+        "first\n  second\n\n  third"
+    );
+    assert_eq!((value.line, value.column), (3, 7));
+    assert_eq!((value.end_line, value.end_column), (6, 8));
+    assert_eq!(config.span_of("section", "flag").unwrap().value, None);
+    let unicode = config.span_of("section", "key2").unwrap().value.unwrap();
+    assert_eq!(&source[unicode.start..unicode.end], "ünïcode");
+    assert_eq!((unicode.column, unicode.end_column), (8, 15));
+
+    // Appended keys point into the appended source, removed ones have no span.
+    config.read_and_append(String::from("[section]\nkey = replaced"))?;
+    assert_eq!(config.span_of("section", "key").unwrap().key.line, 2);
+    assert_eq!(config.section_span("section"), Some(header));
+    config.remove_key("section", "flag");
+    assert_eq!(config.span_of("section", "flag"), None);
+    config.remove_section("section");
+    assert_eq!(config.section_span("section"), None);
+
+    // Parse errors report 1-based lines and character columns.
+    let error = Ini::new()
+        .read(String::from("[ok]\nkey = 1\n\u{a0}\u{a0}= 2"))
+        .unwrap_err();
+    assert_eq!(error, "line 3:3: Key cannot be empty");
+    let mut multiline = Ini::new();
+    multiline.set_multiline(true);
+    let error = multiline.read(String::from("\n  indented")).unwrap_err();
+    assert_eq!(
+        error,
+        "line 2:3: Started with indentation but there is no current entry"
+    );
+
+    // Conversion errors of the getters point at the value, following cascades to the default section.
+    let mut config = Ini::new();
+    config.set_cascade_defaults(true);
+    config.read(String::from(
+        "port = x\n[server]\nworkers =  many\ndebug = maybe",
+    ))?;
+    assert_eq!(
+        config.getuint("server", "workers").unwrap_err(),
+        "line 3:12: invalid digit found in string"
+    );
+    assert_eq!(
+        config.getint("server", "port").unwrap_err(),
+        "line 1:8: invalid digit found in string"
+    );
+    assert_eq!(
+        config.getboolcoerce("server", "debug").unwrap_err(),
+        "line 4:9: Unable to parse value into bool at server:debug"
+    );
+    let server = config.section("server").unwrap();
+    assert_eq!(
+        server.getfloat("workers").unwrap_err(),
+        "line 3:12: invalid float literal"
+    );
+    Ok(())
+}
+
 #[test]
 #[cfg(feature = "serde")]
 fn serde_roundtrip() -> Result<(), Box<dyn Error>> {
@@ -973,3 +1480,1654 @@ fn serde_case_sensitive_roundtrip() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn watch_reloads() -> Result<(), Box<dyn Error>> {
+    use configparser::diff::{KeyChange, SectionChange};
+    use configparser::watch::{ChangeKind, Watcher};
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+    let base = dir.join(format!("configparser_watch_base_{}.ini", id));
+    let local = dir.join(format!("configparser_watch_local_{}.ini", id));
+    std::fs::write(&base, "[server]\nhost = localhost\nport = 80\n")?;
+    std::fs::write(&local, "[server]\nport = 8080\n")?;
+
+    let mut watcher = Watcher::new(Ini::new(), [&base, &local])?;
+    watcher.set_validator(|config| {
+        config
+            .getuint("server", "port")?
+            .map(|_| ())
+            .ok_or_else(|| String::from("server.port is required"))
+    });
+    let updates = watcher.subscribe();
+    assert_eq!(watcher.config().get("server", "port").unwrap(), "8080");
+    assert_eq!(watcher.poll()?, None);
+
+    std::fs::write(&local, "[server]\nport = 9090\n[extra]\n")?;
+    let mut changes = watcher.poll()?.unwrap();
+    changes.sort_by(|a, b| a.section.cmp(&b.section));
+    assert_eq!(changes.len(), 2);
+    assert_eq!(
+        (changes[0].section.as_str(), changes[0].kind),
+        ("extra", ChangeKind::Added)
+    );
+    assert_eq!(changes[1].key.as_deref(), Some("port"));
+    assert_eq!(changes[1].kind, ChangeKind::Modified);
+    let mut notified = updates.try_recv()??;
+    notified.sort_by(|a, b| a.section.cmp(&b.section));
+    assert_eq!(notified, changes);
+
+    // The full diff of the reload is kept as well.
+    let diff = watcher.last_diff().unwrap();
+    assert_eq!(diff.sections.len(), 2);
+    let server = diff.sections.iter().find(|s| s.name == "server").unwrap();
+    assert_eq!(server.change, SectionChange::Modified);
+    assert_eq!(
+        server.keys[0].change,
+        KeyChange::Changed {
+            old: Some("8080".to_owned()),
+            new: Some("9090".to_owned())
+        }
+    );
+    let extra = diff.sections.iter().find(|s| s.name == "extra").unwrap();
+    assert_eq!(extra.change, SectionChange::Added);
+
+    // Invalid content is rejected and the previous configuration kept.
+    std::fs::write(&local, "[server]\nport = invalid\n")?;
+    assert!(watcher.poll().is_err());
+    assert!(updates.try_recv()?.is_err());
+    assert_eq!(watcher.config().get("server", "port").unwrap(), "9090");
+    std::fs::write(&local, "[server\n")?;
+    assert!(watcher.poll().is_err());
+    assert!(updates.try_recv()?.is_err());
+    assert_eq!(watcher.config().get("server", "port").unwrap(), "9090");
+
+    #[cfg(feature = "notify")]
+    watcher.set_backend(configparser::watch::WatchBackend::Notify)?;
+    let config = watcher.shared();
+    let handle = watcher.spawn(Duration::from_millis(5));
+    // Replace the file atomically so the watcher thread never sees it half-written.
+    let staged = dir.join(format!("configparser_watch_local_{}.ini.new", id));
+    std::fs::write(&staged, "[server]\nport = 443\n")?;
+    std::fs::rename(&staged, &local)?;
+    updates.recv_timeout(Duration::from_secs(5))??;
+    assert_eq!(config.read().unwrap().get("server", "port").unwrap(), "443");
+    handle.stop();
+
+    std::fs::remove_file(&base)?;
+    std::fs::remove_file(&local)?;
+    Ok(())
+}
+
+#[test]
+fn structural_diff() -> Result<(), Box<dyn Error>> {
+    use configparser::diff::{KeyChange, SectionChange};
+
+    let mut old = Ini::new();
+    old.load("tests/test.ini")?;
+    let mut new = Ini::new();
+    new.load("tests/test.ini")?;
+    new.load_and_append("tests/test_more.ini")?;
+    new.remove_section("spacing");
+
+    let diff = old.diff(&new);
+    assert!(old.diff(&old).is_empty());
+    let spacing = diff.sections.iter().find(|s| s.name == "spacing").unwrap();
+    assert_eq!(spacing.change, SectionChange::Removed);
+    let topsecret = diff
+        .sections
+        .iter()
+        .find(|s| s.name == "topsecret")
+        .unwrap();
+    let kfc = topsecret.keys.iter().find(|k| k.key == "kfc").unwrap();
+    assert_eq!(
+        kfc.change,
+        KeyChange::Changed {
+            old: Some("the secret herb is orega-".to_owned()),
+            new: Some("redacted".to_owned())
+        }
+    );
+    assert!(diff.to_string().contains("- [spacing]\n"));
+
+    // Every line of a multiline value is signed.
+    let mut multiline = Ini::new();
+    multiline.set_multiline(true);
+    multiline.read(String::from("[s]\nkey = one\n  two"))?;
+    let mut changed = multiline.clone();
+    changed.setstr("s", "key", Some("one\nthree"));
+    assert_eq!(
+        multiline.diff(&changed).to_string(),
+        "  [s]\n- key = one\n-     two\n+ key = one\n+     three\n"
+    );
+
+    // An empty value has no trailing space.
+    let mut emptied = multiline.clone();
+    emptied.setstr("s", "key", Some(""));
+    assert_eq!(
+        multiline.diff(&emptied).to_string(),
+        "  [s]\n- key = one\n-     two\n+ key =\n"
+    );
+
+    // Case-sensitive objects see differently cased names as different sections, others don't.
+    let mut cs_old = Ini::new_cs();
+    cs_old.read(String::from("[Section]\nkey=value"))?;
+    let mut cs_new = Ini::new_cs();
+    cs_new.read(String::from("[section]\nkey=value"))?;
+    assert_eq!(cs_old.diff(&cs_new).sections.len(), 2);
+    let mut preserving = Ini::new_case_preserving();
+    preserving.read(String::from("[Section]\nKey=value"))?;
+    let mut ci_new = Ini::new();
+    ci_new.read(String::from("[SECTION]\nKEY=value"))?;
+    assert!(preserving.diff(&ci_new).is_empty());
+
+    // Patching reproduces the new configuration and the reversed diff undoes it.
+    let mut patched = old.clone();
+    patched.patch(&diff)?;
+    assert_eq!(patched.get_map_ref(), new.get_map_ref());
+    patched.patch(&diff.reversed())?;
+    assert_eq!(patched.get_map_ref(), old.get_map_ref());
+
+    // A patch whose old values don't match is rejected without changing anything.
+    let mut conflicting = new.clone();
+    conflicting.setstr("topsecret", "KFC", Some("other"));
+    let before = conflicting.clone();
+    assert!(conflicting.patch(&diff.reversed()).is_err());
+    assert_eq!(conflicting, before);
+    Ok(())
+}
+
+#[test]
+fn three_way_merge() -> Result<(), Box<dyn Error>> {
+    use configparser::merge::Merge;
+
+    let mut base = Ini::new();
+    base.read(String::from(
+        "[server]
+        port = 80
+        workers = 4
+        [log]
+        level = info
+        [legacy]
+        mode = on
+        [cache]
+        size = 10",
+    ))?;
+    // Local edits: tuned workers, removed the cache section, changed the log level.
+    let mut ours = base.clone();
+    ours.setstr("server", "workers", Some("16"));
+    ours.remove_section("cache");
+    ours.setstr("log", "level", Some("debug"));
+    // Vendor update: new timeout, removed the legacy section, changed the cache size and log level.
+    let mut theirs = Ini::new();
+    theirs.read(String::from(
+        "[SERVER]
+        PORT = 80
+        workers = 4
+        timeout = 30
+        [log]
+        level = warn
+        [cache]
+        size = 20",
+    ))?;
+
+    let merge = Merge::three_way(&base, &ours, &theirs);
+    assert!(!merge.is_clean());
+    assert_eq!(merge.merged.get("server", "workers").unwrap(), "16");
+    assert_eq!(merge.merged.get("server", "timeout").unwrap(), "30");
+    assert!(!merge.merged.sections().contains(&"legacy".to_owned()));
+    // Conflicts keep our version.
+    assert_eq!(merge.merged.get("log", "level").unwrap(), "debug");
+    assert_eq!(merge.merged.get("cache", "size"), None);
+    let conflicts: Vec<String> = merge.conflicts.iter().map(|c| c.to_string()).collect();
+    assert_eq!(conflicts.len(), 2);
+    assert!(conflicts.contains(&r#"cache:size: base "10", ours missing, theirs "20""#.to_owned()));
+    assert!(
+        conflicts.contains(&r#"log:level: base "info", ours "debug", theirs "warn""#.to_owned())
+    );
+
+    let marked = merge.writes_marked().replace("\r\n", "\n");
+    assert!(marked.contains(
+        "[cache]\n<<<<<<< ours\n||||||| base\nsize=10\n=======\nsize=20\n>>>>>>> theirs\n"
+    ));
+
+    // Conflicting versions are written with the formatting options and escapes of the merged configuration.
+    let mut escaped = Ini::new();
+    escaped.set_escapes(configparser::ini::Escapes::Backslash);
+    escaped.read(String::from("[s]\npath = a\nlonger_key = b"))?;
+    let mut ours_escaped = escaped.clone();
+    ours_escaped.setstr("s", "path", Some("C:\\new ; x"));
+    let mut theirs_escaped = escaped.clone();
+    theirs_escaped.setstr("s", "path", Some("other"));
+    let mut write_options = configparser::ini::WriteOptions::default();
+    write_options.space_around_delimiters = true;
+    write_options.align_delimiters = true;
+    let marked = Merge::three_way(&escaped, &ours_escaped, &theirs_escaped)
+        .pretty_writes_marked(&write_options)
+        .replace("\r\n", "\n");
+    assert!(
+        marked
+            .contains("<<<<<<< ours\npath       = C:\\\\new \\; x\n||||||| base\npath       = a\n")
+    );
+    assert!(marked.contains("longer_key = b\n"));
+
+    // Merging identical changes or no changes at all is clean.
+    let clean = Merge::three_way(&base, &ours, &ours);
+    assert!(clean.is_clean());
+    assert_eq!(clean.merged, ours);
+    let clean = Merge::three_way(&base, &base, &theirs);
+    assert!(clean.is_clean());
+    assert_eq!(clean.merged.get_map_ref(), theirs.get_map_ref());
+    Ok(())
+}
+
+#[test]
+fn dialect_presets() -> Result<(), Box<dyn Error>> {
+    use configparser::dialect::Dialect;
+    use configparser::ini::{Escapes, KeyStrategy};
+
+    for dialect in Dialect::ALL {
+        assert_eq!(dialect.to_string().parse::<Dialect>(), Ok(dialect));
+    }
+    assert_eq!(
+        "ini".parse::<Dialect>(),
+        Err(String::from("unknown dialect \"ini\""))
+    );
+
+    // Each preset parses a typical file of its program.
+    let mut python = Ini::with_dialect(Dialect::Python);
+    python.read(String::from(
+        "[DEFAULT]\ntimeout = 5\n[Server]\nHosts = a\n  b ; not a comment\n",
+    ))?;
+    assert_eq!(
+        python.get("Server", "hosts").unwrap(),
+        "a\nb ; not a comment"
+    );
+    assert_eq!(python.get("Server", "timeout").unwrap(), "5");
+    assert!(!python.sections().contains(&String::from("server")));
+    let mut loaded = Ini::new();
+    loaded.load_defaults(Dialect::Python.defaults());
+    assert_eq!(loaded.defaults(), Dialect::Python.defaults());
+
+    let mut php = Ini::with_dialect(Dialect::Php);
+    php.read(String::from(
+        "[db]\ndsn = \"mysql:host=x;port=3306\" ; comment\nDebug = off\n# not a comment",
+    ))?;
+    assert_eq!(php.get("db", "dsn").unwrap(), "mysql:host=x;port=3306");
+    assert_eq!(php.getboolcoerce("db", "Debug")?, Some(false));
+    assert_eq!(php.get("db", "debug"), None);
+    assert!(php.get_map_ref()["db"].contains_key("# not a comment"));
+
+    let mut windows = Ini::with_dialect(Dialect::Windows);
+    windows.read(String::from("[Paths]\nHome=C:\\Users ; kept\nhome=D:\\\n"))?;
+    assert_eq!(windows.get("paths", "HOME").unwrap(), "C:\\Users ; kept");
+    assert!(windows.writes().contains("Home=C:\\Users ; kept"));
+
+    let mut mysql = Ini::with_dialect(Dialect::MySql);
+    mysql.read(String::from(
+        "[MySQLd]\nsocket = \"/tmp/my sock\" # comment\nbind-address = 0.0.0.0\nbind-address = ::\ninit-connect = SET NAMES utf8; # statement\n",
+    ))?;
+    assert_eq!(mysql.get("mysqld", "socket").unwrap(), "/tmp/my sock");
+    assert_eq!(mysql.get("mysqld", "bind-address").unwrap(), "::");
+    assert_eq!(
+        mysql.get("mysqld", "init-connect").unwrap(),
+        "SET NAMES utf8;"
+    );
+
+    let mut samba = Ini::with_dialect(Dialect::Samba);
+    samba.read(String::from(
+        "[global]\nGuest OK = no\n[Public]\npath = /srv/public ; kept\n",
+    ))?;
+    assert_eq!(samba.get("public", "guest ok").unwrap(), "no");
+    assert_eq!(samba.get("public", "path").unwrap(), "/srv/public ; kept");
+
+    let mut systemd = Ini::with_dialect(Dialect::Systemd);
+    systemd.read(String::from(
+        "[Service]\nExecStart=/bin/a\nExecStart=/bin/b\n# comment\n",
+    ))?;
+    assert_eq!(
+        systemd.get("Service", "ExecStart").unwrap(),
+        "/bin/a\n/bin/b"
+    );
+    assert_eq!(systemd.get("service", "ExecStart"), None);
+
+    // Escapes and quotes survive a round trip.
+    for (dialect, value) in [
+        (Dialect::Git, " padded ; with \"quotes\" and \\ "),
+        (Dialect::Desktop, " line\nbreak \\ # "),
+    ] {
+        let mut config = Ini::with_dialect(dialect);
+        config.set("section", "key", Some(value.to_owned()));
+        let written = config.writes();
+        let mut parsed = Ini::with_dialect(dialect);
+        parsed.read(written.clone())?;
+        assert_eq!(
+            parsed.get("section", "key").as_deref(),
+            Some(value),
+            "{}",
+            written
+        );
+    }
+    let mut git = Ini::with_dialect(Dialect::Git);
+    git.set("alias", "co", Some(String::from("checkout ; echo")));
+    assert_eq!(git.writes(), "[alias]\nco=\"checkout ; echo\"\n");
+
+    // The new options also work on their own.
+    let mut config = Ini::new();
+    config.set_duplicate_keys(KeyStrategy::KeepFirst);
+    config.set_multiline(true);
+    config.read(String::from(
+        "[a]\nkey = 1\nkey = 2\n  continued\nother = 3",
+    ))?;
+    assert_eq!(config.get("a", "key").unwrap(), "1");
+    assert_eq!(config.get("a", "other").unwrap(), "3");
+    config.set_escapes(Escapes::Backslash);
+    config.read(String::from(
+        "[a]\nkey = \\;not a comment\\s ; comment\npath = C:\\dir\n",
+    ))?;
+    assert_eq!(config.get("a", "key").unwrap(), ";not a comment ");
+    assert_eq!(config.get("a", "path").unwrap(), "C:\\dir");
+    Ok(())
+}
+
+#[test]
+fn systemd_units() -> Result<(), Box<dyn Error>> {
+    use configparser::systemd::{Unit, expand_specifiers, name_specifiers};
+
+    let dir = std::env::temp_dir().join(format!("configparser_systemd_{}", std::process::id()));
+    let (etc, lib) = (dir.join("etc"), dir.join("lib"));
+    std::fs::create_dir_all(etc.join("web@blue.service.d"))?;
+    std::fs::create_dir_all(lib.join("web@.service.d"))?;
+    std::fs::create_dir_all(lib.join("service.d"))?;
+    std::fs::write(
+        lib.join("web@.service"),
+        "[Unit]\nDescription=Web %i\n\n[Service]\n# the server\nExecStart=/usr/bin/web \\\n  # a comment inside\n  --name %i\nEnvironment=A=1\nEnvironment=B=2\n",
+    )?;
+    std::fs::write(
+        lib.join("web@.service.d/10-env.conf"),
+        "[Service]\nEnvironment=\nEnvironment=C=3\n",
+    )?;
+    std::fs::write(
+        lib.join("service.d/20-limits.conf"),
+        "[Service]\nLimitNOFILE=1024\n",
+    )?;
+    std::fs::write(
+        etc.join("web@blue.service.d/20-limits.conf"),
+        "[Service]\nLimitNOFILE=4096\nenvironment=lowercase\n",
+    )?;
+    let unit = Unit::load_from_dirs("web@blue.service", &[&etc, &lib])?;
+    assert_eq!(
+        unit.get_all("Service", "ExecStart"),
+        ["/usr/bin/web    --name %i"]
+    );
+    assert_eq!(unit.get_all("Service", "Environment"), ["C=3"]);
+    assert_eq!(unit.get("Service", "environment"), Some("lowercase"));
+    assert_eq!(unit.get("Service", "LimitNOFILE"), Some("4096"));
+    let description = unit.get("Unit", "Description").unwrap();
+    assert_eq!(
+        expand_specifiers(description, &name_specifiers("web@blue.service"))?,
+        "Web blue"
+    );
+    assert_eq!(
+        Unit::load_from_dirs("api.service", &[&etc, &lib]),
+        Err(String::from("couldn't find unit api.service"))
+    );
+
+    // Resets are written back, so a drop-in round-trips.
+    let mut dropin = Unit::new();
+    dropin.read(String::from(
+        "[Service]\nEnvironment=A=1\nEnvironment=\nEnvironment=B=%%2\n",
+    ))?;
+    assert_eq!(dropin.get_all("Service", "Environment"), ["B=%%2"]);
+    assert_eq!(
+        dropin.writes(),
+        "[Service]\nEnvironment=\nEnvironment=B=%%2\n"
+    );
+    dropin.set("Service", "Environment", "D=4");
+    dropin.add("Service", "Environment", "E=5");
+    assert_eq!(
+        dropin.writes(),
+        "[Service]\nEnvironment=D=4\nEnvironment=E=5\n"
+    );
+    assert!(dropin.remove("Service", "Environment"));
+    assert!(dropin.get("Service", "Environment").is_none());
+
+    let path = dir.join("written.service");
+    dropin.reset("Service", "ExecStart");
+    dropin.write(&path)?;
+    assert_eq!(Unit::load(&path)?, dropin);
+
+    assert_eq!(
+        Unit::new().read(String::from("Description=x")),
+        Err(String::from("line 1:1: Assignment outside of section"))
+    );
+    assert_eq!(
+        Unit::new().read(String::from("[Unit]\n\nWants")),
+        Err(String::from("line 3:1: Missing '=' after key Wants"))
+    );
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn desktop_entries() -> Result<(), Box<dyn Error>> {
+    use configparser::desktop::DesktopEntry;
+    use configparser::schema::ViolationKind;
+
+    let mut entry = DesktopEntry::new();
+    entry.read(String::from(
+        "# launcher\n[Desktop Entry]\nType=Application\nName=Notes\nName[de_DE]=Notizen\nName[de]=Notizblock\n\
+         Keywords=text;to\\;do;\nKeywords[de]=Text;Aufgaben\nExec=notes %U\nPath=C:\\\\notes\\q\nTerminal=false\n\n\
+         [Desktop Action new]\nName=New Note\n",
+    ))?;
+    assert_eq!(
+        entry
+            .get_localized("Desktop Entry", "Name", "de_DE@euro")
+            .unwrap(),
+        "Notizen"
+    );
+    assert_eq!(
+        entry
+            .get_localized("Desktop Entry", "Name", "de_CH")
+            .unwrap(),
+        "Notizblock"
+    );
+    assert_eq!(
+        entry.get_localized("Desktop Entry", "Name", "fr").unwrap(),
+        "Notes"
+    );
+    assert_eq!(
+        entry.localized_key("Desktop Entry", "Name", "de_DE.UTF-8"),
+        Some(String::from("Name[de_DE]"))
+    );
+    assert_eq!(
+        entry.get_list("Desktop Entry", "Keywords").unwrap(),
+        ["text", "to;do"]
+    );
+    assert_eq!(
+        entry
+            .get_localized_list("Desktop Entry", "Keywords", "de_AT")
+            .unwrap(),
+        ["Text", "Aufgaben"]
+    );
+    assert_eq!(entry.get("Desktop Entry", "Path").unwrap(), "C:\\notes\\q");
+    assert_eq!(entry.getbool("Desktop Entry", "Terminal"), Ok(Some(false)));
+    assert_eq!(entry.get("Desktop Entry", "terminal"), None);
+    assert_eq!(entry.groups().len(), 2);
+    assert!(entry.validate().is_ok());
+
+    // Setters escape what the getters unescape, and written entries read back the same.
+    entry.set("Desktop Entry", "Comment", "  Take\tnotes\n");
+    entry.set_localized("Desktop Entry", "Comment", "de", "Notizen");
+    entry.set_list(
+        "Desktop Entry",
+        "MimeType",
+        &["text/plain", "text/markdown"],
+    );
+    assert_eq!(
+        entry.as_ini().get("Desktop Entry", "Comment").unwrap(),
+        "\\s\\sTake\\tnotes\\n"
+    );
+    assert_eq!(
+        entry.get("Desktop Entry", "Comment").unwrap(),
+        "  Take\tnotes\n"
+    );
+    assert!(entry.writes().starts_with("[Desktop Entry]\n"));
+    let path = std::env::temp_dir().join(format!("configparser_{}.desktop", std::process::id()));
+    entry.write(&path)?;
+    assert_eq!(DesktopEntry::load(&path)?, entry);
+    std::fs::remove_file(&path)?;
+    assert_eq!(
+        entry.remove("Desktop Entry", "Exec"),
+        Some(String::from("notes %U"))
+    );
+
+    let violations = entry.validate().unwrap_err();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].key.as_deref(), Some("Exec"));
+    assert_eq!(violations[0].kind, ViolationKind::MissingKey);
+    entry.set("Desktop Entry", "DBusActivatable", "true");
+    entry.set("Desktop Entry", "Type", "Plugin");
+    let violations = entry.validate().unwrap_err();
+    assert_eq!(
+        violations[0].kind,
+        ViolationKind::InvalidValue(String::from("unknown type Plugin"))
+    );
+
+    entry.read(String::from("[Desktop Action new]\nName=New"))?;
+    let violations = entry.validate().unwrap_err();
+    assert_eq!(violations[0].kind, ViolationKind::MissingSection);
+    assert_eq!(
+        entry.read(String::from("[Desktop Entry]\nName=A\nName=B")),
+        Err(String::from(
+            "line 3:1: Key Name is already set in section Desktop Entry"
+        ))
+    );
+    assert_eq!(
+        entry.read(String::from("[Desktop Entry]\nHidden")),
+        Err(String::from("line 2:1: Missing '=' after key Hidden"))
+    );
+    Ok(())
+}
+
+#[test]
+fn java_properties() -> Result<(), Box<dyn Error>> {
+    use configparser::properties::KeyLayout;
+
+    let text = "# comment\n! comments don't continue \\\n  key.kept = not a comment\n\n   \
+                app.name=Demo\\u0020App\napp.port : 8080\napp.path c:\\\\temp\\\\\nempty\n\
+                list = one, \\\n       two, \\\n       three\n\\:odd\\ key = x=y\nsmile=\\uD83D\\uDE00\n";
+    let mut config = Ini::new_cs();
+    let map = config.read_properties(String::from(text), KeyLayout::Flat)?;
+    assert_eq!(map.len(), 1);
+    assert_eq!(config.get("default", "app.name").unwrap(), "Demo App");
+    assert_eq!(config.getuint("default", "app.port")?, Some(8080));
+    assert_eq!(config.get("default", "app.path").unwrap(), "c:\\temp\\");
+    assert_eq!(config.get("default", "empty").unwrap(), "");
+    assert_eq!(config.get("default", "list").unwrap(), "one, two, three");
+    assert_eq!(config.get("default", ":odd key").unwrap(), "x=y");
+    assert_eq!(config.get("default", "smile").unwrap(), "\u{1F600}");
+    assert_eq!(config.get("default", "key.kept").unwrap(), "not a comment");
+
+    let mut dotted = Ini::new_cs();
+    dotted.read_properties(String::from(text), KeyLayout::Dotted)?;
+    assert_eq!(dotted.getuint("app", "port")?, Some(8080));
+    assert_eq!(dotted.get("default", "list").unwrap(), "one, two, three");
+
+    // Written files read back the same with either layout.
+    let path = std::env::temp_dir().join(format!("configparser_{}.properties", std::process::id()));
+    dotted.set("app", "motd", Some(String::from(" #1 caf\u{e9}\n")));
+    dotted.write_properties(&path)?;
+    let written = std::fs::read_to_string(&path)?;
+    assert!(written.contains("app.motd=\\ \\#1 caf\\u00E9\\n\n"));
+    assert!(written.contains("\\:odd\\ key=x\\=y\n"));
+    let mut reread = Ini::new_cs();
+    reread.load_properties(&path, KeyLayout::Dotted)?;
+    assert_eq!(reread.get_map_ref(), dotted.get_map_ref());
+    std::fs::remove_file(&path)?;
+
+    assert_eq!(
+        config.read_properties(String::from("a=1\nb=\\u12"), KeyLayout::Flat),
+        Err(String::from("line 2:3: Malformed \\uXXXX escape"))
+    );
+    assert_eq!(
+        config.read_properties(String::from("a=1\nc = x\\\n    y\\u1"), KeyLayout::Flat),
+        Err(String::from("line 3:6: Malformed \\uXXXX escape"))
+    );
+    assert_eq!(config.get("default", "a"), None);
+    Ok(())
+}
+
+#[test]
+fn dotenv_files() -> Result<(), Box<dyn Error>> {
+    let mut config = Ini::new_cs();
+    config.read(String::from("[app]\nPORT=80\nNAME=demo"))?;
+    config.read_dotenv(
+        String::from(
+            "# overrides\nexport PORT=8080\n  DEBUG = true  # inline comment\nURL=http://host:${PORT}/$NAME\n\
+             HASH=a#b\nEMPTY=\nFLAG\nMODE=${CONFIGPARSER_UNSET_VAR:-dev}\nLEVEL=${DEBUG:-info}\n\
+             KEEP=${EMPTY-kept}\nLITERAL='${PORT} \\n'\nESCAPED=\"tab\\there \\$PORT \\\"q\\\"\"\n\
+             CERT=\"line one\nline two\"\n",
+        ),
+        "app",
+    )?;
+    assert_eq!(config.getuint("app", "PORT")?, Some(8080));
+    assert_eq!(config.getbool("app", "DEBUG")?, Some(true));
+    assert_eq!(config.get("app", "URL").unwrap(), "http://host:8080/demo");
+    assert_eq!(config.get("app", "HASH").unwrap(), "a#b");
+    assert_eq!(config.get("app", "EMPTY").unwrap(), "");
+    assert_eq!(config.get_map_ref()["app"]["FLAG"], None);
+    assert_eq!(config.get("app", "MODE").unwrap(), "dev");
+    assert_eq!(config.get("app", "LEVEL").unwrap(), "true");
+    assert_eq!(config.get("app", "KEEP").unwrap(), "");
+    assert_eq!(config.get("app", "LITERAL").unwrap(), "${PORT} \\n");
+    assert_eq!(
+        config.get("app", "ESCAPED").unwrap(),
+        "tab\there $PORT \"q\""
+    );
+    assert_eq!(config.get("app", "CERT").unwrap(), "line one\nline two");
+
+    // Written sections read back unchanged.
+    let path = std::env::temp_dir().join(format!("configparser_{}.env", std::process::id()));
+    config.set("app", "QUOTE", Some(String::from("it's $5\n")));
+    config.write_dotenv(&path, "app")?;
+    let written = std::fs::read_to_string(&path)?;
+    assert!(written.contains("PORT=8080\n"));
+    assert!(written.contains("LITERAL='${PORT} \\n'\n"));
+    assert!(written.contains("QUOTE=\"it's \\$5\\n\"\n"));
+    let mut reread = Ini::new_cs();
+    reread.load_dotenv(&path, "app")?;
+    assert_eq!(reread.get_map_ref()["app"], config.get_map_ref()["app"]);
+    std::fs::remove_file(&path)?;
+    assert!(config.write_dotenv(&path, "missing").is_err());
+
+    let mut config = Ini::new();
+    config.read(String::from(
+        "[db]\nhost = db.local\n[cache.redis]\nttl = 60",
+    ))?;
+    let prefixed = config.writes_dotenv_prefixed();
+    assert!(prefixed.contains("DB_HOST=db.local\n"));
+    assert!(prefixed.contains("CACHE_REDIS_TTL=60\n"));
+
+    assert_eq!(
+        config.read_dotenv(String::from("A=1\nB='open\nC=2"), "env"),
+        Err(String::from("line 2:3: Unterminated quoted value"))
+    );
+    assert_eq!(
+        config.read_dotenv(String::from("A=\"x\" y"), "env"),
+        Err(String::from(
+            "line 1:7: Unexpected characters after the closing quote"
+        ))
+    );
+    assert_eq!(
+        config.read_dotenv(String::from("A=1\nB=\"x\ny\" z"), "env"),
+        Err(String::from(
+            "line 3:4: Unexpected characters after the closing quote"
+        ))
+    );
+    assert_eq!(
+        config.read_dotenv(String::from("MY VAR=1"), "env"),
+        Err(String::from("line 1:1: Invalid variable name \"MY VAR\""))
+    );
+    assert!(config.section("env").is_none());
+    Ok(())
+}
+
+#[test]
+fn registry_files() -> Result<(), Box<dyn Error>> {
+    use configparser::registry::{RegFile, RegValue, RegVersion};
+
+    let text = "Windows Registry Editor Version 5.00\r\n\r\n; exported\r\n\
+                [HKEY_LOCAL_MACHINE\\SOFTWARE\\Demo]\r\n@=\"Demo\"\r\n\"a=b\"=\"x\\\\y\"\r\n\
+                \"Count\"=dword:0000002a\r\n\"Big\"=hex(b):00,00,00,00,01,00,00,00\r\n\
+                \"Names\"=hex(7):61,00,00,00,62,00,63,00,00,00,00,00\r\n\
+                \"Empty\"=hex(0):\r\n\"Old\"=-\r\n\r\n[-HKEY_LOCAL_MACHINE\\SOFTWARE\\Gone]\r\n";
+    let mut bytes = vec![0xff, 0xfe];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    let file = RegFile::from_bytes(&bytes)?;
+    assert_eq!(file.version(), RegVersion::Version5);
+    let key = "HKEY_LOCAL_MACHINE\\SOFTWARE\\Demo";
+    assert_eq!(
+        file.get(key, "@"),
+        Some(RegValue::String(String::from("Demo")))
+    );
+    assert_eq!(
+        file.get(key, "A=B"),
+        Some(RegValue::String(String::from("x\\y")))
+    );
+    assert_eq!(file.get(key, "count"), Some(RegValue::Dword(42)));
+    assert_eq!(file.get(key, "Big"), Some(RegValue::Qword(1 << 32)));
+    assert_eq!(
+        file.get(key, "Names"),
+        Some(RegValue::MultiString(vec![
+            String::from("a"),
+            String::from("bc")
+        ]))
+    );
+    assert_eq!(
+        file.get(key, "Empty"),
+        Some(RegValue::Raw {
+            kind: 0,
+            data: Vec::new()
+        })
+    );
+    assert_eq!(file.get(key, "Old"), Some(RegValue::Delete));
+    assert_eq!(file.values(key).map(|values| values.len()), Some(7));
+    assert!(
+        file.keys()
+            .contains(&String::from("-HKEY_LOCAL_MACHINE\\SOFTWARE\\Gone"))
+    );
+
+    // Long hex lists are wrapped like regedit does, and files read back the same.
+    let mut file = RegFile::new(RegVersion::Regedit4);
+    let key = "HKEY_CURRENT_USER\\Software\\Demo";
+    file.set(key, "Blob", &RegValue::Binary((0..40).collect()));
+    file.set(
+        key,
+        "Path",
+        &RegValue::ExpandString(String::from("%HOME%\\caf\u{e9}")),
+    );
+    file.set(key, "Name", &RegValue::String(String::from("caf\u{e9}")));
+    file.set(key, "Temp", &RegValue::Dword(1));
+    assert!(file.remove(key, "Temp"));
+    file.delete_key("HKEY_CURRENT_USER\\Software\\Old");
+    let written = file.writes();
+    assert!(written.starts_with("REGEDIT4\r\n\r\n"));
+    assert!(written.contains("[-HKEY_CURRENT_USER\\Software\\Old]\r\n"));
+    assert!(written.lines().all(|line| line.len() <= 80));
+    assert!(written.contains(
+        "\"Blob\"=hex:00,01,02,03,04,05,06,07,08,09,0a,0b,0c,0d,0e,0f,10,11,12,13,14,15,\\\r\n  16,"
+    ));
+    let path = std::env::temp_dir().join(format!("configparser_{}.reg", std::process::id()));
+    file.write(&path)?;
+    let bytes = std::fs::read(&path)?;
+    assert!(bytes.contains(&0xe9));
+    let reread = RegFile::load(&path)?;
+    assert_eq!(reread, file);
+    assert_eq!(
+        reread.get(key, "Path"),
+        Some(RegValue::ExpandString(String::from("%HOME%\\caf\u{e9}")))
+    );
+    std::fs::remove_file(&path)?;
+
+    // Registry keys keep their file order, so deletions stay before the keys written after them.
+    let mut text = String::from("REGEDIT4\r\n");
+    for index in 0..20 {
+        text.push_str(&format!(
+            "\r\n[-HKEY_CURRENT_USER\\K{0}]\r\n\r\n[HKEY_CURRENT_USER\\K{0}]\r\n\"a\"=\"b\"\r\n",
+            index
+        ));
+    }
+    let mut file = RegFile::read(&text)?;
+    assert_eq!(file.writes(), text + "\r\n");
+    file.delete_key("HKEY_CURRENT_USER\\K0");
+    file.set("HKEY_CURRENT_USER\\K0", "c", &RegValue::Dword(1));
+    let keys = file.keys();
+    assert_eq!(keys[0], "-HKEY_CURRENT_USER\\K0");
+    assert_eq!(keys.last().unwrap(), "HKEY_CURRENT_USER\\K0");
+    assert_eq!(keys.len(), 40);
+
+    assert_eq!(
+        RegFile::read("[HKEY_CURRENT_USER]"),
+        Err(String::from(
+            "line 1:1: Expected a REGEDIT4 or Windows Registry Editor Version 5.00 header"
+        ))
+    );
+    assert_eq!(
+        RegFile::read("REGEDIT4\n\"a\"=\"b\""),
+        Err(String::from("line 2:1: Value outside of a registry key"))
+    );
+    assert_eq!(
+        RegFile::read("REGEDIT4\n[HKEY_CURRENT_USER]\n\n\"a\"=dword:xyz"),
+        Err(String::from("line 4:1: Invalid dword dword:xyz"))
+    );
+    assert_eq!(
+        RegFile::read("REGEDIT4\n[HKEY_CURRENT_USER]\nname=1"),
+        Err(String::from("line 3:1: Expected \"name\"=data or @=data"))
+    );
+    Ok(())
+}
+
+#[test]
+fn append_strategies() -> Result<(), Box<dyn Error>> {
+    use configparser::ini::{AppendOptions, KeyStrategy, SectionStrategy};
+
+    let base = || -> Result<Ini, String> {
+        let mut config = Ini::new();
+        config.load("tests/test.ini")?;
+        Ok(config)
+    };
+    let with = |keys, sections| AppendOptions::new_with_params(keys, sections);
+
+    // The default options behave exactly like load_and_append.
+    let mut expected = base()?;
+    expected.load_and_append("tests/test_more.ini")?;
+    let mut config = base()?;
+    let report = config.load_and_append_with("tests/test_more.ini", &AppendOptions::new())?;
+    assert_eq!(config, expected);
+    let kfc = report.overridden.iter().find(|o| o.key == "kfc").unwrap();
+    assert_eq!(kfc.section, "topsecret");
+    assert_eq!(kfc.previous.as_deref(), Some("the secret herb is orega-"));
+    assert_eq!(kfc.value.as_deref(), Some("redacted"));
+    assert!(report.replaced_sections.is_empty());
+
+    let mut config = base()?;
+    let report = config.load_and_append_with(
+        "tests/test_more.ini",
+        &with(KeyStrategy::KeepFirst, SectionStrategy::Merge),
+    )?;
+    assert_eq!(
+        config.get("topsecret", "KFC").unwrap(),
+        "the secret herb is orega-"
+    );
+    assert!(report.overridden.iter().all(|o| o.value == o.previous));
+
+    let mut config = base()?;
+    let before = config.clone();
+    let error = config.load_and_append_with(
+        "tests/test_more.ini",
+        &with(KeyStrategy::Error, SectionStrategy::Merge),
+    );
+    assert!(
+        error
+            .unwrap_err()
+            .starts_with("couldn't append tests/test_more.ini: ")
+    );
+    assert_eq!(config, before);
+    // Identical values are not conflicts.
+    config.load_and_append_with(
+        "tests/test.ini",
+        &with(KeyStrategy::Error, SectionStrategy::Merge),
+    )?;
+
+    let mut config = base()?;
+    config.read_and_append_with(
+        String::from("[topsecret]\nkfc = more herbs\nNone string"),
+        &with(KeyStrategy::Append, SectionStrategy::Merge),
+    )?;
+    assert_eq!(
+        config.get("topsecret", "KFC").unwrap(),
+        "the secret herb is orega-\nmore herbs"
+    );
+    assert_eq!(config.get("topsecret", "None string"), None);
+
+    let mut config = base()?;
+    let report = config.read_and_append_with(
+        String::from("[spacing]\nonly = key\n[new]\nkey = value"),
+        &with(KeyStrategy::Error, SectionStrategy::Replace),
+    )?;
+    assert_eq!(report.replaced_sections, ["spacing"]);
+    assert_eq!(config.get("spacing", "indented"), None);
+    assert_eq!(config.get("spacing", "only").unwrap(), "key");
+    assert_eq!(config.get("new", "key").unwrap(), "value");
+
+    // Appended names are matched with the stored spelling of case-preserving names.
+    let mut config = Ini::new_case_preserving();
+    config.read(String::from("[Server]\nPort = 80\nHost = a"))?;
+    let report = config.read_and_append_with(
+        String::from("[server]\nport = 90\nHOST = b"),
+        &with(KeyStrategy::KeepFirst, SectionStrategy::Merge),
+    )?;
+    assert_eq!(config.sections(), ["Server"]);
+    assert_eq!(config.section("server").unwrap().len(), 2);
+    assert_eq!(config.get("SERVER", "port").unwrap(), "80");
+    assert_eq!(report.overridden.len(), 2);
+    assert!(report.overridden.iter().all(|o| o.section == "Server"));
+    config.read_and_append(String::from("[SERVER]\nPORT = 90"))?;
+    assert_eq!(
+        config.get_map_ref()["Server"]["Port"].as_deref(),
+        Some("90")
+    );
+    Ok(())
+}
+
+#[test]
+fn schema_validation() -> Result<(), Box<dyn Error>> {
+    use configparser::schema::{
+        KeySchema, Schema, SectionSchema, ValueType, ViolationKind, parse_duration,
+    };
+    use std::time::Duration;
+
+    let schema = Schema::new()
+        .section(
+            SectionSchema::new("server")
+                .required()
+                .key(KeySchema::new("port", ValueType::uint_range(1, 65535)).required())
+                .key(KeySchema::new("debug", ValueType::Bool).default_value("off"))
+                .key(KeySchema::new(
+                    "hosts",
+                    ValueType::list_of(ValueType::Pattern("10.#.*".into()), ','),
+                ))
+                .key(KeySchema::new("timeout", ValueType::Duration))
+                .key(KeySchema::new(
+                    "ratio",
+                    ValueType::Float {
+                        min: Some(0.0),
+                        max: Some(1.0),
+                    },
+                )),
+        )
+        .section(
+            SectionSchema::new("log")
+                .required()
+                .allow_unknown_keys()
+                .key(KeySchema::new("level", ValueType::one_of(["info", "warn"])).required()),
+        )
+        .section(
+            SectionSchema::new("optional").key(KeySchema::new("key", ValueType::String).required()),
+        );
+
+    let valid = "[SERVER]\nport = 8080\nDebug = yes\nhosts = 10.0.0.1, 10.1.2.3\ntimeout = 1m30s\nratio = 0.5\n[log]\nlevel = info\nfile = app.log";
+    let mut config = Ini::new();
+    config.read(valid.to_owned())?;
+    assert_eq!(config.validate(&schema), Ok(()));
+    assert_eq!(
+        parse_duration(&config.get("server", "timeout").unwrap())?,
+        Duration::from_secs(90)
+    );
+
+    let invalid = "[server]\nport = 70000\ndebug = maybe\nhosts = 10.0.0.1, 192.168.0.1\ntimeout = soon\nratio = 2\nextra = 1\n[other]";
+    config.read(invalid.to_owned())?;
+    let violations = config.validate_source(&schema, invalid).unwrap_err();
+    let rendered: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        rendered[..5],
+        [
+            "line 2:8: server:port: 70000 is above the maximum 65535",
+            "line 3:9: server:debug: expected a boolean, found \"maybe\"",
+            "line 4:9: server:hosts: expected a value like \"10.#.*\", found \"192.168.0.1\"",
+            "line 5:11: server:timeout: expected a duration, found \"soon\"",
+            "line 6:9: server:ratio: 2 is above the maximum 1",
+        ]
+    );
+    assert_eq!(violations[5].kind, ViolationKind::MissingSection);
+    assert_eq!(violations[5].line, None);
+    assert!(rendered.contains(&"line 7:1: server:extra: unknown key".to_owned()));
+    assert!(rendered.contains(&"line 8:1: other: unknown section".to_owned()));
+    assert_eq!(violations.len(), 8);
+    let lenient = schema.clone().allow_unknown_sections();
+    assert_eq!(config.validate(&lenient).unwrap_err().len(), 7);
+
+    // Oversized durations are violations rather than overflows.
+    assert!(parse_duration("999999999999999999d").is_err());
+    assert!(parse_duration("18446744073709551615s1s").is_err());
+    config.read(String::from(
+        "[server]\nport = 80\ntimeout = 999999999999999999d\n[log]\nlevel = info",
+    ))?;
+    let violations = config.validate(&schema).unwrap_err();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(
+        violations[0].to_string(),
+        "line 3:11: server:timeout: expected a duration, found \"999999999999999999d\""
+    );
+
+    // Patterns with many wildcards match without backtracking blowups.
+    let pattern = ValueType::Pattern("*a*a*a*a*a*a*a*a*a*a*b".into());
+    let long = "a".repeat(200);
+    config.read(format!(
+        "[server]\nport = 80\nhosts = {long}\n[log]\nlevel = info"
+    ))?;
+    let backtracking = Schema::new().allow_unknown_sections().section(
+        SectionSchema::new("server")
+            .allow_unknown_keys()
+            .key(KeySchema::new("hosts", pattern)),
+    );
+    assert_eq!(config.validate(&backtracking).unwrap_err().len(), 1);
+
+    // Required keys are satisfied by the default section when cascading.
+    let mut config = Ini::new();
+    config.set_cascade_defaults(true);
+    config.read(String::from("port = 80\n[server]\n[log]\nlevel = warn"))?;
+    let server_only = Schema::new()
+        .allow_unknown_sections()
+        .section(schema.sections[0].clone());
+    assert_eq!(config.validate(&server_only), Ok(()));
+    schema.apply_defaults(&mut config);
+    assert_eq!(config.getboolcoerce("server", "debug")?, Some(false));
+    Ok(())
+}
+
+#[test]
+fn schema_sample() -> Result<(), Box<dyn Error>> {
+    use configparser::ini::WriteOptions;
+    use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+
+    let schema = Schema::new()
+        .section(
+            SectionSchema::new("server")
+                .description("The HTTP listener.\nRestart after changes.")
+                .key(
+                    KeySchema::new("port", ValueType::uint_range(1, 65535))
+                        .required()
+                        .description("Port to listen on."),
+                )
+                .key(KeySchema::new("debug", ValueType::Bool).default_value("off")),
+        )
+        .section(SectionSchema::new("log").key(
+            KeySchema::new("level", ValueType::one_of(["info", "warn"])).default_value("info"),
+        ));
+
+    let write_options = WriteOptions::new_with_params(true, 4, 1);
+    let sample = schema.sample(&write_options, false);
+    for line in [
+        "; The HTTP listener.",
+        "; Restart after changes.",
+        "; Port to listen on.",
+        "; Type: unsigned integer (1..=65535) (required)",
+        "; port =",
+        "; Type: boolean",
+        "debug = off",
+        "level = info",
+    ] {
+        assert!(
+            sample.lines().any(|l| l == line),
+            "missing {:?} in {}",
+            line,
+            sample
+        );
+    }
+
+    // Sections and keys follow schema order with or without the indexmap feature.
+    let order: Vec<&str> = sample
+        .lines()
+        .filter(|l| l.starts_with('[') || l.contains(" ="))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            "[server]",
+            "; port =",
+            "debug = off",
+            "[log]",
+            "level = info"
+        ]
+    );
+
+    // The sample reads back with its defaults, and only the keys without one are missing.
+    let mut config = Ini::new();
+    config.read(sample)?;
+    assert_eq!(config.get("log", "level"), Some(String::from("info")));
+    assert_eq!(config.get("server", "port"), None);
+    assert_eq!(config.validate(&schema).unwrap_err().len(), 1);
+
+    schema.write_sample("output_sample.ini", &write_options, true)?;
+    let mut config = Ini::new();
+    config.load("output_sample.ini")?;
+    assert_eq!(config.sections().len(), 2);
+    assert_eq!(config.get("server", "debug"), None);
+    assert!(
+        std::fs::read_to_string("output_sample.ini")?
+            .lines()
+            .any(|l| l == "; debug = off")
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "derive")]
+fn derive_from_ini() -> Result<(), Box<dyn Error>> {
+    use configparser::schema::ViolationKind;
+    use configparser::typed::FromIni;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn default_workers() -> u8 {
+        4
+    }
+
+    fn parse_hosts(value: &str) -> Result<Vec<String>, String> {
+        Ok(value
+            .split(',')
+            .map(|host| host.trim().to_owned())
+            .collect())
+    }
+
+    #[derive(FromIni, Debug, PartialEq)]
+    struct Limits {
+        #[ini(cascade)]
+        timeout: Duration,
+        #[ini(default = "default_workers")]
+        workers: u8,
+    }
+
+    #[derive(FromIni, Debug, PartialEq)]
+    #[ini(section = "server")]
+    struct Server {
+        port: u16,
+        #[ini(rename = "hosts", with = "parse_hosts")]
+        allowed_hosts: Vec<String>,
+        r#type: char,
+        debug: Option<bool>,
+        #[ini(section = "log", cascade = "paths")]
+        file: PathBuf,
+        #[ini(flatten)]
+        limits: Limits,
+        #[ini(skip)]
+        reloads: usize,
+    }
+
+    let mut config = Ini::new();
+    config.read(String::from(
+        "timeout = 30s\n[server]\nport = 8080\nhosts = a, b\ntype = x\n[log]\n[paths]\nfile = /var/log/app.log",
+    ))?;
+    assert_eq!(
+        Server::from_ini(&config),
+        Ok(Server {
+            port: 8080,
+            allowed_hosts: vec![String::from("a"), String::from("b")],
+            r#type: 'x',
+            debug: None,
+            file: PathBuf::from("/var/log/app.log"),
+            limits: Limits {
+                timeout: Duration::from_secs(30),
+                workers: 4,
+            },
+            reloads: 0,
+        })
+    );
+
+    // Every missing or invalid key is reported, including the ones of flattened structs.
+    config.read(String::from(
+        "[server]\nport = 70000\ntype = xy\ndebug = maybe\nworkers = many",
+    ))?;
+    let violations = Server::from_ini(&config).unwrap_err();
+    let rendered: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        rendered,
+        [
+            "line 2:8: server:port: invalid value \"70000\": out of range for u16",
+            "line 1:1: server:hosts: missing required key",
+            "line 3:8: server:type: invalid value \"xy\": expected a single character",
+            "line 4:9: server:debug: invalid value \"maybe\": Unable to parse value into bool at server:debug",
+            "log:file: missing required key",
+            "line 1:1: server:timeout: missing required key",
+            "line 5:11: server:workers: invalid value \"many\": invalid digit found in string",
+        ]
+    );
+    assert_eq!(violations[1].kind, ViolationKind::MissingKey);
+
+    // Floats that don't fit into an f32 are rejected instead of turning into infinity.
+    #[derive(FromIni, Debug, PartialEq)]
+    struct Tuning {
+        scale: f32,
+        limit: f32,
+    }
+
+    config.read(String::from("[tuning]\nscale = 0.5\nlimit = inf"))?;
+    assert_eq!(
+        Tuning::from_ini_section(&config, "tuning"),
+        Ok(Tuning {
+            scale: 0.5,
+            limit: f32::INFINITY,
+        })
+    );
+    config.read(String::from("[tuning]\nscale = 1e300\nlimit = 1"))?;
+    let violations = Tuning::from_ini_section(&config, "tuning").unwrap_err();
+    assert_eq!(
+        violations[0].to_string(),
+        "line 2:9: tuning:scale: invalid value \"1e300\": out of range for f32"
+    );
+    Ok(())
+}
+
+#[test]
+fn edit_document() -> Result<(), Box<dyn Error>> {
+    use configparser::edit::Document;
+    use configparser::ini::IniDefault;
+
+    let mut defaults = IniDefault::default();
+    defaults.multiline = true;
+    let text = "top = 1\r\n[paths]\r\n# search order\r\nsearch = /usr\r\n    /opt\r\nflag\r\n\r\n[Empty]\r\n";
+    let mut document = Document::new(text.to_owned(), defaults.clone())?;
+    assert_eq!(document.sections()?, ["default", "paths", "Empty"]);
+    assert_eq!(document.keys("PATHS")?, ["search", "flag"]);
+    assert!(document.has_section("Paths")? && !document.has_section("missing")?);
+
+    // Multiline values replace all continuation lines, and line endings follow the text.
+    document.set("paths", "search", Some("/srv\n/home"))?;
+    document.set("paths", "flag", Some("on"))?;
+    document.set("empty", "key", None)?;
+    assert_eq!(
+        document.text(),
+        "top = 1\r\n[paths]\r\n# search order\r\nsearch = /srv\r\n    /home\r\nflag = on\r\n\r\n[Empty]\r\nkey\r\n"
+    );
+    let config = document.to_ini()?;
+    assert_eq!(
+        config.get("paths", "search"),
+        Some(String::from("/srv\n/home"))
+    );
+
+    assert!(document.remove_section("default")?);
+    assert!(document.remove_section("empty")?);
+    assert!(!document.remove_key("paths", "missing")?);
+    document.set("paths", "search", None)?;
+    assert_eq!(
+        document.text(),
+        "[paths]\r\n# search order\r\nsearch\r\nflag = on\r\n\r\n"
+    );
+
+    assert!(document.set("paths", "a=b", Some("c")).is_err());
+
+    // Values that read back as they are written are accepted, all others are rejected.
+    let readable = ["a b", "x=y", "/srv\n\n/home", "\\path"];
+    for value in readable {
+        document.set("paths", "value", Some(value))?;
+        assert_eq!(
+            document.to_ini()?.get("paths", "value").as_deref(),
+            Some(value)
+        );
+    }
+    let unreadable = [
+        "a ; b",
+        "a # b",
+        "  padded  ",
+        "a\n",
+        "a\n  b",
+        "a\n[b]",
+        "a\n;b",
+        "a\rb",
+    ];
+    for value in unreadable {
+        assert!(
+            document.set("paths", "other", Some(value)).is_err(),
+            "{:?}",
+            value
+        );
+    }
+    assert!(!document.keys("paths")?.contains(&String::from("other")));
+
+    // With escapes, values are escaped like `Ini` writes them instead.
+    let mut escaping = defaults.clone();
+    escaping.escapes = configparser::ini::Escapes::Backslash;
+    let mut document = Document::new(String::from("[paths]\n"), escaping)?;
+    for value in unreadable {
+        document.set("paths", "other", Some(value))?;
+        assert_eq!(
+            document.to_ini()?.get("paths", "other").as_deref(),
+            Some(value)
+        );
+    }
+    assert!(Document::new(String::from("[broken"), defaults).is_err());
+    Ok(())
+}
+
+#[test]
+fn rendered_reports() -> Result<(), Box<dyn Error>> {
+    use configparser::lint::{Linter, Severity};
+    use configparser::report::{Renderer, Report};
+    use configparser::schema::{Schema, SectionSchema};
+
+    let source = "[server]\n\tport =\teighty\n";
+    let mut config = Ini::new();
+    config.read(source.to_owned())?;
+    let error = config.getint("server", "port").unwrap_err();
+    let report = Report::from_value_error(&config, "server", "port", &error).hint("use a number");
+    assert_eq!(
+        Renderer::new().render(&report, "app.ini", source),
+        "error: invalid value for server:port: invalid digit found in string\n --> app.ini:2:9\n  |\n1 | [server]\n2 |     port =    eighty\n  |               ^^^^^^\n  |\n  = hint: use a number\n"
+    );
+
+    // Errors of the loading functions keep their context, and reports without a line have no snippet.
+    let error = Ini::new()
+        .read(String::from("key = 1\n = 2"))
+        .map_err(|why| format!("couldn't read app.ini: {}", why))
+        .unwrap_err();
+    let report = Report::from_parse_error(&error);
+    assert_eq!(report.message, "couldn't read app.ini: Key cannot be empty");
+    assert_eq!(
+        (report.line, report.column, report.width),
+        (Some(2), Some(2), Some(1))
+    );
+    let schema = Schema::new().section(SectionSchema::new("log").required());
+    let violations = config
+        .validate(&schema.allow_unknown_sections())
+        .unwrap_err();
+    assert_eq!(
+        Renderer::new().render(&Report::from_violation(&violations[0]), "app.ini", source),
+        "error: log: missing required section\n --> app.ini\n  = hint: add a [log] section\n"
+    );
+
+    let diagnostics = Linter::new().lint("[a]\nkey = 1\nkey = 2");
+    let report = Report::from_lint(&diagnostics[0]);
+    assert_eq!(report.severity, Severity::Warning);
+    assert_eq!(
+        Renderer::new()
+            .color(true)
+            .context(0)
+            .render(&report, "x.ini", "[a]\nkey = 1\nkey = 2"),
+        "\x1b[1;33mwarning[duplicate-key]\x1b[0m\x1b[1m: a:key is already set on line 2\x1b[0m\n \x1b[1;34m-->\x1b[0m x.ini:3:1\n  \x1b[1;34m|\x1b[0m\n\x1b[1;34m3\x1b[0m \x1b[1;34m|\x1b[0m key = 2\n  \x1b[1;34m|\x1b[0m \x1b[1;33m^^^^^^^\x1b[0m\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn cli_edits_files() -> Result<(), Box<dyn Error>> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let path = std::env::temp_dir().join(format!("configparser-cli-{}.ini", std::process::id()));
+    std::fs::write(&path, "; settings\n[server]\nport = 80 ; public\n")?;
+    let file = path.to_str().unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_configparser"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["get", file, "SERVER", "port"]);
+    assert_eq!(
+        (output.status.code(), output.stdout),
+        (Some(0), b"80\n".to_vec())
+    );
+    assert_eq!(run(&["get", file, "server", "host"]).status.code(), Some(1));
+    assert_eq!(run(&["get", file, "server"]).status.code(), Some(2));
+
+    assert!(
+        run(&["set", file, "server", "port", "8080"])
+            .status
+            .success()
+    );
+    assert!(
+        run(&["--delimiters", ":", "set", file, "log", "level", "info"])
+            .status
+            .success()
+    );
+    let mut merge = Command::new(env!("CARGO_BIN_EXE_configparser"))
+        .args(["merge", file])
+        .stdin(Stdio::piped())
+        .spawn()?;
+    merge
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"[server]\nworkers = 4\n")?;
+    assert!(merge.wait()?.success());
+    assert_eq!(
+        std::fs::read_to_string(&path)?,
+        "; settings\n[server]\nport = 8080 ; public\nworkers = 4\n\n[log]\nlevel:info\n"
+    );
+
+    assert!(run(&["del", file, "server", "port"]).status.success());
+    let output = run(&["list-keys", file, "server"]);
+    assert_eq!(output.stdout, b"workers\n".to_vec());
+    assert_eq!(run(&["list-keys", file, "missing"]).status.code(), Some(1));
+
+    // Values are escaped for dialects with escapes, so they read back as they were set.
+    for (dialect, value, written) in [
+        ("git", "vim ; -n", "editor=\"vim ; -n\""),
+        ("php", "C:\\new", "editor=C:\\\\new"),
+    ] {
+        std::fs::write(&path, "[core]\n")?;
+        let set = run(&["--dialect", dialect, "set", file, "core", "editor", value]);
+        assert!(set.status.success());
+        assert!(std::fs::read_to_string(&path)?.contains(written));
+        let output = run(&["--dialect", dialect, "get", file, "core", "editor"]);
+        assert_eq!(output.stdout, format!("{}\n", value).into_bytes());
+    }
+
+    std::fs::write(&path, "[broken")?;
+    assert_eq!(run(&["get", file, "a", "b"]).status.code(), Some(3));
+    std::fs::remove_file(&path)?;
+    assert_eq!(run(&["get", file, "a", "b"]).status.code(), Some(4));
+    Ok(())
+}
+
+#[test]
+fn cli_formats_files() -> Result<(), Box<dyn Error>> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let path = std::env::temp_dir().join(format!("configparser-fmt-{}.ini", std::process::id()));
+    std::fs::write(
+        &path,
+        "; settings\n[Server]\nPort:80 ; public\n# bind address\nhost=localhost\n\n\n[log]\nlevel=info\n",
+    )?;
+    let file = path.to_str().unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_configparser-fmt"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let options = ["--space-around-delimiters", "--blank-lines", "1"];
+
+    assert_eq!(
+        run(&[&options[..], &["--check", file]].concat())
+            .status
+            .code(),
+        Some(1)
+    );
+    assert!(run(&[&options[..], &[file]].concat()).status.success());
+    assert_eq!(
+        std::fs::read_to_string(&path)?,
+        "; settings\n[Server]\nPort = 80 ; public\n# bind address\nhost = localhost\n\n[log]\nlevel = info\n"
+    );
+    assert!(
+        run(&[&options[..], &["--check", file]].concat())
+            .status
+            .success()
+    );
+
+    let mut format = Command::new(env!("CARGO_BIN_EXE_configparser-fmt"))
+        .args(["--sort", "--align-delimiters"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    format
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"[b]\nlong=1\nx=2\n[a]\nkey=3\n")?;
+    assert_eq!(
+        format.wait_with_output()?.stdout,
+        b"[a]\nkey=3\n[b]\nlong=1\nx   =2\n".to_vec()
+    );
+
+    std::fs::write(&path, "[broken")?;
+    assert_eq!(run(&[file]).status.code(), Some(3));
+    assert_eq!(run(&["--delimiter", "=="]).status.code(), Some(2));
+    std::fs::remove_file(&path)?;
+    assert_eq!(run(&["--check", file]).status.code(), Some(4));
+    Ok(())
+}
+
+#[test]
+fn lint_rules() -> Result<(), Box<dyn Error>> {
+    use configparser::lint::{Linter, Rule, Severity};
+
+    let text = "port = 1\n\
+        [Server]\n\
+        Port = 80 \n\
+        host: localhost\n\
+        color = #fff\n\
+        url = http://host/#top\n\
+        debug\n\
+        name =\n\
+        host = example.com ; configparser-lint: allow(duplicate-key)\n\
+        ; configparser-lint: allow(valueless-key, empty-value)\n\
+        flag\n\
+        ; configparser-lint: allow(no-such-rule)\n\
+        [broken\n";
+    let diagnostics = Linter::new().lint(text);
+    let found: Vec<(Rule, usize, usize)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.rule, diagnostic.line, diagnostic.column))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (Rule::KeyCase, 3, 1),
+            (Rule::ShadowedKey, 3, 1),
+            (Rule::TrailingWhitespace, 3, 10),
+            (Rule::MixedDelimiters, 4, 5),
+            (Rule::InlineComment, 5, 9),
+            (Rule::InlineComment, 6, 19),
+            (Rule::ValuelessKey, 7, 1),
+            (Rule::EmptyValue, 8, 1),
+            (Rule::InvalidSuppression, 12, 1),
+            (Rule::ParseError, 13, 1),
+        ]
+    );
+    assert_eq!(diagnostics[9].severity, Severity::Error);
+    assert_eq!(
+        diagnostics[1].to_string(),
+        "line 3:1: warning[shadowed-key]: Server:Port overrides the key of the default section on line 1"
+    );
+
+    let linter = Linter::new()
+        .allow(Rule::ShadowedKey)
+        .severity(Rule::KeyCase, Severity::Error);
+    let diagnostics =
+        linter.lint("; configparser-lint: allow-file(key-case)\na = 1\n[s]\nA = 2\na = 3\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, Rule::DuplicateKey);
+    assert_eq!(
+        linter.lint("a = 1\n[s]\nA = 2\n")[0].severity,
+        Severity::Error
+    );
+    assert_eq!("inline-comment".parse::<Rule>(), Ok(Rule::InlineComment));
+
+    // Repeated keys follow the strategy of the defaults.
+    let repeated = "[remote]\nfetch = a\nfetch = b\n";
+    let mut defaults = configparser::ini::IniDefault::default();
+    defaults.duplicate_keys = configparser::ini::KeyStrategy::Append;
+    assert!(
+        Linter::new_from_defaults(defaults.clone())
+            .lint(repeated)
+            .is_empty()
+    );
+    defaults.duplicate_keys = configparser::ini::KeyStrategy::KeepFirst;
+    assert_eq!(
+        Linter::new_from_defaults(defaults).lint(repeated)[0].message,
+        "remote:fetch is already set on line 2, so this value is ignored"
+    );
+    Ok(())
+}
+
+#[test]
+fn cli_lints_files() -> Result<(), Box<dyn Error>> {
+    use std::process::Command;
+
+    let path = std::env::temp_dir().join(format!("configparser-lint-{}.ini", std::process::id()));
+    std::fs::write(&path, "[a]\nx = 1\nx = 2\n")?;
+    let file = path.to_str().unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_configparser-lint"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[file]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        format!(
+            "{}:3:1: warning[duplicate-key]: a:x is already set on line 2\n",
+            file
+        )
+    );
+    assert_eq!(
+        run(&["--deny", "duplicate-key", file]).status.code(),
+        Some(1)
+    );
+    assert_eq!(run(&["--deny-warnings", file]).status.code(), Some(1));
+    assert!(
+        run(&["--deny-warnings", "--allow", "duplicate-key", file])
+            .stdout
+            .is_empty()
+    );
+    assert_eq!(
+        run(&["--allow", "no-such-rule", file]).status.code(),
+        Some(2)
+    );
+    std::fs::remove_file(&path)?;
+    assert_eq!(run(&[file]).status.code(), Some(4));
+    Ok(())
+}
+
+#[test]
+fn lsp_scripted_session() -> Result<(), Box<dyn Error>> {
+    use configparser::ini::WriteOptions;
+    use configparser::schema::{KeySchema, Schema, SectionSchema, ValueType};
+    use serde_json::{Value, json};
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let schema_path =
+        std::env::temp_dir().join(format!("configparser-lsp-{}.ini", std::process::id()));
+    Schema::new()
+        .section(
+            SectionSchema::new("server")
+                .description("The HTTP listener.")
+                .key(
+                    KeySchema::new("port", ValueType::uint_range(1, 65535))
+                        .default_value("80")
+                        .description("Port to listen on."),
+                )
+                .key(KeySchema::new("mode", ValueType::one_of(["dev", "prod"])))
+                .key(KeySchema::new("root", ValueType::String)),
+        )
+        .write_sample(&schema_path, &WriteOptions::new(), true)?;
+
+    let uri = "file:///app.ini";
+    let text = "home = /srv\n[server]\nport = 0\nroot = %(home)s/www\nmode=\n";
+    let messages = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"capabilities": {}}}),
+        json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": {"uri": uri, "languageId": "ini", "version": 1, "text": text}}}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/documentSymbol",
+            "params": {"textDocument": {"uri": uri}}}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "textDocument/hover",
+            "params": {"textDocument": {"uri": uri}, "position": {"line": 2, "character": 1}}}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "textDocument/completion",
+            "params": {"textDocument": {"uri": uri}, "position": {"line": 4, "character": 5}}}),
+        json!({"jsonrpc": "2.0", "id": 5, "method": "textDocument/definition",
+            "params": {"textDocument": {"uri": uri}, "position": {"line": 3, "character": 10}}}),
+        json!({"jsonrpc": "2.0", "id": 6, "method": "textDocument/formatting",
+            "params": {"textDocument": {"uri": uri}, "options": {"tabSize": 4, "insertSpaces": true}}}),
+        json!({"jsonrpc": "2.0", "id": 7, "method": "no/such/method"}),
+        json!({"jsonrpc": "2.0", "id": 8, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+    ];
+    let mut server = Command::new(env!("CARGO_BIN_EXE_configparser-lsp"))
+        .args(["--schema", schema_path.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = server.stdin.take().unwrap();
+    for message in messages.iter() {
+        let body = message.to_string();
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    }
+    drop(stdin);
+    let output = server.wait_with_output()?;
+    std::fs::remove_file(&schema_path)?;
+    assert_eq!(output.status.code(), Some(0));
+
+    let mut replies: Vec<Value> = Vec::new();
+    let mut rest = std::str::from_utf8(&output.stdout)?;
+    while let Some((header, body)) = rest.split_once("\r\n\r\n") {
+        let length: usize = header.trim_start_matches("Content-Length: ").parse()?;
+        replies.push(serde_json::from_str(&body[..length])?);
+        rest = &body[length..];
+    }
+    let reply = |id: u64| {
+        replies
+            .iter()
+            .find(|reply| reply["id"] == json!(id))
+            .cloned()
+            .unwrap()
+    };
+
+    assert_eq!(
+        reply(1)["result"]["capabilities"]["hoverProvider"],
+        json!(true)
+    );
+    let diagnostics = &replies[1]["params"]["diagnostics"];
+    assert_eq!(replies[1]["method"], "textDocument/publishDiagnostics");
+    assert!(diagnostics.as_array().unwrap().iter().any(|d| {
+        d["code"] == "schema"
+            && d["range"]["start"]["line"] == 2
+            && d["message"].as_str().unwrap().contains("below the minimum")
+    }));
+    assert!(
+        diagnostics
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "empty-value")
+    );
+
+    let symbols = reply(2)["result"].clone();
+    assert_eq!(symbols[0]["name"], "home");
+    assert_eq!(symbols[1]["name"], "server");
+    assert_eq!(symbols[1]["children"].as_array().unwrap().len(), 3);
+
+    let hover = reply(3)["result"]["contents"]["value"].clone();
+    assert_eq!(
+        hover,
+        "**port**: unsigned integer (1..=65535)\n\nDefault: `80`\n\nPort to listen on."
+    );
+    let labels: Vec<Value> = reply(4)["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].clone())
+        .collect();
+    assert_eq!(labels, [json!("dev"), json!("prod")]);
+    assert_eq!(
+        reply(5)["result"],
+        json!({"uri": uri, "range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 4}}})
+    );
+    assert_eq!(
+        reply(6)["result"][0]["newText"],
+        "home=/srv\n[server]\nport=0\nroot=%(home)s/www\nmode=\n"
+    );
+    assert_eq!(reply(7)["error"]["code"], -32601);
+    assert_eq!(reply(8)["result"], Value::Null);
+    Ok(())
+}